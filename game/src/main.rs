@@ -1,48 +1,170 @@
 mod rendering;
 
-use std::{collections::HashMap, env, path::PathBuf};
+use std::{collections::HashMap, env, path::{Path, PathBuf}, process};
 
 use common_macros::hash_map;
-use game_core::{config::initialize_config_paths, constants::{INITIAL_CAMERA_VIEWPORT, SPRITE_SHEET_ANIMATED_OBJECTS, SPRITE_SHEET_AVATARS, SPRITE_SHEET_BASE_ATTACK, SPRITE_SHEET_BIOME_TILES, SPRITE_SHEET_BUILDINGS, SPRITE_SHEET_CONSTRUCTION_TILES, SPRITE_SHEET_FARM_PLANTS, SPRITE_SHEET_HUMANOIDS_1X1, SPRITE_SHEET_HUMANOIDS_1X2, SPRITE_SHEET_HUMANOIDS_2X2, SPRITE_SHEET_HUMANOIDS_2X3, SPRITE_SHEET_INVENTORY, SPRITE_SHEET_MENU, SPRITE_SHEET_STATIC_OBJECTS, TILE_SIZE}, initialize_game, is_creative_mode, is_game_running, stop_game, ui::components::Typography, update_game, update_keyboard, update_mouse, utils::vector::Vector2d, window_size_changed};
+use game_core::{config::{Config, DeathPenalties}, constants::{INITIAL_CAMERA_VIEWPORT, SPRITE_SHEET_ANIMATED_OBJECTS, SPRITE_SHEET_AVATARS, SPRITE_SHEET_BASE_ATTACK, SPRITE_SHEET_BIOME_TILES, SPRITE_SHEET_BUILDINGS, SPRITE_SHEET_CONSTRUCTION_TILES, SPRITE_SHEET_FARM_PLANTS, SPRITE_SHEET_HUMANOIDS_1X1, SPRITE_SHEET_HUMANOIDS_1X2, SPRITE_SHEET_HUMANOIDS_2X2, SPRITE_SHEET_HUMANOIDS_2X3, SPRITE_SHEET_HUD, SPRITE_SHEET_INVENTORY, SPRITE_SHEET_MENU, SPRITE_SHEET_STATIC_OBJECTS, TILE_SIZE}, game_engine::{engine::GameEngine, events::{GameEvent, StateUpdate}, migrations::migrate_data_folder, replay::replay, simulation::SimulationMode, state_updates::{EngineStateUpdate, WorldStateUpdate}}, log_debug, log_error, ui::components::Typography, worlds::world_binary::{convert_world_file, convert_worlds_folder}, utils::{logger::{LogLevel, Logger, LoggerConfig, Subsystem}, vector::Vector2d}};
 use raylib::{ffi::{KeyboardKey, MouseButton}, texture::Texture2D, RaylibHandle, RaylibThread};
 use rendering::{ui::{get_rendering_config, get_rendering_config_mut, init_rendering_config, is_rendering_config_initialized, RenderingConfig}, worlds::render_frame};
 
 fn main() {
     let mut needs_window_init = true;
     let creative_mode = env::args().any(|arg| arg == "creative");
+    let seed = env::args().find_map(|arg| arg.strip_prefix("seed=").and_then(|seed| seed.parse().ok()));
+    let simulation = match seed {
+        Some(seed) => SimulationMode::Deterministic { seed, time_step: 1.0 / 60.0 },
+        None => SimulationMode::real_time()
+    };
 
-    initialize_config_paths(
-        "en".to_owned(),
-        local_path("data"),
-        local_path("data/species.json"),
-        local_path("data/inventory.json"),
-        local_path("data/save.json"),
-        local_path("lang")
-    );
-    initialize_game(creative_mode);
+    if let Some(path) = env::args().find_map(|arg| arg.strip_prefix("replay=").map(str::to_owned)) {
+        run_replay(Path::new(&path));
+        return;
+    }
+    if env::args().any(|arg| arg == "migrate") {
+        run_migrations();
+        return;
+    }
+    if let Some(path) = env::args().find_map(|arg| arg.strip_prefix("convert=").map(str::to_owned)) {
+        run_conversion(Path::new(&path));
+        return;
+    }
+
+    let mut engine = match GameEngine::new(engine_config(), simulation) {
+        Ok(engine) => engine,
+        Err(e) => {
+            eprintln!("Failed to load game data: {}", e);
+            process::exit(1);
+        }
+    };
+    if let Some(path) = env::args().find_map(|arg| arg.strip_prefix("record=").map(str::to_owned)) {
+        if let Err(e) = engine.start_recording(Path::new(&path)) {
+            log_error!(engine.context.logger, Subsystem::Replay, "Failed to start recording at {}: {}", path, e);
+        }
+    }
+    let logger = engine.context.logger.clone();
+    engine.context.events.subscribe(move |event| log_event(&logger, event));
+    engine.set_creative_mode(creative_mode);
+    engine.start();
+
+    if let Some(path) = env::args().find_map(|arg| arg.strip_prefix("import=").map(str::to_owned)) {
+        let slot = env::args().find_map(|arg| arg.strip_prefix("slot=").and_then(|slot| slot.parse().ok())).unwrap_or(1);
+        engine.import_save_bundle(Path::new(&path), slot);
+    }
     
-    let (mut rl, thread) = start_rl();
+    let (mut rl, thread) = start_rl(&engine.context.logger);
     rl.set_window_min_size(360, 240);
         
-    while is_game_running() {
+    while engine.is_running {
         let time_since_last_update = rl.get_frame_time().min(0.1);
 
         if needs_window_init || rl.is_window_resized() {
             needs_window_init = false;
-            handle_window_size_changed(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+            handle_window_size_changed(&mut engine, rl.get_screen_width() as f32, rl.get_screen_height() as f32);
         }
         if rl.window_should_close() && !rl.is_key_pressed(raylib::consts::KeyboardKey::KEY_ESCAPE) {
-            stop_game();
+            engine.exit();
+            break
+        }
+
+        handle_keyboard_updates(&mut engine, &mut rl, time_since_last_update);
+        handle_mouse_updates(&mut engine, &mut rl, get_rendering_config().rendering_scale);
+        engine.update(time_since_last_update);
+        render_frame(&mut rl, &thread, &engine);  
+    }
+}
+
+fn engine_config() -> Config {
+    Config {
+        current_lang: "en".to_owned(),
+        levels_path: local_path("data"),
+        species_path: local_path("data/species.json"),
+        inventory_path: local_path("data/inventory.json"),
+        key_value_storage_path: local_path("data/save.json"),
+        localized_strings_path: local_path("lang"),
+        saves_path: local_path("data/saves"),
+        death_penalties: death_penalties(),
+        logging: logger_config(),
+    }
+}
+
+fn death_penalties() -> DeathPenalties {
+    let defaults = DeathPenalties::default();
+    DeathPenalties {
+        respawn_hp: env::args().find_map(|arg| arg.strip_prefix("respawn_hp=").and_then(|share| share.parse().ok())).unwrap_or(defaults.respawn_hp),
+        loses_consumables: defaults.loses_consumables || env::args().any(|arg| arg == "loses_consumables"),
+    }
+}
+
+/// Replays start from the storage and inventory in the recording, never from the save slots
+fn replay_config() -> Config {
+    Config { saves_path: PathBuf::new(), ..engine_config() }
+}
+
+fn run_replay(path: &Path) {
+    match replay(replay_config(), path) {
+        Ok(summary) => println!("{}", summary.to_json()),
+        Err(e) => {
+            eprintln!("Failed to replay {}: {}", path.display(), e);
+            process::exit(1);
         }
+    }
+}
 
-        handle_keyboard_updates(&mut rl, time_since_last_update);
-        handle_mouse_updates(&mut rl, get_rendering_config().rendering_scale);
-        update_game(time_since_last_update);
-        render_frame(&mut rl, &thread);  
+fn run_migrations() {
+    match migrate_data_folder(&local_path("data")) {
+        Ok(rewritten) => rewritten.iter().for_each(|path| println!("Upgraded {}", path.display())),
+        Err(e) => {
+            eprintln!("Failed to upgrade data files: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_conversion(path: &Path) {
+    let result = if path.is_dir() {
+        convert_worlds_folder(path)
+    } else {
+        convert_world_file(path).map(|destination| vec![destination])
+    };
+    match result {
+        Ok(written) => written.iter().for_each(|path| println!("Wrote {}", path.display())),
+        Err(e) => {
+            eprintln!("Failed to convert {}: {}", path.display(), e);
+            process::exit(1);
+        }
     }
 }
 
-fn start_rl() -> (RaylibHandle, RaylibThread) {
+fn logger_config() -> LoggerConfig {
+    let mut config = LoggerConfig { prints_to_console: true, ..Default::default() };
+
+    for arg in env::args().filter_map(|arg| arg.strip_prefix("log=").map(str::to_owned)) {
+        match arg.split_once(':') {
+            Some((subsystem, level)) => {
+                if let (Some(subsystem), Some(level)) = (Subsystem::from_name(subsystem), LogLevel::from_name(level)) {
+                    config.subsystem_levels.insert(subsystem, level);
+                }
+            }
+            None => {
+                if let Some(level) = LogLevel::from_name(&arg) {
+                    config.level = level;
+                }
+            }
+        }
+    }
+    config
+}
+
+fn log_event(logger: &Logger, event: &GameEvent) {
+    match event.update {
+        StateUpdate::World(WorldStateUpdate::CacheHeroProps(_)) => {},
+        StateUpdate::Engine(EngineStateUpdate::CenterCamera(_, _, _)) => {},
+        StateUpdate::World(update) => log_debug!(logger, Subsystem::World, "World update: {:?}", update),
+        StateUpdate::Engine(update) => log_debug!(logger, Subsystem::Engine, "Engine update: {:?}", update),
+    }
+}
+
+fn start_rl(logger: &Logger) -> (RaylibHandle, RaylibThread) {
     let width = (TILE_SIZE * INITIAL_CAMERA_VIEWPORT.w as f32) as i32;
     let height = (TILE_SIZE * INITIAL_CAMERA_VIEWPORT.h as f32) as i32;
 
@@ -57,7 +179,7 @@ fn start_rl() -> (RaylibHandle, RaylibThread) {
 
     // rl.set_target_fps(60.0);    
 
-    let textures: HashMap<u32, Texture2D> = load_textures(&mut rl, &thread);
+    let textures: HashMap<u32, Texture2D> = load_textures(&mut rl, &thread, logger);
     init_rendering_config(RenderingConfig {
         font,
         font_bold,
@@ -70,15 +192,15 @@ fn start_rl() -> (RaylibHandle, RaylibThread) {
     (rl, thread)
 }
 
-fn handle_window_size_changed(width: f32, height: f32) {
+fn handle_window_size_changed(engine: &mut GameEngine, width: f32, height: f32) {
     if !is_rendering_config_initialized() {
         return
     }
-    println!("Window size changed to {}x{}", width, height);
-    let (scale, font_scale) = rendering_scale_for_screen_width(width);
+    log_debug!(engine.context.logger, Subsystem::Engine, "Window size changed to {}x{}", width, height);
+    let (scale, font_scale) = rendering_scale_for_screen_width(engine.creative_mode, width);
     
-    println!("Updated rendering scale to {}", scale);
-    println!("Updated font scale to {}", scale);
+    log_debug!(engine.context.logger, Subsystem::Engine, "Updated rendering scale to {}", scale);
+    log_debug!(engine.context.logger, Subsystem::Engine, "Updated font scale to {}", font_scale);
     
     let config = get_rendering_config_mut();
     config.rendering_scale = scale;
@@ -88,29 +210,30 @@ fn handle_window_size_changed(width: f32, height: f32) {
 
     let font_size = config.scaled_font_size(&Typography::Regular);
     let line_spacing = config.font_lines_spacing(&Typography::Regular);
-    window_size_changed(width, height, scale, font_size, line_spacing);
+    engine.window_size_changed(width, height, scale, font_size, line_spacing);
 }
 
-fn load_textures(rl: &mut RaylibHandle, thread: &RaylibThread) -> HashMap<u32, Texture2D> {    
+fn load_textures(rl: &mut RaylibHandle, thread: &RaylibThread, logger: &Logger) -> HashMap<u32, Texture2D> {    
     let mut textures: HashMap<u32, Texture2D> = hash_map!();
-    textures.insert(SPRITE_SHEET_INVENTORY, texture(rl, thread, "inventory").unwrap());
-    textures.insert(SPRITE_SHEET_BIOME_TILES, texture(rl, thread, "tiles_biome").unwrap());
-    textures.insert(SPRITE_SHEET_CONSTRUCTION_TILES, texture(rl, thread, "tiles_constructions").unwrap());
-    textures.insert(SPRITE_SHEET_BUILDINGS, texture(rl, thread, "buildings").unwrap());
-    textures.insert(SPRITE_SHEET_BASE_ATTACK, texture(rl, thread, "baseattack").unwrap());
-    textures.insert(SPRITE_SHEET_STATIC_OBJECTS, texture(rl, thread, "static_objects").unwrap());
-    textures.insert(SPRITE_SHEET_MENU, texture(rl, thread, "menu").unwrap());        
-    textures.insert(SPRITE_SHEET_ANIMATED_OBJECTS, texture(rl, thread, "animated_objects").unwrap());     
-    textures.insert(SPRITE_SHEET_HUMANOIDS_1X1, texture(rl, thread, "humanoids_1x1").unwrap());      
-    textures.insert(SPRITE_SHEET_HUMANOIDS_1X2, texture(rl, thread, "humanoids_1x2").unwrap());
-    textures.insert(SPRITE_SHEET_HUMANOIDS_2X2, texture(rl, thread, "humanoids_2x2").unwrap());
-    textures.insert(SPRITE_SHEET_HUMANOIDS_2X3, texture(rl, thread, "humanoids_2x3").unwrap());
-    textures.insert(SPRITE_SHEET_AVATARS, texture(rl, thread, "avatars").unwrap());     
-    textures.insert(SPRITE_SHEET_FARM_PLANTS, texture(rl, thread, "farm_plants").unwrap());             
+    textures.insert(SPRITE_SHEET_INVENTORY, texture(rl, thread, logger, "inventory").unwrap());
+    textures.insert(SPRITE_SHEET_BIOME_TILES, texture(rl, thread, logger, "tiles_biome").unwrap());
+    textures.insert(SPRITE_SHEET_CONSTRUCTION_TILES, texture(rl, thread, logger, "tiles_constructions").unwrap());
+    textures.insert(SPRITE_SHEET_BUILDINGS, texture(rl, thread, logger, "buildings").unwrap());
+    textures.insert(SPRITE_SHEET_BASE_ATTACK, texture(rl, thread, logger, "baseattack").unwrap());
+    textures.insert(SPRITE_SHEET_STATIC_OBJECTS, texture(rl, thread, logger, "static_objects").unwrap());
+    textures.insert(SPRITE_SHEET_MENU, texture(rl, thread, logger, "menu").unwrap());        
+    textures.insert(SPRITE_SHEET_ANIMATED_OBJECTS, texture(rl, thread, logger, "animated_objects").unwrap());     
+    textures.insert(SPRITE_SHEET_HUMANOIDS_1X1, texture(rl, thread, logger, "humanoids_1x1").unwrap());      
+    textures.insert(SPRITE_SHEET_HUMANOIDS_1X2, texture(rl, thread, logger, "humanoids_1x2").unwrap());
+    textures.insert(SPRITE_SHEET_HUMANOIDS_2X2, texture(rl, thread, logger, "humanoids_2x2").unwrap());
+    textures.insert(SPRITE_SHEET_HUMANOIDS_2X3, texture(rl, thread, logger, "humanoids_2x3").unwrap());
+    textures.insert(SPRITE_SHEET_AVATARS, texture(rl, thread, logger, "avatars").unwrap());     
+    textures.insert(SPRITE_SHEET_FARM_PLANTS, texture(rl, thread, logger, "farm_plants").unwrap());             
+    textures.insert(SPRITE_SHEET_HUD, texture(rl, thread, logger, "hud").unwrap());
     textures
 }
 
-fn texture(rl: &mut RaylibHandle, thread: &RaylibThread, name: &str) -> Option<Texture2D> {
+fn texture(rl: &mut RaylibHandle, thread: &RaylibThread, logger: &Logger, name: &str) -> Option<Texture2D> {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("..");
     path.push("assets");
@@ -122,14 +245,14 @@ fn texture(rl: &mut RaylibHandle, thread: &RaylibThread, name: &str) -> Option<T
     match result {
         Ok(texture) => Some(texture),
         Err(err) => {
-            eprintln!("Failed to load texture at {}: {:#?}", filename, err);
+            log_error!(logger, Subsystem::Engine, "Failed to load texture at {}: {:?}", filename, err);
             None
         }
     }
 }
 
-fn handle_mouse_updates(rl: &mut RaylibHandle, rendering_scale: f32) {
-    update_mouse(
+fn handle_mouse_updates(engine: &mut GameEngine, rl: &mut RaylibHandle, rendering_scale: f32) {
+    engine.update_mouse(
         rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT), 
         rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT), 
         rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT), 
@@ -139,8 +262,8 @@ fn handle_mouse_updates(rl: &mut RaylibHandle, rendering_scale: f32) {
     );
 }
 
-fn handle_keyboard_updates(rl: &mut RaylibHandle, time_since_last_update: f32) {
-    update_keyboard(
+fn handle_keyboard_updates(engine: &mut GameEngine, rl: &mut RaylibHandle, time_since_last_update: f32) {
+    engine.update_keyboard(
         rl.is_key_pressed(KeyboardKey::KEY_W) || rl.is_key_pressed(KeyboardKey::KEY_UP), 
         rl.is_key_pressed(KeyboardKey::KEY_D) || rl.is_key_pressed(KeyboardKey::KEY_RIGHT), 
         rl.is_key_pressed(KeyboardKey::KEY_S) || rl.is_key_pressed(KeyboardKey::KEY_DOWN), 
@@ -159,17 +282,12 @@ fn handle_keyboard_updates(rl: &mut RaylibHandle, time_since_last_update: f32) {
     );
 }
 
-fn get_char_pressed(rl: &mut RaylibHandle) -> u32 {
-    let character = rl.get_char_pressed();
-    if let Some(character) = character { 
-        character as u32
-    } else {
-        0
-    }
+fn get_char_pressed(rl: &mut RaylibHandle) -> Option<char> {
+    rl.get_char_pressed()
 }
 
-fn rendering_scale_for_screen_width(width: f32) -> (f32, f32) {
-    if is_creative_mode() {
+fn rendering_scale_for_screen_width(creative_mode: bool, width: f32) -> (f32, f32) {
+    if creative_mode {
         return (1.0, 2.0)
     }
     if width < 500.0 {
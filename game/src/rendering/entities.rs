@@ -1,14 +1,15 @@
-use game_core::{constants::TILE_SIZE, renderables_vec, utils::{vector::Vector2d, rect::IntRect}, RenderableItem};
+use game_core::{constants::TILE_SIZE, game_engine::engine::GameEngine, renderables_vec, utils::{vector::Vector2d, rect::IntRect}, RenderableItem};
 use raylib::prelude::*;
 
 use super::ui::get_rendering_config;
 
 pub fn render_entities(
     d: &mut RaylibDrawHandle, 
+    engine: &GameEngine,
     camera_viewport: &IntRect, 
     camera_viewport_offset: &Vector2d
 ) {
-    for item in &renderables_vec() {
+    for item in &renderables_vec(engine) {
         render_entity(d, item, camera_viewport, camera_viewport_offset);
     }
 }
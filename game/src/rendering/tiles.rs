@@ -1,4 +1,4 @@
-use game_core::{constants::{SPRITE_SHEET_BIOME_TILES, SPRITE_SHEET_CONSTRUCTION_TILES, TILE_SIZE}, current_biome_tiles_variant, current_world_default_tile, current_world_height, current_world_width, maps::{biome_tiles::{Biome, BiomeTile}, constructions_tiles::{Construction, ConstructionTile}, tiles::SpriteTile}, utils::{rect::IntRect, vector::Vector2d}};
+use game_core::{constants::{SPRITE_SHEET_BIOME_TILES, SPRITE_SHEET_CONSTRUCTION_TILES, TILE_SIZE}, game_engine::world::World, maps::{biome_tiles::Biome, constructions_tiles::Construction, tiles::SpriteTile}, utils::{rect::IntRect, vector::Vector2d}};
 use raylib::prelude::*;
 
 use super::ui::get_rendering_config;
@@ -7,13 +7,14 @@ pub fn render_tiles(
     d: &mut RaylibDrawHandle, 
     camera_viewport: &IntRect, 
     camera_viewport_offset: &Vector2d,
-    biome_tiles: &[Vec<BiomeTile>],
-    constructions_tiles: &[Vec<ConstructionTile>]
+    world: &World
 ) {
-    let variant = current_biome_tiles_variant();
-    let world_width = current_world_width();
-    let world_height = current_world_height();
-    let default_tile = &current_world_default_tile();
+    let variant = world.biome_tiles.current_variant();
+    let world_width = world.bounds.w;
+    let world_height = world.bounds.h;
+    let default_tile = &world.default_tile();
+    let biome_tiles = &world.biome_tiles.tiles;
+    let constructions_tiles = &world.constructions_tiles.tiles;
     
     let config = get_rendering_config();
     let texture_biome = config.get_texture(SPRITE_SHEET_BIOME_TILES).unwrap();
@@ -109,6 +109,10 @@ fn calculate_position(layout: &Layout, anchor: &AnchorPoint, view: &View, config
             layout.frame.x as f32 + layout.frame.w as f32 - size.x, 
             0.0
         ),
+        AnchorPoint::BottomLeft => (
+            0.0, 
+            layout.frame.y as f32 + layout.frame.h as f32 - size.y
+        ),
         AnchorPoint::BottomCenter => (
             layout.frame.x as f32 + layout.frame.w as f32 / 2.0 - size.x / 2.0, 
             layout.frame.y as f32 + layout.frame.h as f32 - size.y
@@ -1,28 +1,26 @@
-use game_core::{camera_viewport, camera_viewport_offset, can_render_frame, engine};
+use game_core::game_engine::engine::GameEngine;
 use raylib::prelude::*;
 
 use super::{entities::render_entities, tiles::render_tiles, ui::render_layout};
 
-pub fn render_frame(rl: &mut RaylibHandle, thread: &RaylibThread) {
-    let engine = engine();
+pub fn render_frame(rl: &mut RaylibHandle, thread: &RaylibThread, engine: &GameEngine) {
     let world = &engine.world;
 
     let fps = rl.get_fps();
     let mut d = rl.begin_drawing(thread);
     d.clear_background(Color::BLACK);
     
-    if can_render_frame() {
-        let camera_viewport = camera_viewport();
-        let camera_viewport_offset = camera_viewport_offset();
+    if engine.can_render_frame() {
+        let camera_viewport = engine.camera_viewport;
+        let camera_viewport_offset = engine.camera_viewport_offset;
 
         render_tiles(
             &mut d, 
             &camera_viewport, 
             &camera_viewport_offset,
-            &world.biome_tiles.tiles,
-            &world.constructions_tiles.tiles
+            world
         );
-        render_entities(&mut d, &camera_viewport, &camera_viewport_offset);
+        render_entities(&mut d, engine, &camera_viewport, &camera_viewport_offset);
     }
 
     let hud = engine.hud_ui(d.get_screen_width(), d.get_screen_height());
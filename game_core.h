@@ -16,7 +16,11 @@
 
 #define NO_PARENT 0
 
-#define HERO_KUNAI_COOLDOWN 0.1
+#define UNASSIGNED_ENTITY_ID 0
+
+#define HERO_INVULNERABILITY_DURATION 1.0
+
+#define HERO_KNOCKBACK_DISTANCE 2
 
 #define KEYBOARD_KEY_HOLD_TIME_TO_NEXT_PRESS_FIRST 0.4
 
@@ -34,6 +38,10 @@
 
 #define MENU_OPEN_TIME 0.1
 
+#define DAMAGE_BLINK_INTERVAL 0.1
+
+#define HURT_FLASH_DURATION 0.3
+
 #define HOUSE_INTERIOR_ROWS 6
 
 #define HOUSE_INTERIOR_COLUMNS 10
@@ -78,6 +86,8 @@
 
 #define SPRITE_SHEET_HUMANOIDS_2X3 1018
 
+#define SPRITE_SHEET_HUD 1019
+
 #define SPECIES_HERO 1001
 
 #define SPECIES_HOUSE_1 1002
@@ -176,8 +186,49 @@ enum Construction {
 };
 typedef uint32_t Construction;
 
+typedef enum LogLevel {
+  LogLevel_Debug,
+  LogLevel_Info,
+  LogLevel_Warning,
+  LogLevel_Error,
+  LogLevel_Off,
+} LogLevel;
+
 typedef struct BordersTextures BordersTextures;
 
+typedef struct GameEngine GameEngine;
+
+typedef struct EngineConfig {
+  const char *current_lang;
+  const char *levels_path;
+  const char *species_path;
+  const char *inventory_path;
+  const char *key_value_storage_path;
+  const char *localized_strings_path;
+  /**
+   * Optional, null keeps a single save at `key_value_storage_path` and `inventory_path`
+   */
+  const char *saves_path;
+  /**
+   * Runs the engine in deterministic mode when greater than zero
+   */
+  float fixed_time_step;
+  uint64_t seed;
+  /**
+   * Share of the maximum hp the hero respawns with, zero or less brings it back with full hp
+   */
+  float respawn_hp;
+  /**
+   * Consumable items are lost when the hero respawns
+   */
+  bool loses_consumables;
+  /**
+   * Minimum level of the entries the engine keeps, see `configure_logging`
+   */
+  LogLevel log_level;
+  bool prints_log_to_console;
+} EngineConfig;
+
 typedef struct IntRect {
   int32_t x;
   int32_t y;
@@ -216,27 +267,163 @@ typedef struct ConstructionTile {
   struct IntRect texture_source_rect;
 } ConstructionTile;
 
+typedef uint32_t ListenerId;
 
+typedef struct CGameEvent {
+  uint32_t world_id;
+  float timestamp;
+  bool is_engine_update;
+  const char *name;
+  const char *description;
+} CGameEvent;
 
-void test_integration(void);
+typedef void (*CGameEventCallback)(const struct CGameEvent *event, void *user_data);
 
-void initialize_game(bool creative_mode);
 
-bool is_creative_mode(void);
 
-bool is_game_running(void);
-
-void stop_game(void);
+void test_integration(void);
 
-void window_size_changed(float width,
+/**
+ * # Safety
+ * All paths in `config` must be valid, nul-terminated strings.
+ * The returned handle must be released with `nokemon_engine_free`.
+ * Returns null if the game data could not be loaded, see `nokemon_last_error`.
+ */
+struct GameEngine *nokemon_engine_new(struct EngineConfig config);
+
+/**
+ * # Safety
+ * `engine` must be null or a live handle returned by `nokemon_engine_new`.
+ * Returns the latest loading error not reported yet, or null if there is none.
+ * Pass a null `engine` to read why `nokemon_engine_new` failed.
+ * The string is owned by the library and stays valid until the next call on the same thread.
+ */
+const char *nokemon_last_error(struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must come from `nokemon_engine_new` and must not be used afterwards.
+ */
+void nokemon_engine_free(struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ * Sets the minimum level for every subsystem of this engine, entries are kept in memory and printed only if `prints_to_console`.
+ */
+void configure_logging(struct GameEngine *engine, LogLevel level, bool prints_to_console);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`
+ * and `path` a valid, nul-terminated string.
+ */
+bool start_recording(struct GameEngine *engine, const char *path);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ * `callback` receives every applied state update, the event and its strings are only valid during the call.
+ */
+ListenerId add_event_listener(struct GameEngine *engine, CGameEventCallback callback, void *user_data);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void remove_event_listener(struct GameEngine *engine, ListenerId listener_id);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void initialize_game(struct GameEngine *engine, bool creative_mode);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ * Loads the given save slot, skipping the slot picker shown by `initialize_game` when `saves_path` is set.
+ * Returns false if the slot could not be loaded, see `nokemon_last_error`.
+ */
+bool select_save_slot(struct GameEngine *engine, uint32_t slot);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`
+ * and `path` a valid, nul-terminated string.
+ * Writes storage, inventory, world changes, hero position and build info of the current save to a single file.
+ * Returns false if the bundle could not be written, see `nokemon_last_error`.
+ */
+bool export_save_bundle(struct GameEngine *engine, const char *path);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`
+ * and `path` a valid, nul-terminated string.
+ * Replaces the progress of `slot` with the bundle at `path` and resumes from there,
+ * `slot` is ignored when `saves_path` is not set.
+ * Returns false if the bundle could not be imported, see `nokemon_last_error`.
+ */
+bool import_save_bundle(struct GameEngine *engine, const char *path, uint32_t slot);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ * Writes the full state of the engine and of the current world next to the current save.
+ * Returns false if the quick save could not be written, see `nokemon_last_error`.
+ */
+bool quick_save(struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ * Resumes from the last quick save of the current save, closing any open menu.
+ * Returns false if there is no quick save or it could not be loaded, see `nokemon_last_error`.
+ */
+bool quick_load(struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+bool is_creative_mode(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+bool is_game_running(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ * Saves progress and waits for pending writes before the game stops.
+ */
+void stop_game(struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void window_size_changed(struct GameEngine *engine,
+                         float width,
                          float height,
                          float scale,
                          float font_size,
                          float line_spacing);
 
-void update_game(float time_since_last_update);
-
-void update_keyboard(bool up_pressed,
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void update_game(struct GameEngine *engine, float time_since_last_update);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void update_keyboard(struct GameEngine *engine,
+                     bool up_pressed,
                      bool right_pressed,
                      bool down_pressed,
                      bool left_pressed,
@@ -252,48 +439,100 @@ void update_keyboard(bool up_pressed,
                      uint32_t current_char,
                      float time_since_last_update);
 
-void update_mouse(bool mouse_left_down,
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void update_mouse(struct GameEngine *engine,
+                  bool mouse_left_down,
                   bool mouse_left_pressed,
                   bool mouse_right_pressed,
                   float mouse_x,
                   float mouse_y,
                   float rendering_scale);
 
-struct RenderableItem *renderables(uintptr_t *length);
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+struct RenderableItem *renderables(const struct GameEngine *engine, uintptr_t *length);
 
+/**
+ * # Safety
+ * `ptr` and `length` must come from a single call to `renderables`.
+ */
 void free_renderables(struct RenderableItem *ptr, uintptr_t length);
 
-void initialize_config(const char *current_lang,
-                       const char *levels_path,
-                       const char *species_path,
-                       const char *inventory_path,
-                       const char *key_value_storage_path,
-                       const char *localized_strings_path);
-
-bool can_render_frame(void);
-
-int32_t current_biome_tiles_variant(void);
-
-int32_t current_world_width(void);
-
-int32_t current_world_height(void);
-
-struct IntRect camera_viewport(void);
-
-struct Vector2d camera_viewport_offset(void);
-
-struct BiomeTile current_world_default_tile(void);
-
-void get_biome_tiles(const struct BiomeTile **out_tiles,
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+bool can_render_frame(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+int32_t current_biome_tiles_variant(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+int32_t current_world_width(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+int32_t current_world_height(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+struct IntRect camera_viewport(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+struct Vector2d camera_viewport_offset(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+struct BiomeTile current_world_default_tile(const struct GameEngine *engine);
+
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void get_biome_tiles(const struct GameEngine *engine,
+                     const struct BiomeTile **out_tiles,
                      uintptr_t *out_len_x,
                      uintptr_t *out_len_y);
 
+/**
+ * # Safety
+ * `tiles_ptr`, `len_x` and `len_y` must come from a single call to `get_biome_tiles`.
+ */
 void free_biome_tiles(struct BiomeTile *tiles_ptr, uintptr_t len_x, uintptr_t len_y);
 
-void get_construction_tiles(const struct ConstructionTile **out_tiles,
+/**
+ * # Safety
+ * `engine` must be a live handle returned by `nokemon_engine_new`.
+ */
+void get_construction_tiles(const struct GameEngine *engine,
+                            const struct ConstructionTile **out_tiles,
                             uintptr_t *out_len_x,
                             uintptr_t *out_len_y);
 
+/**
+ * # Safety
+ * `tiles_ptr`, `len_x` and `len_y` must come from a single call to `get_construction_tiles`.
+ */
 void free_construction_tiles(struct ConstructionTile *tiles_ptr, uintptr_t len_x, uintptr_t len_y);
 
 #endif  /* GAME_CORE_H */
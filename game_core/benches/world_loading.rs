@@ -0,0 +1,26 @@
+use std::{env, fs, path::PathBuf, process};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use game_core::worlds::{world_binary::{binary_world_path, convert_world_file}, world_serde::read_world_data};
+
+/// Same world through the regular loading path, from json alone and with an up to date binary version next to it.
+/// Both build the same tile sets afterwards, so only the parsing differs.
+fn world_loading(c: &mut Criterion) {
+    let folder = env::temp_dir().join(format!("nokemon-bench-{}", process::id()));
+    fs::create_dir_all(&folder).unwrap();
+    let json_path = folder.join("1001.json");
+    fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("data").join("1001.json"), &json_path).unwrap();
+
+    let mut group = c.benchmark_group("world_loading");
+    group.bench_function("json", |b| b.iter(|| read_world_data(&json_path).unwrap()));
+
+    convert_world_file(&json_path).unwrap();
+    assert!(binary_world_path(&json_path).exists());
+    group.bench_function("binary", |b| b.iter(|| read_world_data(&json_path).unwrap()));
+    group.finish();
+
+    fs::remove_dir_all(folder).unwrap();
+}
+
+criterion_group!(benches, world_loading);
+criterion_main!(benches);
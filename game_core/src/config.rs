@@ -1,5 +1,8 @@
 use std::path::PathBuf;
 
+use crate::utils::logger::LoggerConfig;
+
+#[derive(Debug, Clone)]
 pub struct Config {
     pub current_lang: String,
     pub levels_path: PathBuf,
@@ -7,34 +10,95 @@ pub struct Config {
     pub inventory_path: PathBuf,
     pub key_value_storage_path: PathBuf,
     pub localized_strings_path: PathBuf,
+    /// Folder with one subfolder per save slot, leave empty to keep a single save
+    /// at `key_value_storage_path` and `inventory_path`, which otherwise become slot 1 the first time slots are used
+    pub saves_path: PathBuf,
+    pub death_penalties: DeathPenalties,
+    pub logging: LoggerConfig,
+}
+
+/// What dying costs, applied whenever the hero respawns from the death screen.
+#[derive(Debug, Clone, Copy)]
+pub struct DeathPenalties {
+    /// Share of the maximum hp the hero comes back with, never less than one
+    pub respawn_hp: f32,
+    /// Consumable items are lost
+    pub loses_consumables: bool,
 }
 
-static mut CONFIG: *mut Config = std::ptr::null_mut();
+impl Default for DeathPenalties {
+    fn default() -> Self {
+        Self {
+            respawn_hp: 1.0,
+            loses_consumables: false,
+        }
+    }
+}
+
+impl Config {
+    /// Config for engines that never read or write files, see `GameContext::in_memory`
+    pub fn in_memory() -> Self {
+        Self {
+            current_lang: "en".to_owned(),
+            levels_path: PathBuf::new(),
+            species_path: PathBuf::new(),
+            inventory_path: PathBuf::new(),
+            key_value_storage_path: PathBuf::new(),
+            localized_strings_path: PathBuf::new(),
+            saves_path: PathBuf::new(),
+            death_penalties: DeathPenalties::default(),
+            logging: LoggerConfig::default(),
+        }
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.levels_path.as_os_str().is_empty()
+    }
+
+    pub fn uses_save_slots(&self) -> bool {
+        !self.saves_path.as_os_str().is_empty()
+    }
 
-pub fn config() -> &'static Config {
-    unsafe {
-        &*CONFIG
+    /// Changes made to worlds while playing without save slots, next to the single save
+    pub fn overlays_path(&self) -> PathBuf {
+        self.key_value_storage_path.with_file_name("overlays")
+    }
+
+    /// Quick save of the single save, slots keep their own
+    pub fn quick_save_path(&self) -> PathBuf {
+        self.key_value_storage_path.with_file_name("quicksave.json")
+    }
+
+    /// Save bundles exported from the game menu
+    pub fn bundles_path(&self) -> PathBuf {
+        if self.uses_save_slots() {
+            self.saves_path.join("bundles")
+        } else {
+            self.key_value_storage_path.with_file_name("bundles")
+        }
+    }
+
+    pub fn world_path(&self, id: u32) -> PathBuf {
+        self.levels_path.join(format!("{}.json", id))
     }
 }
 
-pub fn initialize_config_paths(
-    current_lang: String,
-    levels_path: PathBuf,
-    species_path: PathBuf,
-    inventory_path: PathBuf,
-    key_value_storage_path: PathBuf,
-    localized_strings_path: PathBuf,
-) {
-    unsafe {
-        let config = Config {
-            current_lang,
-            levels_path,
-            species_path,
-            inventory_path,
-            key_value_storage_path,
-            localized_strings_path
-        };
-        let boxed = Box::new(config);
-        CONFIG = Box::into_raw(boxed);      
+#[cfg(test)]
+impl Config {
+    pub fn for_tests() -> Self {
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("data");
+        let lang = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("lang");
+
+        Self {
+            current_lang: "en".to_owned(),
+            levels_path: data.clone(),
+            species_path: data.join("species.json"),
+            inventory_path: data.join("inventory.json"),
+            key_value_storage_path: data.join("save.json"),
+            localized_strings_path: lang,
+            saves_path: PathBuf::new(),
+            death_penalties: DeathPenalties::default(),
+            logging: LoggerConfig::default(),
+        }
     }
 }
@@ -9,7 +9,16 @@ pub const WORLD_SIZE_ROWS: usize = 80;
 pub const WORLD_SIZE_COLUMNS: usize = 120;
 pub const UNLIMITED_LIFESPAN: f32 = -420.0;
 pub const NO_PARENT: u32 = 0;
-pub const HERO_KUNAI_COOLDOWN: f32 = 0.1;
+pub const UNASSIGNED_ENTITY_ID: u32 = 0;
+pub const HERO_INVULNERABILITY_DURATION: f32 = 1.0;
+pub const HERO_KNOCKBACK_DISTANCE: i32 = 2;
+pub const ACTIVE_REGION_MARGIN: i32 = 20;
+pub const OFF_SCREEN_UPDATE_INTERVAL: f32 = 0.1;
+pub const WORLD_CACHE_CAPACITY: usize = 8;
+pub const WORLD_PRELOAD_DISTANCE: i32 = 8;
+pub const SAVE_SLOTS_COUNT: u32 = 3;
+pub const SAVE_SLOT_PREVIEW_SIZE: i32 = 9;
+pub const SAVE_BACKUPS_COUNT: usize = 3;
 
 // Input
 pub const KEYBOARD_KEY_HOLD_TIME_TO_NEXT_PRESS_FIRST: f32 = 0.4;
@@ -26,6 +35,8 @@ pub const WORLD_ID_DEMO: u32 = 1001;
 pub const WORLD_TRANSITION_TIME: f32 = 0.3;
 pub const MENU_CLOSE_TIME: f32 = 0.2;
 pub const MENU_OPEN_TIME: f32 = 0.1;
+pub const DAMAGE_BLINK_INTERVAL: f32 = 0.1;
+pub const HURT_FLASH_DURATION: f32 = 0.3;
 
 // Prefabs
 pub const HOUSE_INTERIOR_ROWS: usize = 6;
@@ -53,4 +64,5 @@ pub const SPRITE_SHEET_HUMANOIDS_1X1: u32 = 1014;
 pub const SPRITE_SHEET_AVATARS: u32 = 1015;
 pub const SPRITE_SHEET_HUMANOIDS_2X2: u32 = 1016;
 pub const SPRITE_SHEET_FARM_PLANTS: u32 = 1017;
-pub const SPRITE_SHEET_HUMANOIDS_2X3: u32 = 1018;
\ No newline at end of file
+pub const SPRITE_SHEET_HUMANOIDS_2X3: u32 = 1018;
+pub const SPRITE_SHEET_HUD: u32 = 1019;
\ No newline at end of file
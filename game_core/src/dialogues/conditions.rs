@@ -0,0 +1,361 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{entities::species::SpeciesId, game_engine::{inventory::InventoryStorage, storage::{KeyValueStorage, StorageScope, StorageValue}}};
+
+/// Expression over stored values and the inventory, written as a string in level files, for example
+/// `quest.stage >= 2 and not has 7000` or `(entity:talked or world:gate_open) && name != "bob"`.
+///
+/// Operands are keys, numbers, `"strings"`, `true` and `false`, missing keys read as 0.
+/// Supports `==`, `!=`, `>`, `>=`, `<`, `<=`, `contains` (lists and strings),
+/// `and`/`&&`, `or`/`||`, `not`/`!`, parentheses and `has <species id>`.
+/// A key on its own is true when its value is not 0, false or empty.
+/// Keys starting with `world:` or `entity:` are scoped, see `StorageScope`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    source: String,
+    expression: Expression,
+}
+
+/// What conditions can look at.
+pub struct ConditionContext<'a> {
+    pub storage: &'a KeyValueStorage,
+    pub inventory: &'a InventoryStorage,
+    pub scope: StorageScope,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Operand, Operator, Operand),
+    IsTruthy(Operand),
+    HasItem(SpeciesId),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Key(String),
+    Value(StorageValue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(i64),
+    Text(String),
+    Symbol(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionError(String);
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Self, ConditionError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let expression = parser.parse_or()?;
+
+        if let Some(token) = parser.peek() {
+            return Err(ConditionError(format!("Unexpected {:?} in `{}`", token, source)))
+        }
+        Ok(Self { source: source.to_owned(), expression })
+    }
+
+    pub fn is_met(&self, context: &ConditionContext) -> bool {
+        self.expression.evaluate(context)
+    }
+}
+
+impl Expression {
+    fn evaluate(&self, context: &ConditionContext) -> bool {
+        match self {
+            Expression::And(lhs, rhs) => lhs.evaluate(context) && rhs.evaluate(context),
+            Expression::Or(lhs, rhs) => lhs.evaluate(context) || rhs.evaluate(context),
+            Expression::Not(expression) => !expression.evaluate(context),
+            Expression::Compare(lhs, operator, rhs) => operator.compare(&lhs.value(context), &rhs.value(context)),
+            Expression::IsTruthy(operand) => operand.value(context).is_truthy(),
+            Expression::HasItem(species_id) => context.inventory.contains_species(*species_id),
+        }
+    }
+}
+
+impl Operand {
+    fn value(&self, context: &ConditionContext) -> StorageValue {
+        match self {
+            Operand::Key(key) => context.storage.get(&context.scope.resolve(key)).unwrap_or(StorageValue::Int(0)),
+            Operand::Value(value) => value.clone()
+        }
+    }
+}
+
+impl Operator {
+    fn compare(&self, lhs: &StorageValue, rhs: &StorageValue) -> bool {
+        match self {
+            Operator::Equal => are_equal(lhs, rhs),
+            Operator::NotEqual => !are_equal(lhs, rhs),
+            Operator::Contains => match (lhs, rhs) {
+                (StorageValue::List(values), value) => values.iter().any(|item| are_equal(item, value)),
+                (StorageValue::Text(text), StorageValue::Text(part)) => text.contains(part.as_str()),
+                _ => false
+            },
+            _ => {
+                let (Some(lhs), Some(rhs)) = (lhs.as_int(), rhs.as_int()) else { return false };
+                match self {
+                    Operator::Greater => lhs > rhs,
+                    Operator::GreaterOrEqual => lhs >= rhs,
+                    Operator::Less => lhs < rhs,
+                    _ => lhs <= rhs
+                }
+            }
+        }
+    }
+}
+
+/// Bools and numbers compare by value, so that `flag == 1` works for flags stored either way.
+fn are_equal(lhs: &StorageValue, rhs: &StorageValue) -> bool {
+    match (lhs.as_int(), rhs.as_int()) {
+        (Some(lhs), Some(rhs)) => lhs == rhs,
+        _ => lhs == rhs
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_is(&mut self, options: &[&str]) -> bool {
+        let matches = match self.peek() {
+            Some(Token::Symbol(symbol)) => options.contains(symbol),
+            Some(Token::Word(word)) => options.contains(&word.as_str()),
+            _ => false
+        };
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, ConditionError> {
+        let mut expression = self.parse_and()?;
+        while self.next_is(&["or", "||"]) {
+            expression = Expression::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, ConditionError> {
+        let mut expression = self.parse_not()?;
+        while self.next_is(&["and", "&&"]) {
+            expression = Expression::And(Box::new(expression), Box::new(self.parse_not()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<Expression, ConditionError> {
+        if self.next_is(&["not", "!"]) {
+            return Ok(Expression::Not(Box::new(self.parse_not()?)))
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ConditionError> {
+        if self.next_is(&["("]) {
+            let expression = self.parse_or()?;
+            if !self.next_is(&[")"]) {
+                return Err(ConditionError("Missing `)`".to_owned()))
+            }
+            return Ok(expression)
+        }
+        if self.next_is(&["has"]) {
+            return match self.next() {
+                Some(Token::Number(species_id)) if species_id >= 0 => Ok(Expression::HasItem(species_id as SpeciesId)),
+                token => Err(ConditionError(format!("Expected a species id after `has`, found {:?}", token)))
+            }
+        }
+        let lhs = self.parse_operand()?;
+        let operator = match self.peek() {
+            Some(Token::Symbol("==")) => Operator::Equal,
+            Some(Token::Symbol("!=")) => Operator::NotEqual,
+            Some(Token::Symbol(">")) => Operator::Greater,
+            Some(Token::Symbol(">=")) => Operator::GreaterOrEqual,
+            Some(Token::Symbol("<")) => Operator::Less,
+            Some(Token::Symbol("<=")) => Operator::LessOrEqual,
+            Some(Token::Word(word)) if word == "contains" => Operator::Contains,
+            _ => return Ok(Expression::IsTruthy(lhs))
+        };
+        self.position += 1;
+        Ok(Expression::Compare(lhs, operator, self.parse_operand()?))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, ConditionError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Operand::Value(StorageValue::Int(value))),
+            Some(Token::Text(value)) => Ok(Operand::Value(StorageValue::Text(value))),
+            Some(Token::Word(word)) if word == "true" => Ok(Operand::Value(StorageValue::Bool(true))),
+            Some(Token::Word(word)) if word == "false" => Ok(Operand::Value(StorageValue::Bool(false))),
+            Some(Token::Word(word)) if !is_keyword(&word) => Ok(Operand::Key(word)),
+            token => Err(ConditionError(format!("Expected a key or a value, found {:?}", token)))
+        }
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    matches!(word, "and" | "or" | "not" | "has" | "contains")
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ConditionError> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            tokens.push(Token::Text(take_text(&mut chars)?));
+        } else if c.is_ascii_digit() || c == '-' {
+            let number = take_while(&mut chars, |c| c.is_ascii_digit() || c == '-');
+            let value = number.parse().map_err(|_| ConditionError(format!("Invalid number `{}`", number)))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphanumeric() || c == '_' {
+            tokens.push(Token::Word(take_while(&mut chars, |c| c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '-'))));
+        } else {
+            chars.next();
+            let symbol = match (c, chars.peek()) {
+                ('=', Some('=')) => "==",
+                ('!', Some('=')) => "!=",
+                ('>', Some('=')) => ">=",
+                ('<', Some('=')) => "<=",
+                ('&', Some('&')) => "&&",
+                ('|', Some('|')) => "||",
+                ('>', _) => ">",
+                ('<', _) => "<",
+                ('!', _) => "!",
+                ('(', _) => "(",
+                (')', _) => ")",
+                _ => return Err(ConditionError(format!("Unexpected `{}` in `{}`", c, source)))
+            };
+            if symbol.len() == 2 {
+                chars.next();
+            }
+            tokens.push(Token::Symbol(symbol));
+        }
+    }
+    Ok(tokens)
+}
+
+fn take_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+    let mut taken = String::new();
+    while let Some(&c) = chars.peek() {
+        if !predicate(c) {
+            break
+        }
+        taken.push(c);
+        chars.next();
+    }
+    taken
+}
+
+fn take_text(chars: &mut Peekable<Chars>) -> Result<String, ConditionError> {
+    let mut text = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(text)
+        }
+        text.push(c);
+    }
+    Err(ConditionError(format!("Missing closing quote after `{}`", text)))
+}
+
+impl TryFrom<String> for Condition {
+    type Error = ConditionError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Condition::parse(&source)
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> Self {
+        condition.source
+    }
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid condition: {}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{entities::known_species::SPECIES_KUNAI, game_engine::{context::GameContext, inventory::InventoryStorage, storage::{KeyValueStorage, StorageKey, StorageScope, StorageValue}}};
+
+    use super::{Condition, ConditionContext};
+
+    fn is_met(source: &str, storage: &KeyValueStorage, inventory: &InventoryStorage) -> bool {
+        let context = ConditionContext { storage, inventory, scope: StorageScope::new(1001, 42) };
+        Condition::parse(source).unwrap().is_met(&context)
+    }
+
+    #[test]
+    fn evaluates_comparisons_logic_and_items() {
+        let storage = KeyValueStorage::from_values(BTreeMap::from([
+            ("quest.stage".to_owned(), StorageValue::Int(2)),
+            ("met_wizard".to_owned(), StorageValue::Bool(true)),
+            ("name".to_owned(), StorageValue::from("bob")),
+            ("visited".to_owned(), StorageValue::List(vec![StorageValue::Int(1001), StorageValue::Int(1002)])),
+            (StorageKey::in_world(1001, "gate_open"), StorageValue::Bool(true)),
+            (StorageKey::of_entity(42, "talked"), StorageValue::Int(1)),
+        ]));
+        let kunai = GameContext::for_tests().make_entity(SPECIES_KUNAI);
+        let inventory = InventoryStorage::from_items(vec![kunai]);
+
+        assert!(is_met("quest.stage >= 2 && met_wizard", &storage, &inventory));
+        assert!(is_met("quest.stage != 3 and not (name == \"alice\")", &storage, &inventory));
+        assert!(is_met("missing.key == 0 and !missing.key", &storage, &inventory));
+        assert!(is_met("visited contains 1002 or false", &storage, &inventory));
+        assert!(is_met("world:gate_open and entity:talked == true", &storage, &inventory));
+        assert!(is_met(&format!("has {}", SPECIES_KUNAI), &storage, &inventory));
+        assert!(!is_met("has 1 || quest.stage < 2", &storage, &inventory));
+        assert!(is_met("always", &storage, &inventory));
+    }
+
+    #[test]
+    fn rejects_malformed_conditions() {
+        for source in ["", "quest.stage >=", "(a and b", "has item", "a == \"b", "a = 1", "and"] {
+            assert!(Condition::parse(source).is_err(), "`{}` should not parse", source);
+        }
+        let condition: Condition = serde_json::from_str("\"a >= 1\"").unwrap();
+        assert_eq!(serde_json::to_string(&condition).unwrap(), "\"a >= 1\"");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{constants::SPRITE_SHEET_INVENTORY, dialogues::storage::{has_dialogue_reward_been_collected, set_dialogue_reward_collected}, entities::species::species_by_id, game_engine::{keyboard_events_provider::KeyboardEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::LocalizableText, menus::{menu::{Menu, MenuItem}, toasts::{Toast, ToastImage}}, ui::components::View, utils::animator::Animator};
+use crate::{constants::SPRITE_SHEET_INVENTORY, dialogues::storage::{has_dialogue_reward_been_collected, set_dialogue_reward_collected}, game_engine::{context::GameContext, keyboard_events_provider::KeyboardEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::{LocalizableText, LocalizedStrings}, menus::{menu::{Menu, MenuItem}, toasts::{Toast, ToastImage}}, ui::components::View, utils::animator::Animator};
 
 use super::{models::Dialogue, storage::set_dialogue_read};
 
@@ -18,13 +18,19 @@ enum DialogueAnswerItem {
 }
 
 impl MenuItem for DialogueAnswerItem {
-    fn title(&self) -> String {
+    fn title(&self, _: &LocalizedStrings) -> String {
         match self {
             DialogueAnswerItem::Value(text) => text.clone()
         }
     }
 }
 
+impl Default for DialogueMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DialogueMenu {
     pub fn new() -> Self {
         let mut options_menu = Menu::empty();
@@ -41,24 +47,24 @@ impl DialogueMenu {
         }
     }
 
-    pub fn show(&mut self, npc_id: u32, npc_name: &str, dialogue: &Dialogue) {
+    pub fn show(&mut self, npc_id: u32, npc_name: &str, dialogue: &Dialogue, strings: &LocalizedStrings) {
         if self.time_since_last_closed >= 0.5 {
-            self.show_now(npc_id, npc_name, dialogue, false);
+            self.show_now(npc_id, npc_name, dialogue, strings, false);
         }
     }
 
-    fn show_now(&mut self, npc_id: u32, npc_name: &str, dialogue: &Dialogue, skip_animation: bool) {
+    fn show_now(&mut self, npc_id: u32, npc_name: &str, dialogue: &Dialogue, strings: &LocalizedStrings, skip_animation: bool) {
         self.npc_id = npc_id;
         self.npc_name = npc_name.to_string();
         self.dialogue = dialogue.clone();       
         
         self.menu.title = format!("{: <45}", format!("{}:", self.npc_name));
-        self.text = self.dialogue.localized_text();
+        self.text = self.dialogue.localized_text(strings);
 
         self.text_animator.animate(0.0, 1.0, self.text.len() as f32 / 80.0);
         self.time_since_last_closed = 0.0;
         
-        self.menu.items = vec![DialogueAnswerItem::Value("ok".localized())];
+        self.menu.items = vec![DialogueAnswerItem::Value("ok".localized(strings))];
 
         if skip_animation {
             self.menu.show_no_animation();
@@ -70,6 +76,7 @@ impl DialogueMenu {
     pub fn update(
         &mut self,
         keyboard: &KeyboardEventsProvider,
+        context: &GameContext,
         time_since_last_update: f32,
     ) -> (bool, Vec<WorldStateUpdate>) {
         self.text_animator.update(time_since_last_update);
@@ -89,7 +96,7 @@ impl DialogueMenu {
                 self.menu.is_open = true;
                 self.menu.selection_has_been_confirmed = false;
             } else {
-                let updates = self.handle_answer();
+                let updates = self.handle_answer(context);
                 self.dialogue = Dialogue::empty();
                 self.menu.close();
                 return (self.menu.is_open, updates)
@@ -99,22 +106,27 @@ impl DialogueMenu {
         (self.menu.is_open, vec![])
     }
 
-    fn handle_answer(&mut self) -> Vec<WorldStateUpdate> {
+    fn handle_answer(&mut self, context: &GameContext) -> Vec<WorldStateUpdate> {
+        let storage = &context.storage;
         let dialogue_id = self.dialogue.text.as_str();
-        set_dialogue_read(dialogue_id);       
+        set_dialogue_read(storage, dialogue_id);       
         self.menu.clear_selection();
 
+        let mut updates: Vec<WorldStateUpdate> = self.dialogue.sets.iter()
+            .map(|(key, value)| WorldStateUpdate::SetStorageValue(self.npc_id, key.clone(), value.clone()))
+            .collect();
+
         if let Some(reward) = self.dialogue.reward {
-            if !has_dialogue_reward_been_collected(dialogue_id) {
-                set_dialogue_reward_collected(dialogue_id);
-                let species = species_by_id(reward);
-                let reward_entity = Box::new(species.make_entity());
+            if !has_dialogue_reward_been_collected(storage, dialogue_id) {
+                set_dialogue_reward_collected(storage, dialogue_id);
+                let species = context.species.get(reward);
+                let reward_entity = Box::new(species.make_entity(&context.strings));
                 
-                vec! [
+                updates.extend(vec![
                     WorldStateUpdate::EngineUpdate(
                         EngineStateUpdate::Toast(
                             Toast::regular_with_image(
-                                self.dialogue.localized_reward_text(),
+                                self.dialogue.localized_reward_text(&context.species, &context.strings),
                                 ToastImage::static_image(
                                     species.inventory_sprite_frame(), 
                                     SPRITE_SHEET_INVENTORY
@@ -124,20 +136,21 @@ impl DialogueMenu {
                     ),
                     WorldStateUpdate::EngineUpdate(EngineStateUpdate::AddToInventory(reward_entity)),
                     WorldStateUpdate::EngineUpdate(EngineStateUpdate::SaveGame)
-                ]
-            } else {
-                vec![]
+                ]);
             }
-        } else {
-            vec![]
         }
+        updates
     }
 
     pub fn is_open(&self) -> bool {
         self.menu.is_open
     }
 
-    pub fn ui(&self) -> View {
-        self.menu.ui()
+    pub fn options(&self, strings: &LocalizedStrings) -> Vec<String> {
+        self.menu.item_titles(strings)
+    }
+
+    pub fn ui(&self, strings: &LocalizedStrings) -> View {
+        self.menu.ui(strings)
     }    
 }
@@ -1,3 +1,4 @@
+pub mod conditions;
 pub mod menu;
 pub mod models;
 pub mod storage;
\ No newline at end of file
@@ -1,28 +1,53 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{entities::species::{species_by_id, SpeciesId}, lang::localizable::LocalizableText};
+use crate::{entities::species::{SpeciesId, SpeciesRegistry}, game_engine::storage::StorageValue, lang::localizable::{LocalizableText, LocalizedStrings}};
+
+use super::conditions::{Condition, ConditionContext};
 
 pub type EntityDialogues = Vec<Dialogue>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dialogue {
+    #[serde(default)]
     pub key: String,
+
+    #[serde(default)]
     pub expected_value: u32,
+
+    /// Takes the place of `key` and `expected_value` when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+
     pub text: String,
     
     #[serde(default)]
-    pub reward: Option<SpeciesId>
+    pub reward: Option<SpeciesId>,
+
+    /// Written every time the dialogue is read, keys can use `world:` and `entity:` like conditions
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sets: BTreeMap<String, StorageValue>
 }
 
 impl Dialogue {
-    pub fn localized_text(&self) -> String {
-        self.text.localized()
+    /// Without a condition, `key` must be equal to `expected_value`, missing keys count as 0.
+    pub fn is_available(&self, context: &ConditionContext) -> bool {
+        if let Some(condition) = &self.condition {
+            return condition.is_met(context)
+        }
+        let value = context.storage.get_value_for_key(&context.scope.resolve(&self.key));
+        value == Some(self.expected_value) || (self.expected_value == 0 && value.is_none())
+    }
+
+    pub fn localized_text(&self, strings: &LocalizedStrings) -> String {
+        self.text.localized(strings)
     }
 
-    pub fn localized_reward_text(&self) -> String {
+    pub fn localized_reward_text(&self, species: &SpeciesRegistry, strings: &LocalizedStrings) -> String {
         if let Some(reward_species_id) = self.reward {
-            let species_name = species_by_id(reward_species_id).localized_name();
-            let text = "dialogue.reward_received".localized();
+            let species_name = species.get(reward_species_id).localized_name(strings);
+            let text = "dialogue.reward_received".localized(strings);
             text.replace("%s", &species_name)
         } else {
             "".to_owned()
@@ -35,8 +60,10 @@ impl Dialogue {
         Dialogue {
             key: "always".to_owned(),
             expected_value: 0,
-            text: "empty_dialogue".localized(),
-            reward: None
+            condition: None,
+            text: "empty_dialogue".to_owned(),
+            reward: None,
+            sets: BTreeMap::new()
         }
     }
 }
\ No newline at end of file
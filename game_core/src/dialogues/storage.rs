@@ -1,4 +1,4 @@
-use crate::game_engine::storage::{get_value_for_key, set_value_for_key, StorageKey};
+use crate::game_engine::storage::{KeyValueStorage, StorageKey};
 
 impl StorageKey {
     fn dialogue_answer(dialogue: &str) -> String {
@@ -10,16 +10,16 @@ impl StorageKey {
     }
 }
 
-pub fn set_dialogue_read(dialogue: &str) {
-    set_value_for_key(&StorageKey::dialogue_answer(dialogue), 1);
+pub fn set_dialogue_read(storage: &KeyValueStorage, dialogue: &str) {
+    storage.set_value_for_key(&StorageKey::dialogue_answer(dialogue), 1);
 }
 
-pub fn set_dialogue_reward_collected(dialogue: &str) {
-    set_value_for_key(&StorageKey::dialogue_reward_collected(dialogue), 1);    
+pub fn set_dialogue_reward_collected(storage: &KeyValueStorage, dialogue: &str) {
+    storage.set_value_for_key(&StorageKey::dialogue_reward_collected(dialogue), 1);    
 }
 
-pub fn has_dialogue_reward_been_collected(dialogue: &str) -> bool {
-    if let Some(collected) = get_value_for_key(&StorageKey::dialogue_reward_collected(dialogue)) {
+pub fn has_dialogue_reward_been_collected(storage: &KeyValueStorage, dialogue: &str) -> bool {
+    if let Some(collected) = storage.get_value_for_key(&StorageKey::dialogue_reward_collected(dialogue)) {
         collected == 1
     } else {
         false
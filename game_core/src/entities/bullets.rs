@@ -1,4 +1,4 @@
-use crate::{game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World}, utils::directions::Direction};
+use crate::{constants::HERO_ENTITY_ID, game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World}, utils::directions::Direction};
 
 impl Entity {
     pub fn setup_bullet(&mut self) {
@@ -27,9 +27,16 @@ impl Entity {
     }
 
     fn check_hits(&self, world: &World) -> Vec<WorldStateUpdate> {
+        if self.parent_id != HERO_ENTITY_ID {
+            return if world.is_hero_at(self.frame.x, self.frame.y) {
+                vec![WorldStateUpdate::HandleHit(self.id, HERO_ENTITY_ID)]
+            } else {
+                vec![]
+            }
+        }
         let hit = world.entities_map[self.frame.y as usize][self.frame.x as usize];
 
-        if hit == 0 || hit == self.id || hit == self.parent_id { 
+        if hit == 0 || hit == self.id || hit == self.parent_id || hit == self.last_hit_id { 
             vec![] 
         } else {
             vec![WorldStateUpdate::HandleHit(self.id, hit)]
@@ -1,8 +1,8 @@
-use crate::game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World};
+use crate::game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::KeyValueStorage, world::World};
 
 impl Entity {
-    pub fn setup_gate(&mut self, creative_mode: bool) {
-        if self.is_related_pressure_plate_down() {
+    pub fn setup_gate(&mut self, storage: &KeyValueStorage, creative_mode: bool) {
+        if self.is_related_pressure_plate_down(storage) {
             self.sprite.frame.x += 1;
         }
         if creative_mode {
@@ -21,7 +21,7 @@ impl Entity {
             ];   
         }
 
-        if self.is_related_pressure_plate_down() {
+        if self.is_related_pressure_plate_down(&world.context.storage) {
             self.is_rigid = !world.creative_mode;
             self.sprite.frame.x = self.original_sprite_frame.x;
         } else {
@@ -34,8 +34,8 @@ impl Entity {
 }
 
 impl Entity {
-    pub fn setup_inverse_gate(&mut self) {
-        if !self.is_related_pressure_plate_down() {
+    pub fn setup_inverse_gate(&mut self, storage: &KeyValueStorage) {
+        if !self.is_related_pressure_plate_down(storage) {
             self.sprite.frame.x += 1;
         }
     }  
@@ -51,7 +51,7 @@ impl Entity {
             ];   
         }
 
-        if !self.is_related_pressure_plate_down() {
+        if !self.is_related_pressure_plate_down(&world.context.storage) {
             self.is_rigid = true;
             self.sprite.frame.x = self.original_sprite_frame.x;
         } else {
@@ -1,8 +1,13 @@
-use crate::{constants::HERO_KUNAI_COOLDOWN, entities::{known_species::SPECIES_KUNAI, species::species_by_id}, game_engine::{entity::{Entity, EntityProps}, inventory::{inventory_contains_species, remove_one_of_species_from_inventory}, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World}, utils::rect::IntRect};
+use crate::{game_engine::{entity::{Entity, EntityProps}, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::{KeyValueStorage, StorageKey}, world::World}, utils::rect::IntRect};
 
 impl Entity {
-    pub fn setup_hero(&mut self, creative_mode: bool) {
+    pub fn setup_hero(&mut self, storage: &KeyValueStorage, creative_mode: bool) {
         self.speed_multiplier = if creative_mode { 2.0 } else { 1.0 };
+
+        let stored_hp = storage.get(&StorageKey::hero_hp()).and_then(|value| value.as_int());
+        if let Some(hp) = stored_hp.filter(|hp| *hp > 0) {
+            self.hp = (hp as u32).min(self.hp);
+        }
     }
 
     pub fn update_hero(&mut self, world: &World, time_since_last_update: f32) -> Vec<WorldStateUpdate> {        
@@ -22,7 +27,7 @@ impl Entity {
         
         world_updates.push(self.cache_props());
         world_updates.push(self.move_camera_update());
-        world_updates.append(&mut self.shoot_kunai(world, time_since_last_update));
+        world_updates.append(&mut self.attack(world, time_since_last_update));
         world_updates
     }
 
@@ -38,7 +43,8 @@ impl Entity {
             direction: self.direction,
             offset: self.offset,
             speed: self.current_speed,
-            is_invulnerable: self.is_invulnerable,
+            is_invulnerable: self.is_invulnerable || self.time_invulnerable > 0.0,
+            hp: self.hp,
             hittable_frame: IntRect {
                 x: self.frame.x + (self.sprite.frame.w - 1) / 2,
                 y: self.frame.y + (self.sprite.frame.h - 1),
@@ -57,32 +63,4 @@ impl Entity {
             )
         )
     }
-    
-    fn shoot_kunai(&mut self, world: &World, time_since_last_update: f32) -> Vec<WorldStateUpdate> {
-        self.shooting_cooldown_remaining -= time_since_last_update;
-        
-        if self.shooting_cooldown_remaining > 0.0 {
-            return vec![]
-        }
-        if !world.has_attack_key_been_pressed {
-            return vec![]
-        }
-        if !inventory_contains_species(SPECIES_KUNAI) {
-            return vec![]
-        }
-
-        self.shooting_cooldown_remaining = HERO_KUNAI_COOLDOWN;
-        remove_one_of_species_from_inventory(SPECIES_KUNAI);
-
-        let mut bullet = species_by_id(SPECIES_KUNAI).make_entity();
-        bullet.direction = world.cached_hero_props.direction;
-        let (dx, dy) = bullet.direction.as_col_row_offset();
-        bullet.frame = world.cached_hero_props.frame.offset(dx, dy).offset_y(1).with_h(1);
-        bullet.offset = self.offset;
-        bullet.parent_id = self.id;
-        bullet.remaining_lifespan = 5.0;
-        bullet.reset_speed();
-
-        vec![WorldStateUpdate::AddEntity(Box::new(bullet))]
-    }
 }
\ No newline at end of file
@@ -1,11 +1,11 @@
-use crate::{constants::{SPRITE_SHEET_AVATARS, SPRITE_SHEET_INVENTORY, SPRITE_SHEET_STATIC_OBJECTS}, game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::{get_value_for_key, set_value_for_key, StorageKey}, world::World}, lang::localizable::LocalizableText, menus::toasts::{Toast, ToastImage}, utils::rect::IntRect};
+use crate::{constants::{SPRITE_SHEET_AVATARS, SPRITE_SHEET_INVENTORY, SPRITE_SHEET_STATIC_OBJECTS}, game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::{KeyValueStorage, StorageKey}, world::World}, lang::localizable::{LocalizableText, LocalizedStrings}, menus::toasts::{Toast, ToastImage}, utils::rect::IntRect};
 
-use super::species::species_by_id;
+use super::species::SpeciesRegistry;
 
 impl Entity {
-    pub fn setup_hint(&mut self, creative_mode: bool) {
+    pub fn setup_hint(&mut self, species: &SpeciesRegistry, creative_mode: bool) {
         if creative_mode { 
-            let species = species_by_id(self.species_id);
+            let species = species.get(self.species_id);
             self.sprite.sheet_id = SPRITE_SHEET_INVENTORY;
             self.sprite.frame.x = species.inventory_texture_offset.1;
             self.sprite.frame.y = species.inventory_texture_offset.0;
@@ -18,23 +18,23 @@ impl Entity {
 
     pub fn update_hint(&mut self, world: &World, _: f32) -> Vec<WorldStateUpdate> {   
         if !world.creative_mode && world.is_hero_at(self.frame.x, self.frame.y) {
-            self.hint_updates()    
+            self.hint_updates(&world.context.storage, &world.context.strings)    
         } else {
             vec![]
         }
     }
 
-    fn hint_updates(&self) -> Vec<WorldStateUpdate> {
-        if self.is_consumable && self.has_been_read() || self.contents.is_none() {
+    fn hint_updates(&self, storage: &KeyValueStorage, strings: &LocalizedStrings) -> Vec<WorldStateUpdate> {
+        if self.is_consumable && self.has_been_read(storage) || self.contents.is_none() {
             vec![]
         } else {
-            self.set_read();
-            vec![self.toast()]
+            self.set_read(storage);
+            vec![self.toast(strings)]
         }        
     }
 
-    fn toast(&self) -> WorldStateUpdate {
-        let hint = self.key().localized();
+    fn toast(&self, strings: &LocalizedStrings) -> WorldStateUpdate {
+        let hint = self.key().localized(strings);
         WorldStateUpdate::EngineUpdate(EngineStateUpdate::Toast(            
             Toast::goddess_message(hint)
         ))
@@ -44,12 +44,12 @@ impl Entity {
         self.contents.clone().unwrap_or("".to_owned())
     }
 
-    fn has_been_read(&self) -> bool {
-        has_hint_been_read(&self.key())
+    fn has_been_read(&self, storage: &KeyValueStorage) -> bool {
+        has_hint_been_read(storage, &self.key())
     }
 
-    fn set_read(&self) {
-        set_hint_read(&self.key())
+    fn set_read(&self, storage: &KeyValueStorage) {
+        set_hint_read(storage, &self.key())
     }
 }
 
@@ -59,12 +59,12 @@ impl StorageKey {
     }
 }
 
-fn set_hint_read(hint: &str) {
-    set_value_for_key(&StorageKey::hint_read(hint), 1);
+fn set_hint_read(storage: &KeyValueStorage, hint: &str) {
+    storage.set_value_for_key(&StorageKey::hint_read(hint), 1);
 }
 
-fn has_hint_been_read(hint: &str) -> bool {
-    if let Some(read) = get_value_for_key(&StorageKey::hint_read(hint)) {
+fn has_hint_been_read(storage: &KeyValueStorage, hint: &str) -> bool {
+    if let Some(read) = storage.get_value_for_key(&StorageKey::hint_read(hint)) {
         read == 1
     } else {
         false
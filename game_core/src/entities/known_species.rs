@@ -20,6 +20,8 @@ pub const SPECIES_KEY_GREEN: u32 = 2002;
 pub const SPECIES_KEY_BLUE: u32 = 2003;
 pub const SPECIES_KEY_SILVER: u32 = 2004;
 pub const SPECIES_KUNAI: u32 = 7000;
+pub const SPECIES_SWORD: u32 = 7002;
+pub const SPECIES_SWORD_SWING: u32 = 7003;
 pub const SPECIES_TELEPORTER: u32 = 1019;
 pub const SPECIES_ZOMBIE: u32 = 4002;
 pub const SPECIES_GHOST: u32 = 4003;
@@ -15,8 +15,16 @@ impl Entity {
         }
         
         if !world.creative_mode {
-            self.update_direction(world);
-            self.move_linearly(world, time_since_last_update);
+            if self.ranged_attacks_hero {
+                let updates = self.handle_ranged_attack(world, time_since_last_update);
+                if !updates.is_empty() {
+                    return updates
+                }
+            }
+            if !self.is_aiming() {
+                self.update_direction(world);
+                self.move_linearly(world, time_since_last_update);
+            }
             
             if self.melee_attacks_hero {
                 let updates = self.handle_melee_attack(world);                
@@ -38,7 +46,7 @@ impl Entity {
                     )
                 ];
                 return vec;  
-            } else if let Some(dialogue) = self.next_dialogue() {
+            } else if let Some(dialogue) = self.next_dialogue(world) {
                 self.demands_attention = false;
 
                 return vec![
@@ -1,8 +1,8 @@
-use crate::game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World};
+use crate::game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::KeyValueStorage, world::World};
 
 impl Entity {
-    pub fn setup_pressure_plate(&mut self) {
-        if !self.is_related_pressure_plate_down() {
+    pub fn setup_pressure_plate(&mut self, storage: &KeyValueStorage) {
+        if !self.is_related_pressure_plate_down(storage) {
             self.sprite.frame.x += 1;
         }
     }
@@ -21,7 +21,7 @@ impl Entity {
         let hero_on_it = world.is_hero_at(self.frame.x, self.frame.y);
         let weight_on_it = world.weights_map[self.frame.y as usize][self.frame.x as usize] > 0;
         let is_pressed = hero_on_it || weight_on_it;
-        let is_up = self.is_related_pressure_plate_down();
+        let is_up = self.is_related_pressure_plate_down(&world.context.storage);
 
         if is_up && is_pressed {
             self.sprite.frame.x = self.original_sprite_frame.x + 1;
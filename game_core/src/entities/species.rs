@@ -1,16 +1,19 @@
 use serde::{Deserialize, Serialize};
-use lazy_static::lazy_static;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::Read;
-use crate::config::config;
-use crate::constants::{HERO_ENTITY_ID, NO_PARENT, SPRITE_SHEET_BIOME_TILES, UNLIMITED_LIFESPAN};
+use std::io::BufReader;
+use std::path::Path;
+use crate::constants::{HERO_ENTITY_ID, NO_PARENT, SPRITE_SHEET_BIOME_TILES, UNASSIGNED_ENTITY_ID, UNLIMITED_LIFESPAN};
 use crate::features::animated_sprite::AnimatedSprite;
 use crate::features::directions::MovementDirections;
+use crate::features::health::DamageType;
+use crate::features::weapons::Weapon;
+use crate::game_engine::errors::{GameError, GameResult};
 use crate::game_engine::entity::Entity;
 use crate::game_engine::locks::LockType;
-use crate::lang::localizable::LocalizableText;
+use crate::game_engine::migrations::{from_reader, DataKind, SpeciesFile};
+use crate::lang::localizable::{LocalizableText, LocalizedStrings};
 use crate::utils::directions::Direction;
-use crate::utils::ids::get_next_id;
 use crate::utils::rect::IntRect;
 use crate::utils::vector::Vector2d;
 
@@ -41,11 +44,39 @@ pub struct Species {
     #[serde(default)]
     pub melee_attacks_hero: bool,
 
+    /// Shoots the weapon of the species at the hero when it's in line of sight
+    #[serde(default)]
+    pub ranged_attacks_hero: bool,
+
     #[serde(default)]
     pub bundle_contents: Vec<u32>,
 
     #[serde(default)]
     pub is_invulnerable: bool,
+
+    #[serde(default="one")]
+    pub hp: u32,
+
+    #[serde(default)]
+    pub armor: u32,
+
+    #[serde(default)]
+    pub resistances: BTreeMap<DamageType, f32>,
+
+    /// Dealt by melee creeps to the hero, or by projectiles to what they hit
+    #[serde(default="one")]
+    pub damage: u32,
+
+    #[serde(default)]
+    pub damage_type: DamageType,
+
+    /// Makes the species a weapon the hero can equip, creeps that attack from range fire it too
+    #[serde(default)]
+    pub weapon: Option<Weapon>,
+}
+
+fn one() -> u32 {
+    1
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -63,17 +94,19 @@ pub enum EntityType {
     Bullet,
     Bundle,
     RailObject,
-    Hint
+    Hint,
+    /// Purely visual, stays out of hitmaps
+    Effect
 }
 
 impl Species {
-    pub fn localized_name(&self) -> String {
-        self.name.localized()
+    pub fn localized_name(&self, strings: &LocalizedStrings) -> String {
+        self.name.localized(strings)
     }
 }
 
 impl Species {
-    pub fn make_entity(&self) -> Entity {
+    pub fn make_entity(&self, strings: &LocalizedStrings) -> Entity {
         let sprite = self.make_sprite(false);
         let original_sprite_frame = sprite.frame; 
         let initial_speed = if self.movement_directions.moves_by_default() { self.base_speed } else { 0.0 };
@@ -92,7 +125,7 @@ impl Species {
             sprite,
             dialogues: vec![],
             time_immobilized: 0.0,
-            name: self.name.localized(),
+            name: self.name.localized(strings),
             destination: None,
             lock_type: self.lock_type,
             original_sprite_frame,
@@ -100,17 +133,23 @@ impl Species {
             remaining_lifespan: UNLIMITED_LIFESPAN,
             shooting_cooldown_remaining: 0.0,
             parent_id: NO_PARENT,
+            remaining_pierce: 0,
+            last_hit_id: UNASSIGNED_ENTITY_ID,
             is_dying: false,
             melee_attacks_hero: self.melee_attacks_hero,
+            ranged_attacks_hero: self.ranged_attacks_hero,
+            time_to_fire: 0.0,
             speed_multiplier: 1.0,
             is_invulnerable: false,
+            hp: self.hp,
+            time_invulnerable: 0.0,
             demands_attention: false,
             is_consumable: self.is_consumable,
             movement_directions: self.movement_directions
         }
     }
 
-    pub fn reload_props(&self, entity: &mut Entity) {
+    pub fn reload_props(&self, entity: &mut Entity, strings: &LocalizedStrings) {
         let sprite = self.make_sprite(false);        
         entity.frame.w = sprite.frame.w;  
         entity.frame.h = sprite.frame.h;  
@@ -119,12 +158,16 @@ impl Species {
         entity.entity_type = self.entity_type;
         entity.is_rigid = self.is_rigid;
         entity.sprite = sprite;
-        entity.name = self.name.localized();
+        entity.name = self.name.localized(strings);
         entity.shooting_cooldown_remaining = 0.0;
         entity.melee_attacks_hero = self.melee_attacks_hero;
+        entity.ranged_attacks_hero = self.ranged_attacks_hero;
+        entity.time_to_fire = 0.0;
         entity.speed_multiplier = 1.0;
         entity.is_consumable = self.is_consumable;
         entity.is_invulnerable = self.is_invulnerable;
+        entity.hp = self.hp;
+        entity.time_invulnerable = 0.0;
         entity.movement_directions = self.movement_directions;
     }
 
@@ -143,18 +186,47 @@ impl Species {
     fn next_entity_id(&self) -> u32 {
         match self.entity_type {
             EntityType::Hero => HERO_ENTITY_ID,
-            _ => get_next_id()
+            _ => UNASSIGNED_ENTITY_ID
         }
     }
 }
 
-lazy_static! {
-    pub static ref ALL_SPECIES: Vec<Species> = {
-        let mut file = File::open(config().species_path.clone()).expect("Could not open species.json");
-        let mut data = String::new();
-        file.read_to_string(&mut data).expect("Could not read species.json");
-        serde_json::from_str(&data).expect("Error parsing species.json")
-    };
+#[derive(Debug, Clone, Default)]
+pub struct SpeciesRegistry {
+    species: Vec<Species>,
+}
+
+impl SpeciesRegistry {
+    pub fn new(species: Vec<Species>) -> Self {
+        let mut registry = Self::default();
+        registry.register(species);
+        registry
+    }
+
+    pub fn load(path: &Path) -> GameResult<Self> {
+        let file = File::open(path).map_err(|e| GameError::io(path, e))?;
+        let data: SpeciesFile = from_reader(DataKind::Species, path, BufReader::new(file))?;
+        Ok(Self::new(data.species))
+    }
+
+    /// Species with an id that is already known replace the existing one
+    pub fn register(&mut self, species: Vec<Species>) {
+        for item in species {
+            if let Some(existing) = self.species.iter_mut().find(|s| s.id == item.id) {
+                *existing = item;
+            } else {
+                self.species.push(item);
+            }
+        }
+    }
+
+    pub fn all(&self) -> &[Species] {
+        &self.species
+    }
+
+    pub fn get(&self, species_id: SpeciesId) -> &Species {
+        self.species.iter().find(|s| s.id == species_id).unwrap_or(&NO_SPECIES)
+    }
 }
 
 pub const SPECIES_NONE: Species = Species {
@@ -170,16 +242,17 @@ pub const SPECIES_NONE: Species = Species {
     sprite_number_of_frames: 1,
     lock_type: LockType::None,
     melee_attacks_hero: false,
+    ranged_attacks_hero: false,
     is_consumable: false,
     bundle_contents: vec![],
     is_invulnerable: false,
+    hp: 1,
+    armor: 0,
+    resistances: BTreeMap::new(),
+    damage: 1,
+    damage_type: DamageType::Blunt,
+    weapon: None,
     movement_directions: MovementDirections::None
 };
 
-pub fn species_by_id(species_id: u32) -> Species {
-    ALL_SPECIES.iter().find(|s| s.id == species_id).cloned().unwrap_or(SPECIES_NONE)
-}
-
-pub fn make_entity_by_species(species_id: u32) -> Entity {
-    species_by_id(species_id).make_entity()
-}
\ No newline at end of file
+static NO_SPECIES: Species = SPECIES_NONE;
@@ -1,4 +1,4 @@
-use crate::{game_engine::{entity::Entity, inventory::inventory_contains_species, locks::LockType, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World}, lang::localizable::LocalizableText, menus::toasts::Toast, utils::directions::Direction};
+use crate::{log_debug, utils::logger::Subsystem, game_engine::{entity::Entity, locks::LockType, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World}, lang::localizable::{LocalizableText, LocalizedStrings}, menus::toasts::Toast, utils::directions::Direction};
 
 impl Entity {
     pub fn setup_teleporter(&mut self, creative_mode: bool) {
@@ -17,11 +17,14 @@ impl Entity {
         } 
 
         if self.should_teleport(world) {
+            let hero = world.cached_hero_props.hittable_frame;
+            log_debug!(world.context.logger, Subsystem::Entities, "Hero x {} y {} d {:?} reached teleporter at x {} y {}", hero.x, hero.y, world.cached_hero_props.direction, self.frame.x, self.frame.y);
+
             if !world.creative_mode && self.lock_type != LockType::None {
-                if inventory_contains_species(self.lock_type.key()) {
-                    vec![self.show_unlock_confirmation()]
+                if world.context.inventory.contains_species(self.lock_type.key()) {
+                    vec![self.show_unlock_confirmation(&world.context.strings)]
                 } else {
-                    vec![self.show_locked_message()]
+                    vec![self.show_locked_message(&world.context.strings)]
                 }                
             } else {
                 vec![self.engine_update_push_world()]
@@ -38,21 +41,16 @@ impl Entity {
         let hero = world.cached_hero_props.hittable_frame;
         let hero_direction = world.cached_hero_props.direction;
 
-
         if matches!(hero_direction, Direction::Up) && hero.x == self.frame.x && hero.y == self.frame.y + 1 {
-            println!("Hero x {} y {} d {:#?} selfx {} selfy {}", hero.x, hero.y, hero_direction, self.frame.x, self.frame.y);
             return true
         }
         if matches!(hero_direction, Direction::Down) && hero.x == self.frame.x && hero.y == self.frame.y - 1 {
-            println!("Hero x {} y {} d {:#?} selfx {} selfy {}", hero.x, hero.y, hero_direction, self.frame.x, self.frame.y);
             return true
         }
         if matches!(hero_direction, Direction::Right) && hero.x == self.frame.x - 1 && hero.y == self.frame.y {
-            println!("Hero x {} y {} d {:#?} selfx {} selfy {}", hero.x, hero.y, hero_direction, self.frame.x, self.frame.y);
             return true
         }
         if matches!(hero_direction, Direction::Left) && hero.x == self.frame.x + 1 && hero.y == self.frame.y {
-            println!("Hero x {} y {} d {:#?} selfx {} selfy {}", hero.x, hero.y, hero_direction, self.frame.x, self.frame.y);
             return true
         }
         false
@@ -66,30 +64,30 @@ impl Entity {
         )
     }
 
-    fn show_locked_message(&self) -> WorldStateUpdate {        
+    fn show_locked_message(&self, strings: &LocalizedStrings) -> WorldStateUpdate {        
         WorldStateUpdate::EngineUpdate(
             EngineStateUpdate::Toast(
-                Toast::regular(self.locked_message())
+                Toast::regular(self.locked_message(strings))
             )
         )
     }
 
-    fn locked_message(&self) -> String {
+    fn locked_message(&self, strings: &LocalizedStrings) -> String {
         if matches!(self.lock_type, LockType::Permanent) {
-            "telepoter.locked.permanent".localized()
+            "telepoter.locked.permanent".localized(strings)
         } else {
-            let name = self.lock_type.localized_name().to_uppercase();
-            "teleporter.locked".localized().replace("%s", &name)
+            let name = self.lock_type.localized_name(strings).to_uppercase();
+            "teleporter.locked".localized(strings).replace("%s", &name)
         }
     } 
 
-    fn show_unlock_confirmation(&self) -> WorldStateUpdate {
-        let name = self.lock_type.localized_name().to_uppercase();
+    fn show_unlock_confirmation(&self, strings: &LocalizedStrings) -> WorldStateUpdate {
+        let name = self.lock_type.localized_name(strings).to_uppercase();
         
         WorldStateUpdate::EngineUpdate(
             EngineStateUpdate::Confirmation(
-                "teleporter.unlock.title".localized(),
-                "teleporter.unlock.message".localized().replace("%s", &name),
+                "teleporter.unlock.title".localized(strings),
+                "teleporter.unlock.message".localized(strings).replace("%s", &name),
                 vec![
                     WorldStateUpdate::ChangeLock(self.id, LockType::None),
                     WorldStateUpdate::EngineUpdate(EngineStateUpdate::SaveGame),
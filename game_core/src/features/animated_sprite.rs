@@ -39,7 +39,7 @@ impl AnimatedSprite {
 impl Entity {
     pub fn update_sprite_for_current_state(&mut self) {
         if self.remaining_lifespan == UNLIMITED_LIFESPAN || self.remaining_lifespan > 1.0 {
-            if self.demands_attention {
+            if self.demands_attention || self.is_aiming() {
                 self.sprite.frame.y = self.sprite.frame.h * 8
             } else {
                 self.update_sprite_for_direction_speed(self.direction, self.current_speed)
@@ -73,6 +73,7 @@ impl TimedContentProvider<i32> {
     }
 }
 
+#[allow(clippy::match_like_matches_macro)]
 fn supports_directions(sheet_id: u32) -> bool {
     match sheet_id {
         SPRITE_SHEET_HUMANOIDS_1X1 => true,
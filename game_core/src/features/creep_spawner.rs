@@ -1,19 +1,31 @@
-use crate::{constants::{WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::{known_species::{SPECIES_GHOST, SPECIES_HOMUNCULUS, SPECIES_ZOMBIE}, species::species_by_id}, game_engine::{entity::Entity, state_updates::WorldStateUpdate, world::World}, maps::biome_tiles::Biome, utils::{directions::Direction, rect::IntRect}};
-use rand::{rngs::ThreadRng, seq::SliceRandom};
+use crate::{constants::{WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::{known_species::{SPECIES_GHOST, SPECIES_HOMUNCULUS, SPECIES_ZOMBIE}, }, game_engine::{entity::Entity, state_updates::WorldStateUpdate, world::World}, maps::biome_tiles::Biome, utils::{directions::Direction, rect::IntRect}};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
 pub struct CreepSpawner {
-    time_to_next_spawn: f32,
-    rng: ThreadRng,
+    pub time_to_next_spawn: f32,
+    rng: StdRng,
 }
 
 impl CreepSpawner {
-    pub fn new() -> Self {
+    pub fn new(rng: StdRng) -> Self {
         CreepSpawner {
             time_to_next_spawn: 1.0,
-            rng: rand::thread_rng(),
+            rng,
         }
     }
 
+    /// Continues with a new sequence of random numbers, seeded from the current one.
+    /// Snapshots keep the seed, so that spawns after a quick load are the same as after the save.
+    pub fn reseed(&mut self) -> u64 {
+        let seed = self.rng.gen();
+        self.restore_seed(seed);
+        seed
+    }
+
+    pub fn restore_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     pub fn update(
         &mut self,
         world: &World,
@@ -30,7 +42,7 @@ impl CreepSpawner {
             if let Some((x, y)) = self.next_creep_position(&hero_direction, world) {
                 self.time_to_next_spawn = world.creep_spawn_interval;
 
-                let mut entity = self.make_creep();
+                let mut entity = self.make_creep(world);
                 entity.frame.x = x;
                 entity.frame.y = y;
 
@@ -42,11 +54,11 @@ impl CreepSpawner {
         vec![]
     }
 
-    fn make_creep(&mut self) -> Entity {
+    fn make_creep(&mut self, world: &World) -> Entity {
         let id = *[SPECIES_ZOMBIE, SPECIES_HOMUNCULUS, SPECIES_GHOST]
             .choose(&mut self.rng)
             .unwrap_or(&SPECIES_ZOMBIE);
-        species_by_id(id).make_entity()
+        world.context.make_entity(id)
     }
 
     fn next_creep_position(
@@ -1,25 +1,73 @@
-use crate::{lang::localizable::LocalizableText, text, ui::components::{empty_view, Typography, View}};
+use crate::{game_engine::keyboard_events_provider::KeyboardEventsProvider, lang::localizable::{LocalizableText, LocalizedStrings}, menus::menu::{Menu, MenuItem}, ui::components::View};
 
 pub struct DeathScreen {
-    pub is_open: bool
+    menu: Menu<DeathScreenOption>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DeathScreenOption {
+    ContinueFromCheckpoint,
+    ReturnToEntrance,
+    Quit,
+}
+
+impl MenuItem for DeathScreenOption {
+    fn title(&self, strings: &LocalizedStrings) -> String {
+        match self {
+            DeathScreenOption::ContinueFromCheckpoint => "death_screen.continue".localized(strings),
+            DeathScreenOption::ReturnToEntrance => "death_screen.entrance".localized(strings),
+            DeathScreenOption::Quit => "death_screen.quit".localized(strings),
+        }
+    }
 }
 
 impl DeathScreen {
-    pub fn new() -> Self {
+    pub fn new(strings: &LocalizedStrings) -> Self {
         Self {
-            is_open: false
+            menu: Menu::empty_with_title("death_screen.title".localized(strings))
         }
     }
 
-    pub fn show(&mut self) {
-        self.is_open = true
+    pub fn is_open(&self) -> bool {
+        self.menu.is_open
+    }
+
+    pub fn options(&self, strings: &LocalizedStrings) -> Vec<String> {
+        self.menu.item_titles(strings)
     }
 
-    pub fn ui(&self) -> View {
-        if self.is_open {
-            text!(Typography::Title, "death_screen.title".localized())
+    /// Continuing from a checkpoint is only offered when there is one.
+    pub fn show(&mut self, has_checkpoint: bool) {
+        self.menu.items = if has_checkpoint {
+            vec![DeathScreenOption::ContinueFromCheckpoint, DeathScreenOption::ReturnToEntrance, DeathScreenOption::Quit]
         } else {
-            empty_view()
+            vec![DeathScreenOption::ReturnToEntrance, DeathScreenOption::Quit]
+        };
+        self.menu.clear_selection();
+        self.menu.show();
+    }
+
+    pub fn close(&mut self) {
+        self.menu.clear_selection();
+        self.menu.close();
+    }
+
+    /// Returns the option the player picked, the screen can't be dismissed otherwise.
+    pub fn update(&mut self, keyboard: &KeyboardEventsProvider, time_since_last_update: f32) -> Option<DeathScreenOption> {
+        self.menu.update(keyboard, time_since_last_update);
+
+        if !self.menu.is_open {
+            self.menu.show_no_animation();
         }
+        if self.menu.selection_has_been_confirmed {
+            let selection = self.menu.selected_item();
+            self.close();
+            return Some(selection)
+        }
+        None
+    }
+
+    pub fn ui(&self, strings: &LocalizedStrings) -> View {
+        self.menu.ui(strings)
     }
-}
\ No newline at end of file
+}
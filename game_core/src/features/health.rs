@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{constants::{HERO_ENTITY_ID, HERO_INVULNERABILITY_DURATION, HERO_KNOCKBACK_DISTANCE, HURT_FLASH_DURATION}, entities::species::{Species, SpeciesRegistry}, game_engine::{entity::Entity, state_updates::EngineStateUpdate, storage::StorageKey, world::World}, utils::{directions::Direction, vector::Vector2d}};
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DamageType {
+    #[default]
+    Blunt,
+    Piercing,
+    Slashing,
+    Magic,
+}
+
+impl Species {
+    /// Damage left after resistances, then armor. Hits always hurt a little, unless fully resisted.
+    pub fn damage_taken(&self, damage: u32, damage_type: DamageType) -> u32 {
+        let resistance = self.resistances.get(&damage_type).copied().unwrap_or_default();
+        if damage == 0 || resistance >= 1.0 {
+            return 0
+        }
+        let resisted = (damage as f32 * (1.0 - resistance)).round() as u32;
+        resisted.saturating_sub(self.armor).max(1)
+    }
+}
+
+impl Entity {
+    /// Takes a hit and flashes for a moment, returns true if it was fatal.
+    pub fn receive_damage(&mut self, damage: u32, damage_type: DamageType, species: &SpeciesRegistry) -> bool {
+        let damage = species.get(self.species_id).damage_taken(damage, damage_type);
+        self.hp = self.hp.saturating_sub(damage);
+        self.time_invulnerable = HURT_FLASH_DURATION;
+        self.hp == 0
+    }
+}
+
+impl World {
+    /// Hits the hero unless it's still recovering from the previous hit, shows the death screen when hp runs out.
+    pub fn damage_hero(&mut self, damage: u32, damage_type: DamageType, direction: Direction) -> Option<EngineStateUpdate> {
+        let index = self.index_for_entity(HERO_ENTITY_ID)?;
+        let mut entities = self.entities.borrow_mut();
+        let hero = entities.get_mut(index)?;
+
+        if hero.is_invulnerable || hero.time_invulnerable > 0.0 {
+            return None
+        }
+        hero.receive_damage(damage, damage_type, &self.context.species);
+        hero.time_invulnerable = HERO_INVULNERABILITY_DURATION;
+        let hp = hero.hp;
+        let direction = match direction {
+            Direction::Unknown | Direction::Still => hero.direction.opposite(),
+            direction => direction
+        };
+        drop(entities);
+
+        self.context.storage.set(&StorageKey::hero_hp(), hp);
+        self.cached_hero_props.hp = hp;
+        self.cached_hero_props.is_invulnerable = true;
+
+        if hp == 0 {
+            return Some(EngineStateUpdate::DeathScreen)
+        }
+        self.knock_back_hero(direction);
+        None
+    }
+
+    /// Heals or hurts the hero without any of the effects of a hit.
+    pub fn set_hero_hp(&mut self, hp: u32) {
+        if let Some(index) = self.index_for_entity(HERO_ENTITY_ID) {
+            self.entities.borrow_mut()[index].hp = hp;
+        }
+        self.context.storage.set(&StorageKey::hero_hp(), hp);
+        self.cached_hero_props.hp = hp;
+    }
+
+    fn knock_back_hero(&mut self, direction: Direction) {
+        let Some(index) = self.index_for_entity(HERO_ENTITY_ID) else { return };
+        let (col, row) = direction.as_col_row_offset();
+        let mut frame = self.entities.borrow()[index].frame;
+
+        for _ in 0..HERO_KNOCKBACK_DISTANCE {
+            if !self.is_free_for_hero(frame.x + col, frame.y + row) {
+                break
+            }
+            frame.x += col;
+            frame.y += row;
+        }
+
+        let mut entities = self.entities.borrow_mut();
+        let hero = &mut entities[index];
+        hero.frame = frame;
+        hero.offset = Vector2d::zero();
+        hero.current_speed = 0.0;
+        self.spatial_grid.update(HERO_ENTITY_ID, &frame);
+        self.cached_hero_props.frame = frame;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{constants::{HERO_ENTITY_ID, HERO_INVULNERABILITY_DURATION}, entities::{known_species::SPECIES_HERO, species::{EntityType, Species}}, game_engine::{state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World}, testing::{fixtures::{fixture_species, WorldFixture}, harness::TestHarness}, utils::directions::Direction};
+
+    use super::DamageType;
+
+    const SPECIES_FIXTURE_CREEP: u32 = 900_401;
+    const SPECIES_FIXTURE_ARROW: u32 = 900_402;
+
+    #[test]
+    fn armor_and_resistances_reduce_damage() {
+        let species = Species {
+            armor: 1,
+            resistances: BTreeMap::from([(DamageType::Piercing, 0.5), (DamageType::Magic, 1.0)]),
+            ..fixture_species(SPECIES_FIXTURE_CREEP, EntityType::Npc, false)
+        };
+        assert_eq!(species.damage_taken(4, DamageType::Blunt), 3);
+        assert_eq!(species.damage_taken(4, DamageType::Piercing), 1);
+        assert_eq!(species.damage_taken(1, DamageType::Slashing), 1);
+        assert_eq!(species.damage_taken(9, DamageType::Magic), 0);
+    }
+
+    #[test]
+    fn tougher_creeps_flash_and_take_several_hits() {
+        let world_fixture = WorldFixture::new(900_400)
+            .biomes(&["1111111111"; 3])
+            .entities(&["a.a.a.c"])
+            .legend('a', SPECIES_FIXTURE_ARROW)
+            .legend('c', SPECIES_FIXTURE_CREEP)
+            .species(Species { damage: 3, damage_type: DamageType::Piercing, ..fixture_species(SPECIES_FIXTURE_ARROW, EntityType::Bullet, false) })
+            .species(Species { hp: 4, armor: 1, ..fixture_species(SPECIES_FIXTURE_CREEP, EntityType::Npc, false) });
+        let mut world = world_fixture.build();
+        let ids: Vec<u32> = world.entities.borrow().iter().map(|e| e.id).collect();
+        let (arrows, creep) = (&ids[..3], ids[3]);
+        let creep_state = |world: &World| {
+            let entities = world.entities.borrow();
+            let creep = &entities[world.index_for_entity(creep).unwrap()];
+            (creep.hp, creep.time_invulnerable > 0.0, creep.is_dying)
+        };
+
+        world.apply_state_updates(vec![WorldStateUpdate::HandleHit(arrows[0], creep)]);
+        assert_eq!(creep_state(&world), (2, true, false));
+        assert!(world.index_for_entity(arrows[0]).is_none());
+
+        world.apply_state_updates(vec![WorldStateUpdate::HandleHit(arrows[1], creep)]);
+        assert_eq!(creep_state(&world), (0, true, true));
+
+        world.apply_state_updates(vec![WorldStateUpdate::HandleHit(arrows[2], creep)]);
+        assert!(world.index_for_entity(arrows[2]).is_some());
+    }
+
+    #[test]
+    fn hits_cost_hp_and_are_followed_by_a_short_invulnerability() {
+        let mut game = TestHarness::new();
+        game.teleport(1002, 0, 0).wait(0.5);
+        let max_hp = game.engine.context.species.get(SPECIES_HERO).hp;
+        let hero = game.engine.world.cached_hero_props.frame;
+        assert_eq!(game.engine.world.cached_hero_props.hp, max_hp);
+
+        let hit = vec![WorldStateUpdate::DamageHero(1, DamageType::Blunt, Direction::Right)];
+        assert!(game.engine.world.apply_state_updates(hit.clone()).is_empty());
+        assert!(game.engine.world.apply_state_updates(hit.clone()).is_empty());
+        assert_eq!(game.engine.world.cached_hero_props.hp, max_hp - 1);
+        assert_eq!(game.engine.world.cached_hero_props.frame.x, hero.x + 2);
+        assert_eq!(game.engine.world.cached_hero_props.frame.y, hero.y);
+        game.assert_storage("hero.hp", Some((max_hp - 1).into()));
+
+        game.wait(HERO_INVULNERABILITY_DURATION + 0.1);
+        let hero_entity = game.engine.world.index_for_entity(HERO_ENTITY_ID).unwrap();
+        assert_eq!(game.engine.world.entities.borrow()[hero_entity].time_invulnerable, 0.0);
+
+        let updates = game.engine.world.apply_state_updates(vec![WorldStateUpdate::DamageHero(max_hp, DamageType::Blunt, Direction::Left)]);
+        assert!(matches!(updates.as_slice(), [EngineStateUpdate::DeathScreen]));
+    }
+}
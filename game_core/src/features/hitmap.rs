@@ -18,8 +18,11 @@ impl World {
         let mut idsmap = vec![vec![0; width]; height];
         let mut weightsmap = vec![vec![0; width]; height];
     
-        for &(index, id) in &self.visible_entities {
+        for &(index, id) in self.visible_entities.iter().chain(&self.active_entities) {
             let entity = &entities[index];
+            if entity.entity_type == EntityType::Effect {
+                continue
+            }
             let col_start = entity.frame.x as usize;
             let col_end = (col_start + entity.frame.w as usize).min(width);
     
@@ -50,6 +53,8 @@ impl World {
         (hitmap, idsmap, weightsmap)
     }
 
+    // Biome tiles, construction tiles and the hitmap share the same indices
+    #[allow(clippy::needless_range_loop)]
     pub fn update_tiles_hitmap(&mut self) {    
         let mut hitmap = vec![vec![false; self.bounds.w as usize]; self.bounds.h as usize];
 
@@ -88,36 +93,43 @@ impl Entity {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
-    use crate::{entities::species::make_entity_by_species, maps::{biome_tiles::{Biome, BiomeTile}, constructions_tiles::ConstructionTile}, utils::{ids::get_next_id, rect::IntRect}};
+    use crate::{entities::species::{EntityType, Species}, testing::fixtures::{fixture_species, WorldFixture}, utils::rect::IntRect};
     
-    const SPECIES_NPC_OLD_MAN: u32 = 3005;
+    const SPECIES_FIXTURE_NPC: u32 = 900_101;
+    const SPECIES_FIXTURE_GHOST: u32 = 900_102;
+
+    fn grass() -> [&'static str; 10] {
+        ["1111111111"; 10]
+    }
     
     #[test]
     fn test_hitmap_with_rigid_entity_excludes_top_row() {
-        let mut world = World::new(get_next_id());        
-        let mut npc = make_entity_by_species(SPECIES_NPC_OLD_MAN);
-        npc.frame.x = 5;
-        npc.frame.y = 5;
-        world.add_entity(npc);
-        world.visible_entities = world.compute_visible_entities(&IntRect::square_from_origin(20));
+        let world = WorldFixture::new(1)
+            .biomes(&grass())
+            .entities(&[
+                "", "", "", "", "",
+                ".....n",
+            ])
+            .legend('n', SPECIES_FIXTURE_NPC)
+            .species(Species { sprite_frame: IntRect::new(0, 0, 1, 2), ..fixture_species(SPECIES_FIXTURE_NPC, EntityType::Npc, true) })
+            .build();
         
         let (hitmap, _, _) = world.compute_hitmap();
-        println!("{:#?}", world.visible_entities);
         assert!(!hitmap[5][5]);
         assert!(hitmap[6][5]);
     }
 
     #[test]
     fn test_hitmap_ignores_non_rigid_entity() {
-        let mut world = World::new(get_next_id());
-        let mut npc = make_entity_by_species(SPECIES_NPC_OLD_MAN);
-        npc.frame = IntRect::new(5, 5, 2, 2);
-        npc.is_rigid = false;
-        
-        world.add_entity(npc);
-        world.compute_visible_entities(&IntRect::square_from_origin(20));
+        let world = WorldFixture::new(1)
+            .biomes(&grass())
+            .entities(&[
+                "", "", "", "", "",
+                ".....g",
+            ])
+            .legend('g', SPECIES_FIXTURE_GHOST)
+            .species(Species { sprite_frame: IntRect::new(0, 0, 2, 2), ..fixture_species(SPECIES_FIXTURE_GHOST, EntityType::Npc, false) })
+            .build();
         
         let (hitmap, _, _) = world.compute_hitmap();
         assert!(!hitmap[6][5]);
@@ -128,14 +140,11 @@ mod tests {
 
     #[test]
     fn test_hitmap_with_biome_tiles_nothing_still_hits() {
-        let mut world = World::new(get_next_id());
-        world.bounds = IntRect::new(0, 0, 10, 10);
+        let mut world = WorldFixture::new(1)
+            .biomes(&["0000000000"; 10])
+            .build();
         world.cached_hero_props.frame = IntRect::new(4, 4, 2, 2);
         
-        world.constructions_tiles.tiles = vec![vec![ConstructionTile::from_data('0'); 10]; 10];
-        world.biome_tiles.tiles = vec![vec![BiomeTile::from_data('0'); 10]; 10];
-        
-        world.update_tiles_hitmap();
         let (hitmap, _, _) = world.compute_hitmap();
 
         assert!(hitmap[4][4]);
@@ -145,15 +154,22 @@ mod tests {
 
     #[test]
     fn test_hitmap_with_biome_tiles() {
-        let mut world = World::new(get_next_id());
-        world.bounds = IntRect::new(0, 0, 10, 10);
+        let mut world = WorldFixture::new(1)
+            .biomes(&[
+                "1111111111",
+                "1111111111",
+                "1111111111",
+                "1111111111",
+                "1111111111",
+                "1111121111",
+                "1111111111",
+                "1111111111",
+                "1111111111",
+                "1111111111",
+            ])
+            .build();
         world.cached_hero_props.frame = IntRect::new(4, 4, 2, 2);
         
-        world.constructions_tiles.tiles = vec![vec![ConstructionTile::from_data('0'); 10]; 10];
-        world.biome_tiles.tiles = vec![vec![BiomeTile::from_data('1'); 10]; 10];
-        world.biome_tiles.tiles[5][5].tile_type = Biome::Water;
-        
-        world.update_tiles_hitmap();
         let (hitmap, _, _) = world.compute_hitmap();
 
         assert!(!hitmap[4][4]);
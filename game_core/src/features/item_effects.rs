@@ -1,7 +1,7 @@
-use crate::{entities::species::SpeciesId, game_engine::world::World};
+use crate::{entities::species::SpeciesId, game_engine::world::World, log_warning, utils::logger::Subsystem};
 
 impl World {
     pub fn use_item(&mut self, species_id: SpeciesId) {
-        println!("Don't know how to use {}", species_id)
+        log_warning!(self.context.logger, Subsystem::Entities, "Don't know how to use {}", species_id)
     }
 }
\ No newline at end of file
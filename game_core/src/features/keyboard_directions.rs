@@ -1,12 +1,12 @@
-use crate::{constants::STEP_COMMITMENT_THRESHOLD, game_engine::entity::Entity, utils::directions::Direction};
+use crate::{constants::STEP_COMMITMENT_THRESHOLD, entities::species::SpeciesRegistry, game_engine::entity::Entity, utils::directions::Direction};
 
 impl Entity {
-    pub fn update_direction_for_current_keys(&mut self, new_direction: Direction) {
+    pub fn update_direction_for_current_keys(&mut self, new_direction: Direction, species: &SpeciesRegistry) {
         let current_direction = self.direction;
 
         if self.offset.x.abs() < STEP_COMMITMENT_THRESHOLD && self.offset.y.abs() < STEP_COMMITMENT_THRESHOLD {
             if new_direction != Direction::Unknown {
-                self.reset_speed();
+                self.reset_speed(species);
                 self.direction = new_direction;
             } else {
                 self.current_speed = 0.0;
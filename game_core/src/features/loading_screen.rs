@@ -5,6 +5,12 @@ pub struct LoadingScreen {
     animator: Animator,
 }
 
+impl Default for LoadingScreen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LoadingScreen {
     pub fn new() -> Self {
         Self {
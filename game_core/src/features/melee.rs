@@ -1,4 +1,4 @@
-use crate::game_engine::{entity::Entity, state_updates::{EngineStateUpdate, WorldStateUpdate}, world::World};
+use crate::{game_engine::{entity::Entity, state_updates::WorldStateUpdate, world::World}};
 
 impl Entity {
     pub fn handle_melee_attack(&mut self, world: &World) -> Vec<WorldStateUpdate> {
@@ -12,8 +12,9 @@ impl Entity {
         let y = self.frame.y + if self.frame.h > 1 { 1 } else { 0 };
         
         if x == hero.x && y == hero.y && !hero_invulnerable {
-            return vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::DeathScreen)]
+            let species = world.context.species.get(self.species_id);
+            return vec![WorldStateUpdate::DamageHero(species.damage, species.damage_type, self.direction)]
         }
         vec![]
     }
-}
\ No newline at end of file
+}
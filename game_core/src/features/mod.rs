@@ -4,9 +4,12 @@ pub mod creep_spawner;
 pub mod death_screen;
 pub mod destination;
 pub mod directions;
+pub mod health;
 pub mod hitmap;
 pub mod keyboard_directions;
 pub mod item_effects;
 pub mod linear_movement;
 pub mod loading_screen;
-pub mod melee;
\ No newline at end of file
+pub mod melee;
+pub mod ranged;
+pub mod weapons;
\ No newline at end of file
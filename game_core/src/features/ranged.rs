@@ -0,0 +1,143 @@
+use crate::{game_engine::{entity::Entity, state_updates::WorldStateUpdate, world::World}};
+
+use super::weapons::Weapon;
+
+impl Entity {
+    /// Stops and aims at the hero for the windup of the weapon, then fires if the hero is still in sight.
+    pub fn handle_ranged_attack(&mut self, world: &World, time_since_last_update: f32) -> Vec<WorldStateUpdate> {
+        self.shooting_cooldown_remaining -= time_since_last_update;
+
+        if self.is_dying || world.creative_mode {
+            return vec![]
+        }
+        if self.is_aiming() {
+            self.time_to_fire -= time_since_last_update;
+
+            if self.time_to_fire > 0.0 {
+                return vec![]
+            }
+            self.time_to_fire = 0.0;
+            self.reset_speed(&world.context.species);
+
+            return match world.context.species.get(self.species_id).weapon.clone() {
+                Some(weapon) => self.fire(world, &weapon),
+                None => vec![]
+            }
+        }
+        if self.shooting_cooldown_remaining > 0.0 || self.offset.x != 0.0 || self.offset.y != 0.0 {
+            return vec![]
+        }
+        if !self.is_hero_in_line_of_sight(world) {
+            return vec![]
+        }
+        let Some(weapon) = world.context.species.get(self.species_id).weapon.clone() else {
+            return vec![]
+        };
+        if !self.is_hero_within(world, weapon.range) {
+            return vec![]
+        }
+        self.change_direction_towards_hero(world);
+
+        if weapon.windup > 0.0 {
+            self.time_to_fire = weapon.windup;
+            self.current_speed = 0.0;
+            return vec![]
+        }
+        self.fire(world, &weapon)
+    }
+
+    /// Aiming creeps show the same alert as entities that demand attention.
+    pub fn is_aiming(&self) -> bool {
+        self.time_to_fire > 0.0
+    }
+
+    fn fire(&mut self, world: &World, weapon: &Weapon) -> Vec<WorldStateUpdate> {
+        self.shooting_cooldown_remaining = weapon.cooldown;
+
+        if !self.is_hero_in_line_of_sight(world) {
+            return vec![]
+        }
+        self.change_direction_towards_hero(world);
+        self.shoot(world, weapon)
+    }
+
+    fn is_hero_within(&self, world: &World, range: f32) -> bool {
+        let hero = world.cached_hero_props.hittable_frame;
+        let y = self.frame.y + if self.frame.h > 1 { 1 } else { 0 };
+        ((hero.x - self.frame.x).abs() + (hero.y - y).abs()) as f32 <= range
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{constants::{HERO_ENTITY_ID, SPRITE_SHEET_HUMANOIDS_1X1}, entities::species::{EntityType, Species}, features::weapons::Weapon, game_engine::storage::StorageValue, testing::{fixtures::{fixture_species, WorldFixture}, harness::TestHarness}};
+
+    const SPECIES_FIXTURE_ARCHER: u32 = 900_601;
+    const SPECIES_FIXTURE_ARROW: u32 = 900_602;
+    const SPECIES_FIXTURE_BYSTANDER: u32 = 900_603;
+
+    #[test]
+    fn creeps_aim_then_shoot_the_hero_when_in_line_of_sight() {
+        let fixture = WorldFixture::new(900_600)
+            .biomes(&["1111111111"; 6])
+            .entities(&["", "", "", "....b..a"])
+            .legend('a', SPECIES_FIXTURE_ARCHER)
+            .legend('b', SPECIES_FIXTURE_BYSTANDER)
+            .species(Species {
+                ranged_attacks_hero: true,
+                sprite_sheet_id: SPRITE_SHEET_HUMANOIDS_1X1,
+                weapon: Some(Weapon { projectile: SPECIES_FIXTURE_ARROW, is_melee: false, cooldown: 3.0, range: 8.0, ammo: None, spread: 0, pierce: 0, windup: 1.0 }),
+                ..fixture_species(SPECIES_FIXTURE_ARCHER, EntityType::Npc, false)
+            })
+            .species(Species { base_speed: 6.0, damage: 2, ..fixture_species(SPECIES_FIXTURE_ARROW, EntityType::Bullet, false) })
+            .species(fixture_species(SPECIES_FIXTURE_BYSTANDER, EntityType::Npc, false));
+
+        let mut game = TestHarness::with_fixture(fixture, 2, 2);
+        let archer = |game: &TestHarness| {
+            let entities = game.engine.world.entities.borrow();
+            let archer = entities.iter().find(|entity| entity.species_id == SPECIES_FIXTURE_ARCHER).unwrap();
+            (archer.is_aiming(), archer.demands_attention, archer.sprite.frame.y)
+        };
+        assert_eq!(archer(&game), (true, false, 8));
+        game.assert_entity_count(SPECIES_FIXTURE_ARROW, 0);
+
+        game.wait(1.0);
+        assert!(!archer(&game).0);
+        assert_eq!(game.engine.world.cached_hero_props.hp, 4);
+        game.assert_hero_at(0, 2)
+            .assert_entity_count(SPECIES_FIXTURE_ARROW, 0)
+            .assert_storage("hero.hp", Some(StorageValue::Int(4)));
+
+        let entities = game.engine.world.entities.borrow();
+        let hero = game.engine.world.index_for_entity(HERO_ENTITY_ID).unwrap();
+        assert!(entities[hero].time_invulnerable > 0.0);
+        assert!(entities.iter().any(|entity| entity.species_id == SPECIES_FIXTURE_BYSTANDER && !entity.is_dying));
+    }
+
+    #[test]
+    fn bullets_are_consumed_by_an_invulnerable_hero() {
+        let fixture = WorldFixture::new(900_601)
+            .biomes(&["1111111111"; 6])
+            .entities(&["", "", "", ".......a"])
+            .legend('a', SPECIES_FIXTURE_ARCHER)
+            .species(Species {
+                ranged_attacks_hero: true,
+                sprite_sheet_id: SPRITE_SHEET_HUMANOIDS_1X1,
+                weapon: Some(Weapon { projectile: SPECIES_FIXTURE_ARROW, is_melee: false, cooldown: 3.0, range: 8.0, ammo: None, spread: 0, pierce: 0, windup: 1.0 }),
+                ..fixture_species(SPECIES_FIXTURE_ARCHER, EntityType::Npc, false)
+            })
+            .species(Species { base_speed: 6.0, damage: 2, ..fixture_species(SPECIES_FIXTURE_ARROW, EntityType::Bullet, false) });
+
+        let mut game = TestHarness::with_fixture(fixture, 2, 2);
+        let hero = game.engine.world.index_for_entity(HERO_ENTITY_ID).unwrap();
+        game.engine.world.entities.borrow_mut()[hero].is_invulnerable = true;
+
+        for _ in 0..30 {
+            game.wait(0.05);
+            let entities = game.engine.world.entities.borrow();
+            assert!(entities.iter().all(|entity| entity.species_id != SPECIES_FIXTURE_ARROW || entity.frame.x >= 2));
+        }
+        assert_eq!(game.engine.world.cached_hero_props.hp, 6);
+        game.assert_hero_at(2, 2).assert_entity_count(SPECIES_FIXTURE_ARROW, 0);
+    }
+}
@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{constants::{ANIMATIONS_FPS, BASE_ENTITY_SPEED, TILE_SIZE}, entities::{known_species::SPECIES_KUNAI, species::SpeciesId}, game_engine::{entity::{Entity, EntityId}, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::StorageKey, world::World}, lang::localizable::LocalizableText, menus::toasts::Toast, utils::{directions::Direction, rect::IntRect}};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Weapon {
+    /// Species spawned on attack, the bullet of ranged weapons or the swing of melee ones
+    pub projectile: SpeciesId,
+
+    #[serde(default)]
+    pub is_melee: bool,
+
+    /// Seconds between attacks
+    pub cooldown: f32,
+
+    /// Tiles travelled by projectiles before they disappear, or reach of a swing in front of the hero
+    pub range: f32,
+
+    /// Species consumed by each attack, if any
+    #[serde(default)]
+    pub ammo: Option<SpeciesId>,
+
+    /// Extra projectiles fired in the lanes next to the first one, alternating sides
+    #[serde(default)]
+    pub spread: u32,
+
+    /// Targets a projectile goes through before it's spent
+    #[serde(default)]
+    pub pierce: u32,
+
+    /// Seconds creeps spend aiming, with an alert over their head, before they fire
+    #[serde(default)]
+    pub windup: f32,
+}
+
+impl World {
+    /// Kunai are equipped until the hero picks another weapon.
+    pub fn equipped_weapon(&self) -> SpeciesId {
+        self.context.storage.get(&StorageKey::hero_weapon())
+            .and_then(|value| value.as_int())
+            .map(|species_id| species_id as SpeciesId)
+            .unwrap_or(SPECIES_KUNAI)
+    }
+
+    /// Species without a weapon can't be equipped, nothing happens then.
+    pub fn equip_weapon(&mut self, species_id: SpeciesId) -> Option<EngineStateUpdate> {
+        let species = self.context.species.get(species_id);
+        species.weapon.as_ref()?;
+        self.context.storage.set(&StorageKey::hero_weapon(), species_id);
+        let strings = &self.context.strings;
+        let name = species.localized_name(strings);
+        Some(EngineStateUpdate::Toast(Toast::regular("weapons.equipped".localized(strings).replace("%s", &name))))
+    }
+}
+
+impl Entity {
+    pub fn attack(&mut self, world: &World, time_since_last_update: f32) -> Vec<WorldStateUpdate> {
+        self.shooting_cooldown_remaining -= time_since_last_update;
+
+        if self.shooting_cooldown_remaining > 0.0 {
+            return vec![]
+        }
+        if !world.has_attack_key_been_pressed {
+            return vec![]
+        }
+        let weapon_id = world.equipped_weapon();
+        let Some(weapon) = world.context.species.get(weapon_id).weapon.clone() else {
+            return vec![]
+        };
+        if !world.context.inventory.contains_species(weapon_id) {
+            return vec![]
+        }
+        if let Some(ammo) = weapon.ammo {
+            if !world.context.inventory.contains_species(ammo) {
+                return vec![]
+            }
+            world.context.inventory.remove_one_of_species(ammo);
+        }
+        self.shooting_cooldown_remaining = weapon.cooldown;
+
+        if weapon.is_melee {
+            self.swing(world, &weapon)
+        } else {
+            self.shoot(world, &weapon)
+        }
+    }
+
+    /// Fires from the lowest row of the entity, towards where it's facing.
+    pub fn shoot(&self, world: &World, weapon: &Weapon) -> Vec<WorldStateUpdate> {
+        let direction = self.direction;
+        let (dx, dy) = direction.as_col_row_offset();
+        let origin = self.frame.offset(dx, dy).offset_y(self.frame.h - 1).with_h(1);
+
+        lanes(weapon.spread)
+            .map(|lane| origin.offset(lane * dy.abs(), lane * dx.abs()))
+            .filter(|frame| world.bounds.contains_or_touches_tile(frame.x, frame.y))
+            .map(|frame| {
+                let mut bullet = world.context.make_entity(weapon.projectile);
+                bullet.direction = direction;
+                bullet.frame = frame;
+                bullet.offset = self.offset;
+                bullet.parent_id = self.id;
+                bullet.remaining_pierce = weapon.pierce;
+                bullet.reset_speed(&world.context.species);
+                let tiles_per_second = bullet.current_speed * BASE_ENTITY_SPEED / TILE_SIZE;
+                bullet.remaining_lifespan = weapon.range / tiles_per_second.max(1.0);
+                WorldStateUpdate::AddEntity(Box::new(bullet))
+            })
+            .collect()
+    }
+
+    fn swing(&self, world: &World, weapon: &Weapon) -> Vec<WorldStateUpdate> {
+        let species = world.context.species.get(weapon.projectile);
+        let mut swing = species.make_entity(&world.context.strings);
+        let (w, h) = (swing.frame.w, swing.frame.h);
+        let bounds = world.bounds;
+        swing.frame = IntRect::new(
+            (self.frame.x - w / 2).min(bounds.x + bounds.w - w).max(bounds.x),
+            (self.frame.y + self.frame.h - h).min(bounds.y + bounds.h - h).max(bounds.y),
+            w,
+            h
+        );
+        swing.parent_id = self.id;
+        swing.remaining_lifespan = species.sprite_number_of_frames as f32 / ANIMATIONS_FPS;
+
+        let area = self.area_in_front(weapon.range as i32);
+        let mut targets: Vec<EntityId> = vec![];
+
+        for y in area.y..(area.y + area.h) {
+            for x in area.x..(area.x + area.w) {
+                if !world.bounds.contains_or_touches_tile(x, y) {
+                    continue
+                }
+                let hit = world.entities_map[y as usize][x as usize];
+                if hit != 0 && hit != self.id && !targets.contains(&hit) {
+                    targets.push(hit);
+                }
+            }
+        }
+
+        let mut updates = vec![WorldStateUpdate::AddEntity(Box::new(swing))];
+        updates.extend(targets.into_iter().map(|target| WorldStateUpdate::HandleMeleeHit(weapon.projectile, target)));
+        updates
+    }
+
+    /// Tiles within `reach` in front of the entity, as wide as the entity itself, empty if it's not facing anywhere.
+    fn area_in_front(&self, reach: i32) -> IntRect {
+        let frame = self.frame;
+        match self.direction {
+            Direction::Up => IntRect::new(frame.x, frame.y - reach, frame.w, reach),
+            Direction::Right => IntRect::new(frame.x + frame.w, frame.y, reach, frame.h),
+            Direction::Down => IntRect::new(frame.x, frame.y + frame.h, frame.w, reach),
+            Direction::Left => IntRect::new(frame.x - reach, frame.y, reach, frame.h),
+            Direction::Unknown | Direction::Still => IntRect::new(frame.x, frame.y, 0, 0)
+        }
+    }
+}
+
+/// Offsets from the aimed lane: 0, 1, -1, 2, -2...
+fn lanes(spread: u32) -> impl Iterator<Item = i32> {
+    (0..=spread as i32).map(|i| if i % 2 == 1 { (i + 1) / 2 } else { -i / 2 })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{constants::HERO_ENTITY_ID, entities::{known_species::SPECIES_SWORD, species::{EntityType, Species}}, game_engine::{state_updates::WorldStateUpdate, storage::StorageKey}, testing::{fixtures::{fixture_species, WorldFixture}, harness::TestHarness}, utils::{directions::Direction, rect::IntRect}};
+
+    use super::Weapon;
+
+    const SPECIES_FIXTURE_LAUNCHER: u32 = 900_501;
+    const SPECIES_FIXTURE_DART: u32 = 900_502;
+    const SPECIES_FIXTURE_AMMO: u32 = 900_503;
+    const SPECIES_FIXTURE_SWORD: u32 = 900_504;
+    const SPECIES_FIXTURE_SWING: u32 = 900_505;
+    const SPECIES_FIXTURE_CREEP: u32 = 900_506;
+
+    fn weapon(projectile: u32, cooldown: f32, range: f32) -> Weapon {
+        Weapon { projectile, is_melee: false, cooldown, range, ammo: None, spread: 0, pierce: 0, windup: 0.0 }
+    }
+
+    fn creeps_hp(game: &TestHarness) -> Vec<u32> {
+        game.engine.world.entities.borrow().iter()
+            .filter(|entity| entity.species_id == SPECIES_FIXTURE_CREEP && !entity.is_dying)
+            .map(|entity| entity.hp)
+            .collect()
+    }
+
+    #[test]
+    fn ranged_weapons_spread_pierce_and_use_ammo() {
+        let fixture = WorldFixture::new(900_500)
+            .biomes(&["1111111111"; 10])
+            .entities(&["", "", "", "", "", "", "..c", "..c"])
+            .legend('c', SPECIES_FIXTURE_CREEP)
+            .storage(&StorageKey::hero_weapon(), SPECIES_FIXTURE_LAUNCHER)
+            .species(Species {
+                weapon: Some(Weapon { ammo: Some(SPECIES_FIXTURE_AMMO), spread: 2, pierce: 1, ..weapon(SPECIES_FIXTURE_DART, 0.5, 6.0) }),
+                ..fixture_species(SPECIES_FIXTURE_LAUNCHER, EntityType::PickableObject, false)
+            })
+            .species(Species { base_speed: 6.0, ..fixture_species(SPECIES_FIXTURE_DART, EntityType::Bullet, false) })
+            .species(fixture_species(SPECIES_FIXTURE_AMMO, EntityType::PickableObject, false))
+            .species(fixture_species(SPECIES_FIXTURE_CREEP, EntityType::Npc, true));
+
+        let mut game = TestHarness::with_fixture(fixture, 2, 2);
+        let context = game.engine.context.clone();
+        context.inventory.add(context.make_entity(SPECIES_FIXTURE_LAUNCHER), &context.ids);
+        context.inventory.add(context.make_entity(SPECIES_FIXTURE_AMMO), &context.ids);
+
+        game.attack()
+            .assert_inventory_count(SPECIES_FIXTURE_AMMO, 0)
+            .assert_entity_count(SPECIES_FIXTURE_DART, 3);
+        let mut lanes: Vec<i32> = game.engine.world.entities.borrow().iter()
+            .filter(|entity| entity.species_id == SPECIES_FIXTURE_DART)
+            .map(|entity| entity.frame.x)
+            .collect();
+        lanes.sort();
+        assert_eq!(lanes, vec![1, 2, 3]);
+
+        game.wait(1.0);
+        assert!(creeps_hp(&game).is_empty());
+        game.assert_entity_count(SPECIES_FIXTURE_DART, 0);
+
+        game.attack().assert_entity_count(SPECIES_FIXTURE_DART, 0);
+    }
+
+    #[test]
+    fn melee_weapons_swing_at_creeps_within_reach_in_front() {
+        let fixture = WorldFixture::new(900_510)
+            .biomes(&["1111111111"; 6])
+            .entities(&["", "", "", ".c.c..c"])
+            .legend('c', SPECIES_FIXTURE_CREEP)
+            .species(Species {
+                weapon: Some(Weapon { is_melee: true, ..weapon(SPECIES_FIXTURE_SWING, 0.4, 1.0) }),
+                ..fixture_species(SPECIES_FIXTURE_SWORD, EntityType::PickableObject, false)
+            })
+            .species(Species { damage: 2, sprite_number_of_frames: 3, is_invulnerable: true, ..fixture_species(SPECIES_FIXTURE_SWING, EntityType::Effect, false) })
+            .species(Species { hp: 3, ..fixture_species(SPECIES_FIXTURE_CREEP, EntityType::Npc, true) });
+
+        let mut game = TestHarness::with_fixture(fixture, 2, 2);
+        let context = game.engine.context.clone();
+        context.inventory.add(context.make_entity(SPECIES_FIXTURE_SWORD), &context.ids);
+        game.engine.world.apply_state_updates(vec![WorldStateUpdate::EquipWeapon(SPECIES_FIXTURE_CREEP)]);
+        game.assert_storage(&StorageKey::hero_weapon(), None);
+        game.engine.world.apply_state_updates(vec![WorldStateUpdate::EquipWeapon(SPECIES_FIXTURE_SWORD)]);
+        game.assert_storage(&StorageKey::hero_weapon(), Some(SPECIES_FIXTURE_SWORD.into()));
+
+        game.face(Direction::Right).attack().assert_entity_count(SPECIES_FIXTURE_SWING, 1);
+        assert_eq!(creeps_hp(&game), vec![3, 1, 3]);
+
+        game.wait(0.5).assert_entity_count(SPECIES_FIXTURE_SWING, 0);
+        game.attack();
+        assert_eq!(creeps_hp(&game), vec![3, 3]);
+
+        game.wait(0.5).face(Direction::Left).attack();
+        assert_eq!(creeps_hp(&game), vec![1, 3]);
+        game.assert_inventory_count(SPECIES_FIXTURE_SWORD, 1);
+    }
+
+    #[test]
+    fn swings_stay_within_the_world_at_its_edges() {
+        let fixture = WorldFixture::new(900_511)
+            .biomes(&["111111"; 5])
+            .entities(&["", "", ".c"])
+            .legend('c', SPECIES_FIXTURE_CREEP)
+            .storage(&StorageKey::hero_weapon(), SPECIES_FIXTURE_SWORD)
+            .species(Species {
+                weapon: Some(Weapon { is_melee: true, ..weapon(SPECIES_FIXTURE_SWING, 0.4, 1.0) }),
+                ..fixture_species(SPECIES_FIXTURE_SWORD, EntityType::PickableObject, false)
+            })
+            .species(Species {
+                damage: 1,
+                sprite_frame: IntRect::new(0, 0, 5, 2),
+                sprite_number_of_frames: 3,
+                ..fixture_species(SPECIES_FIXTURE_SWING, EntityType::Effect, false)
+            })
+            .species(Species { hp: 3, ..fixture_species(SPECIES_FIXTURE_CREEP, EntityType::Npc, true) });
+
+        let mut game = TestHarness::with_fixture(fixture, 0, 1);
+        let context = game.engine.context.clone();
+        context.inventory.add(context.make_entity(SPECIES_FIXTURE_SWORD), &context.ids);
+
+        game.face(Direction::Right).attack().assert_entity_count(SPECIES_FIXTURE_SWING, 1);
+        let swing_frame = game.engine.world.entities.borrow().iter()
+            .find(|entity| entity.species_id == SPECIES_FIXTURE_SWING)
+            .map(|entity| entity.frame)
+            .unwrap();
+        assert_eq!(swing_frame, IntRect::new(0, 1, 5, 2));
+        assert_eq!(game.engine.world.entities_map[2][0], HERO_ENTITY_ID);
+        assert_eq!(creeps_hp(&game), vec![2]);
+    }
+
+    #[test]
+    fn old_man_gives_his_sword_once_then_goes_back_to_his_usual_line() {
+        let mut game = TestHarness::new();
+        game.teleport(1002, 37, 38).walk(Direction::Up, 1);
+        game.engine.context.storage.set("dialogue.reward.1002.old_man.damn_monsters", 1u32);
+
+        game.interact().assert_dialogue_open(true);
+        assert_eq!(game.engine.dialogue_menu.dialogue.text, "1002.old_man.take_my_sword");
+        game.wait(1.0)
+            .choose("ok")
+            .assert_dialogue_open(false)
+            .assert_inventory_count(SPECIES_SWORD, 1);
+
+        game.wait(1.0).interact().assert_dialogue_open(true);
+        assert_eq!(game.engine.dialogue_menu.dialogue.text, "1002.old_man.damn_monsters");
+        game.wait(1.0).choose("ok").assert_inventory_count(SPECIES_SWORD, 1);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{config::Config, log_debug, utils::{files::set_aside_damaged, logger::{Logger, Subsystem}}, worlds::{world_cache::WorldCache, world_overlay::WorldOverlays}, utils::ids::IdGenerator, entities::species::{EntityType, SpeciesId, SpeciesRegistry}, lang::localizable::LocalizedStrings};
+
+use super::{entity::Entity, errors::{GameError, GameResult}, events::EventBus, inventory::InventoryStorage, save_slots::adopt_legacy_save, storage::KeyValueStorage};
+
+pub struct GameContext {
+    pub config: Config,
+    pub storage: KeyValueStorage,
+    pub inventory: InventoryStorage,
+    pub ids: IdGenerator,
+    pub events: EventBus,
+    pub worlds: WorldCache,
+    pub overlays: WorldOverlays,
+    pub species: SpeciesRegistry,
+    pub strings: LocalizedStrings,
+    pub logger: Logger,
+    /// Save files that could not be parsed, they are set aside and the game starts a new save in their place
+    pub damaged_files: Vec<GameError>,
+}
+
+impl GameContext {
+    pub fn new(config: Config) -> GameResult<Self> {
+        let logger = Logger::new(config.logging.clone());
+        let mut damaged_files = vec![];
+        let storage = unless_damaged(KeyValueStorage::load(&config.key_value_storage_path, &logger), &mut damaged_files)?.map_or_else(|| KeyValueStorage::load(&config.key_value_storage_path, &logger), Ok)?;
+        let inventory = unless_damaged(InventoryStorage::load(&config.inventory_path, &logger), &mut damaged_files)?.map_or_else(|| InventoryStorage::load(&config.inventory_path, &logger), Ok)?;
+        let overlays = WorldOverlays::load(&config.overlays_path());
+        if config.uses_save_slots() {
+            adopt_legacy_save(&config, &storage)?;
+        }
+        Ok(Self { overlays, damaged_files, ..Self::with_logger(config, logger, storage, inventory)? })
+    }
+
+    pub fn with_storage(config: Config, storage: KeyValueStorage, inventory: InventoryStorage) -> GameResult<Self> {
+        let logger = Logger::new(config.logging.clone());
+        Self::with_logger(config, logger, storage, inventory)
+    }
+
+    fn with_logger(config: Config, logger: Logger, storage: KeyValueStorage, inventory: InventoryStorage) -> GameResult<Self> {
+        let species = SpeciesRegistry::load(&config.species_path)?;
+        let strings = LocalizedStrings::load(&config.localized_strings_path, &config.current_lang)?;
+        log_debug!(logger, Subsystem::Lang, "Lang folder: {:?}", config.localized_strings_path);
+
+        Ok(Self { config, storage, inventory, ids: IdGenerator::new(), events: EventBus::new(), worlds: WorldCache::new(), overlays: WorldOverlays::in_memory(), species, strings, logger, damaged_files: vec![] })
+    }
+
+    pub fn in_memory(species: SpeciesRegistry, strings: LocalizedStrings, storage: KeyValueStorage) -> Self {
+        let config = Config::in_memory();
+        let logger = Logger::new(config.logging.clone());
+        Self { config, storage, inventory: InventoryStorage::in_memory(), ids: IdGenerator::new(), events: EventBus::new(), worlds: WorldCache::new(), overlays: WorldOverlays::in_memory(), species, strings, logger, damaged_files: vec![] }
+    }
+
+    pub fn make_entity(&self, species_id: SpeciesId) -> Entity {
+        self.species.get(species_id).make_entity(&self.strings)
+    }
+
+    /// Bundles are opened, only their contents end up in the inventory
+    pub fn add_to_inventory(&self, entity: Entity) {
+        if matches!(entity.entity_type, EntityType::Bundle) {
+            for species_id in &self.species.get(entity.species_id).bundle_contents {
+                self.add_to_inventory(self.make_entity(*species_id));
+            }
+        } else {
+            self.inventory.add(entity, &self.ids);
+        }
+    }
+}
+
+/// Damaged files are set aside, so that progress is saved in their place from now on.
+fn unless_damaged<T>(result: GameResult<T>, damaged_files: &mut Vec<GameError>) -> GameResult<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(GameError::InvalidData(path, reason)) => {
+            set_aside_damaged(&path)?;
+            damaged_files.push(GameError::InvalidData(path, reason));
+            Ok(None)
+        }
+        Err(error) => Err(error)
+    }
+}
+
+#[cfg(test)]
+impl GameContext {
+    pub fn for_tests() -> Self {
+        Self::with_storage(Config::for_tests(), KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap()
+    }
+}
@@ -1,14 +1,18 @@
-use crate::{constants::{INITIAL_CAMERA_VIEWPORT, TILE_SIZE, WORLD_ID_NONE}, dialogues::{menu::DialogueMenu, models::Dialogue}, features::{creep_spawner::CreepSpawner, death_screen::DeathScreen, destination::Destination, loading_screen::LoadingScreen}, menus::{confirmation::ConfirmationDialog, entity_options::EntityOptionsMenu, game_menu::GameMenu, long_text_display::LongTextDisplay, toasts::{Toast, ToastDisplay}}, utils::{rect::IntRect, vector::Vector2d}};
+use std::{path::Path, sync::Arc};
 
-use super::{inventory::{add_to_inventory, remove_from_inventory}, keyboard_events_provider::{KeyboardEventsProvider, NO_KEYBOARD_EVENTS}, mouse_events_provider::MouseEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::{get_value_for_key, set_value_for_key, StorageKey}, world::World};
+use crate::{log_error, log_info, utils::logger::Subsystem, config::Config, constants::{INITIAL_CAMERA_VIEWPORT, TILE_SIZE, WORLD_ID_NONE, WORLD_PRELOAD_DISTANCE}, dialogues::{menu::DialogueMenu, models::Dialogue}, lang::localizable::LocalizableText, features::{creep_spawner::CreepSpawner, death_screen::DeathScreen, destination::Destination, loading_screen::LoadingScreen}, menus::{confirmation::ConfirmationDialog, entity_options::EntityOptionsMenu, game_menu::GameMenu, long_text_display::LongTextDisplay, slot_picker::SlotPicker, toasts::{Toast, ToastDisplay}}, utils::{directions::Direction, files::{latest_valid_backup, restore_latest_backup}, rect::IntRect, vector::Vector2d}, worlds::world_overlay::WorldChanges};
+
+use super::{context::GameContext, errors::{GameError, GameResult}, events::StateUpdate, keyboard_events_provider::{KeyboardEventsProvider, NO_KEYBOARD_EVENTS}, mouse_events_provider::MouseEventsProvider, replay::{InputEvent, InputRecorder}, save_slots::{save_slots, SaveSlot, SlotMetadata}, simulation::SimulationMode, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::StorageKey, world::World};
 
 pub struct GameEngine {
+    pub context: Arc<GameContext>,
     pub menu: GameMenu,
     pub world: World,
     pub loading_screen: LoadingScreen,
     pub long_text_display: LongTextDisplay,
     pub confirmation_dialog: ConfirmationDialog,
     pub death_screen: DeathScreen,
+    pub slot_picker: SlotPicker,
     pub dialogue_menu: DialogueMenu,
     pub toast: ToastDisplay,
     pub creep_spawner: CreepSpawner,
@@ -19,44 +23,175 @@ pub struct GameEngine {
     pub camera_viewport_offset: Vector2d,
     pub is_running: bool,
     pub creative_mode: bool,
+    pub simulation: SimulationMode,
+    pub recorder: Option<InputRecorder>,
+    pub last_error: Option<GameError>,
+    pub current_slot: Option<u32>,
+    pub playtime: f32,
 }
 
 impl GameEngine {
-    pub fn new() -> Self {
-        Self {
-            menu: GameMenu::new(),
-            world: World::load_or_create(WORLD_ID_NONE),
+    pub fn new(config: Config, simulation: SimulationMode) -> GameResult<Self> {
+        Ok(Self::with_context(GameContext::new(config)?, simulation))
+    }
+
+    pub fn with_context(mut context: GameContext, simulation: SimulationMode) -> Self {
+        context.ids = simulation.ids();
+        let context = Arc::new(context);
+        let (world, error) = match World::load_or_create(WORLD_ID_NONE, context.clone()) {
+            Ok(world) => (world, None),
+            Err(error) => (World::new(WORLD_ID_NONE, context.clone()), Some(error))
+        };
+
+        let mut engine = Self {
+            menu: GameMenu::new(&context),
+            world,
             loading_screen: LoadingScreen::new(),
             long_text_display: LongTextDisplay::new(50, 9),
             confirmation_dialog: ConfirmationDialog::new(),
-            death_screen: DeathScreen::new(),
+            death_screen: DeathScreen::new(&context.strings),
+            slot_picker: SlotPicker::new(&context.strings),
             dialogue_menu: DialogueMenu::new(),
             toast: ToastDisplay::new(),
-            creep_spawner: CreepSpawner::new(),
-            entity_options_menu: EntityOptionsMenu::new(),
+            creep_spawner: CreepSpawner::new(simulation.rng()),
+            entity_options_menu: EntityOptionsMenu::new(&context.strings),
             keyboard: KeyboardEventsProvider::new(),
             mouse: MouseEventsProvider::new(),
             camera_viewport: INITIAL_CAMERA_VIEWPORT,
             camera_viewport_offset: Vector2d::zero(),
             is_running: true,
-            creative_mode: false
+            creative_mode: false,
+            simulation,
+            recorder: None,
+            last_error: None,
+            current_slot: None,
+            playtime: 0.0,
+            context,
+        };
+        for error in engine.context.damaged_files.clone().iter().chain(error.iter()) {
+            engine.show_error(error);
         }
+        engine
     }
 
     pub fn start(&mut self) {
+        self.record(InputEvent::Start);
+
+        if self.context.config.uses_save_slots() && self.current_slot.is_none() {
+            self.show_slot_picker();
+        } else {
+            self.teleport_to_previous();
+        }
+    }
+
+    fn show_slot_picker(&mut self) {
+        let slots = save_slots(&self.context.config)
+            .into_iter()
+            .map(|slot| {
+                let metadata = slot.metadata().unwrap_or_else(|error| {
+                    self.show_error(&error);
+                    None
+                });
+                (slot, metadata)
+            })
+            .collect();
+        self.slot_picker.show(slots);
+    }
+
+    /// Loads storage and inventory of the given slot and resumes from where it was left.
+    /// Returns false, and reports the error, if the slot could not be loaded.
+    pub fn select_slot(&mut self, id: u32) -> bool {
+        let metadata = match self.switch_to_slot(id) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                self.show_error(&error);
+                return false
+            }
+        };
+        self.playtime = metadata.map(|m| m.playtime).unwrap_or_default();
+        self.restart_recording();
         self.teleport_to_previous();
+        true
+    }
+
+    /// Points storage, inventory and overlays to the given slot, without leaving the current world.
+    pub(super) fn switch_to_slot(&mut self, id: u32) -> GameResult<Option<SlotMetadata>> {
+        let slot = SaveSlot::new(id, &self.context.config.saves_path);
+        let metadata = slot.metadata()?;
+        slot.prepare()?;
+        self.detach_world_from_save();
+        self.context.storage.switch_to(&slot.storage_path(), &self.context.logger)?;
+        self.context.inventory.switch_to(&slot.inventory_path(), &self.context.logger)?;
+        self.context.overlays.switch_to(&slot.overlays_path());
+        self.back_up_progress();
+
+        log_info!(self.context.logger, Subsystem::Engine, "Selected save slot {}", id);
+        self.current_slot = Some(id);
+        self.slot_picker.close();
+        Ok(metadata)
+    }
+
+    /// The current world keeps showing until the next teleport, but its changes belong to the previous save.
+    pub(super) fn detach_world_from_save(&mut self) {
+        self.world.changes = WorldChanges::untracked();
+    }
+
+    pub fn save_slot_metadata(&mut self) {
+        let Some(id) = self.current_slot else { return };
+        let slot = SaveSlot::new(id, &self.context.config.saves_path);
+
+        if let Err(error) = slot.save_metadata(&SlotMetadata::new(id, self.playtime, &self.world)) {
+            self.show_error(&error);
+        }
+    }
+
+    /// Options of the menu that currently gets keyboard input, in the order they are listed
+    pub fn menu_options(&self) -> Vec<String> {
+        let strings = &self.context.strings;
+
+        if self.confirmation_dialog.is_open() {
+            self.confirmation_dialog.options(strings)
+        } else if self.slot_picker.is_open() {
+            self.slot_picker.options(strings)
+        } else if self.death_screen.is_open() {
+            self.death_screen.options(strings)
+        } else if self.dialogue_menu.is_open() {
+            self.dialogue_menu.options(strings)
+        } else if self.entity_options_menu.is_open() {
+            self.entity_options_menu.options(strings)
+        } else {
+            self.menu.options(strings)
+        }
     }
 
     pub fn set_creative_mode(&mut self, enabled: bool) {
+        self.record(InputEvent::CreativeMode { enabled });
         self.menu.set_creative_mode(enabled);
+        self.context.overlays.set_enabled(!enabled);
         self.world.set_creative_mode(enabled);
         self.creative_mode = enabled;
     }
 
-    pub fn update(&mut self, time_since_last_update: f32) {        
+    pub fn update(&mut self, time_since_last_update: f32) {
+        self.record(InputEvent::Update { time_since_last_update });
+        let time_since_last_update = self.simulation.time_step(time_since_last_update);
+        self.context.events.advance(time_since_last_update);
         self.toast.update(time_since_last_update);
 
-        if self.death_screen.is_open {
+        if self.slot_picker.is_open() {
+            if self.confirmation_dialog.is_open() {
+                self.update_confirmation_dialog(time_since_last_update);
+            } else if let Some(id) = self.slot_picker.update(&self.keyboard, time_since_last_update) {
+                self.select_slot(id);
+            }
+            return;
+        }
+        self.playtime += time_since_last_update;
+
+        if self.death_screen.is_open() {
+            if let Some(option) = self.death_screen.update(&self.keyboard, time_since_last_update) {
+                self.respawn(option);
+            }
             return;
         }
 
@@ -80,8 +215,91 @@ impl GameEngine {
         let creeps_world_updates = self.creep_spawner.update(&self.world, time_since_last_update);
         let creeps_engine_updates = self.world.apply_state_updates(creeps_world_updates);
         self.apply_state_updates(creeps_engine_updates);
+
+        self.preload_nearby_destinations();
     } 
 
+    fn preload_nearby_destinations(&self) {
+        if self.context.config.is_in_memory() {
+            return
+        }
+        let hero = self.world.cached_hero_props.frame;
+        let area = IntRect::new(
+            hero.x - WORLD_PRELOAD_DISTANCE, 
+            hero.y - WORLD_PRELOAD_DISTANCE, 
+            hero.w + 2 * WORLD_PRELOAD_DISTANCE, 
+            hero.h + 2 * WORLD_PRELOAD_DISTANCE
+        );
+        for world_id in self.world.destinations_near(&area) {
+            self.context.worlds.preload(world_id, self.context.config.world_path(world_id), &self.context.logger);
+        }
+    }
+
+    pub fn can_render_frame(&self) -> bool {
+        !self.loading_screen.is_in_progress() || self.loading_screen.progress() > 0.4
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_keyboard(
+        &mut self,
+        up_pressed: bool,
+        right_pressed: bool,
+        down_pressed: bool,
+        left_pressed: bool,
+        up_down: bool,
+        right_down: bool,
+        down_down: bool,
+        left_down: bool,
+        escape_pressed: bool,
+        menu_pressed: bool,
+        confirm_pressed: bool,
+        attack_pressed: bool,
+        backspace_pressed: bool,
+        current_char: Option<char>,
+        time_since_last_update: f32
+    ) {
+        self.record(InputEvent::Keyboard { 
+            up_pressed, right_pressed, down_pressed, left_pressed, 
+            up_down, right_down, down_down, left_down, 
+            escape_pressed, menu_pressed, confirm_pressed, attack_pressed, backspace_pressed, 
+            current_char, time_since_last_update 
+        });
+        self.keyboard.update(
+            up_pressed, right_pressed, down_pressed, left_pressed, 
+            up_down, right_down, down_down, left_down, 
+            escape_pressed, menu_pressed, confirm_pressed, attack_pressed, backspace_pressed, 
+            current_char, time_since_last_update
+        );
+    }
+
+    pub fn update_mouse(
+        &mut self, 
+        mouse_left_down: bool, 
+        mouse_left_pressed: bool, 
+        mouse_right_pressed: bool, 
+        mouse_x: f32,
+        mouse_y: f32,
+        rendering_scale: f32
+    ) {
+        self.record(InputEvent::Mouse { 
+            left_down: mouse_left_down, 
+            left_pressed: mouse_left_pressed, 
+            right_pressed: mouse_right_pressed, 
+            x: mouse_x, 
+            y: mouse_y, 
+            rendering_scale 
+        });
+        self.mouse.update(mouse_left_down, mouse_left_pressed, mouse_right_pressed, mouse_x, mouse_y, rendering_scale);
+    }
+
+    fn record(&mut self, event: InputEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record(&event) {
+                log_error!(self.context.logger, Subsystem::Replay, "Failed to record input event: {}", e);
+            }
+        }
+    }
+
     fn update_menus(&mut self, time_since_last_update: f32) -> bool {
         let mut is_game_paused = false;
 
@@ -92,16 +310,12 @@ impl GameEngine {
         }
 
         if !is_game_paused {
-            let keyboard = if self.confirmation_dialog.is_open() { &self.keyboard } else { &NO_KEYBOARD_EVENTS };
-            let (pause, world_updates) = self.confirmation_dialog.update(keyboard, time_since_last_update);
-            is_game_paused = is_game_paused || pause;
-            let engine_updates = self.world.apply_state_updates(world_updates);
-            self.apply_state_updates(engine_updates);
+            is_game_paused = self.update_confirmation_dialog(time_since_last_update);
         }
 
         if !is_game_paused {
             let keyboard = if self.dialogue_menu.is_open() { &self.keyboard } else { &NO_KEYBOARD_EVENTS };
-            let (pause, world_updates) = self.dialogue_menu.update(keyboard, time_since_last_update);
+            let (pause, world_updates) = self.dialogue_menu.update(keyboard, &self.context, time_since_last_update);
             is_game_paused = is_game_paused || pause;
             let engine_updates = self.world.apply_state_updates(world_updates);
             self.apply_state_updates(engine_updates);
@@ -118,7 +332,7 @@ impl GameEngine {
         if !is_game_paused {
             let can_handle = self.menu.is_open() || self.keyboard.has_menu_been_pressed;
             let keyboard = if can_handle { &self.keyboard } else { &NO_KEYBOARD_EVENTS };
-            let (pause, world_updates) = self.menu.update(&self.camera_viewport, keyboard, &self.mouse, time_since_last_update);
+            let (pause, world_updates) = self.menu.update(&self.camera_viewport, keyboard, &self.mouse, &self.context, time_since_last_update);
             is_game_paused = is_game_paused || pause;
             let engine_updates = self.world.apply_state_updates(world_updates);
             self.apply_state_updates(engine_updates);
@@ -127,14 +341,43 @@ impl GameEngine {
         is_game_paused
     }
 
+    fn update_confirmation_dialog(&mut self, time_since_last_update: f32) -> bool {
+        let keyboard = if self.confirmation_dialog.is_open() { &self.keyboard } else { &NO_KEYBOARD_EVENTS };
+        let (pause, world_updates) = self.confirmation_dialog.update(keyboard, time_since_last_update);
+        let engine_updates = self.world.apply_state_updates(world_updates);
+        self.apply_state_updates(engine_updates);
+        pause
+    }
+
     fn teleport_to_previous(&mut self) {
-        if let Some(world) = get_value_for_key(&StorageKey::latest_world()) {
-            self.teleport(&Destination::new(world, 0, 0));
-        } else {
+        let storage = &self.context.storage;
+        let Some(world) = storage.get_value_for_key(&StorageKey::latest_world()) else {
             self.teleport(&Destination::default());
+            return
+        };
+        let x = storage.get(&StorageKey::hero_x()).and_then(|value| value.as_int());
+        let y = storage.get(&StorageKey::hero_y()).and_then(|value| value.as_int());
+        let direction = storage.get(&StorageKey::hero_direction()).and_then(|value| value.as_int());
+
+        match (x, y, direction) {
+            (Some(x), Some(y), Some(direction)) => {
+                let restored = Some(Direction::from_int(direction));
+                self.enter_world(&Destination::new(world, x as i32, y as i32), restored)
+            }
+            _ => self.teleport(&Destination::new(world, 0, 0))
         }
     }
 
+    /// Remembers exactly where the hero is, so that the next launch resumes from there.
+    fn save_hero_position(&mut self) {
+        let storage = &self.context.storage;
+        let hero = &self.world.cached_hero_props;
+        storage.set_value_for_key(&StorageKey::latest_world(), self.world.id);
+        storage.set(&StorageKey::hero_x(), hero.frame.x as i64);
+        storage.set(&StorageKey::hero_y(), hero.frame.y as i64);
+        storage.set(&StorageKey::hero_direction(), hero.direction as i64);
+    }
+
     pub fn window_size_changed(
         &mut self, 
         width: f32, 
@@ -143,6 +386,7 @@ impl GameEngine {
         font_size: f32, 
         line_spacing: f32
     ) {
+        self.record(InputEvent::WindowSizeChanged { width, height, scale, font_size, line_spacing });
         self.camera_viewport.w = (width / (scale * TILE_SIZE)) as i32;
         self.camera_viewport.h = (height / (scale * TILE_SIZE)) as i32;
         self.long_text_display.max_line_length = (width / font_size).floor() as usize;
@@ -166,15 +410,8 @@ impl GameEngine {
         sorted_updates.iter().for_each(|u| self.apply_state_update(u));
     }
 
-    fn log_update(&self, update: &EngineStateUpdate) {
-        match update {
-            EngineStateUpdate::CenterCamera(_, _, _) => {},
-            _ => println!("Engine update: {:#?}", update)
-        }     
-    }
-
     fn apply_state_update(&mut self, update: &EngineStateUpdate) {   
-        self.log_update(update);
+        self.context.events.emit(self.world.id, StateUpdate::Engine(update));
 
         match update {
             EngineStateUpdate::ShowDialogue(npc_id, npc_name, dialogue) => {
@@ -189,20 +426,29 @@ impl GameEngine {
             EngineStateUpdate::SaveGame => {
                 self.save()
             }
+            EngineStateUpdate::QuickSave => {
+                self.quick_save_from_menu()
+            }
+            EngineStateUpdate::QuickLoad => {
+                self.quick_load_from_menu()
+            }
+            EngineStateUpdate::ExportSave => {
+                self.export_save_bundle_to_bundles_folder()
+            }
             EngineStateUpdate::Exit => {
                 self.exit()
             }
             EngineStateUpdate::ShowEntityOptions(entity) => {
-                self.entity_options_menu.show(entity.clone(), self.creative_mode, false)
+                self.entity_options_menu.show(entity.clone(), self.creative_mode, false, &self.context)
             }
             EngineStateUpdate::ShowInventoryOptions(entity) => {
-                self.entity_options_menu.show(entity.clone(), false, true)
+                self.entity_options_menu.show(entity.clone(), false, true, &self.context)
             }
             EngineStateUpdate::AddToInventory(entity) => {
-                add_to_inventory(*entity.clone())
+                self.context.add_to_inventory(*entity.clone())
             }
             EngineStateUpdate::RemoveFromInventory(entity_id) => {
-                remove_from_inventory(*entity_id)
+                self.context.inventory.remove(*entity_id)
             }
             EngineStateUpdate::ResumeGame => {
                 self.menu.close()
@@ -210,6 +456,12 @@ impl GameEngine {
             EngineStateUpdate::Toast(toast) => {
                 self.show_toast(toast)
             }
+            EngineStateUpdate::Error(error) => {
+                self.show_error(error)
+            }
+            EngineStateUpdate::RestoreBackup(path) => {
+                self.restore_backup(path)
+            }
             EngineStateUpdate::Confirmation(title, text, on_confirm) => {
                 self.ask_for_confirmation(title, text, on_confirm)
             }
@@ -217,7 +469,7 @@ impl GameEngine {
                 self.long_text_display.show(contents.clone())
             }
             EngineStateUpdate::DeathScreen => {
-                self.death_screen.show()
+                self.show_death_screen()
             }
         }
     }
@@ -230,44 +482,144 @@ impl GameEngine {
         self.toast.show(toast);
     }
 
+    pub(super) fn show_error(&mut self, error: &GameError) {
+        log_error!(self.context.logger, Subsystem::Engine, "{}", error);
+        let text = "game.error.loading".localized(&self.context.strings).replace("%s", &error.file_name());
+        self.toast.show(&Toast::important(text));
+        self.last_error = Some(error.clone());
+        self.offer_restore(error);
+    }
+
+    fn offer_restore(&mut self, error: &GameError) {
+        let GameError::InvalidData(path, _) = error else { return };
+        if latest_valid_backup(path).is_none() {
+            return
+        }
+        self.ask_for_confirmation(
+            &"backup.restore.title".localized(&self.context.strings),
+            &"backup.restore.message".localized(&self.context.strings).replace("%s", &error.file_name()),
+            &[WorldStateUpdate::EngineUpdate(EngineStateUpdate::RestoreBackup(path.clone()))]
+        );
+    }
+
+    fn restore_backup(&mut self, path: &Path) {
+        // Pending writes of the current progress would land on top of the restored file
+        self.context.storage.close();
+        self.context.inventory.close();
+
+        match restore_latest_backup(path) {
+            Ok(backup) => {
+                log_info!(self.context.logger, Subsystem::Engine, "Restored {} from {}", path.display(), backup.display());
+                self.toast.show(&Toast::regular("backup.restored".localized(&self.context.strings)));
+            }
+            Err(error) => self.show_error(&error)
+        }
+        self.context.worlds.invalidate_all();
+        self.context.overlays.reload();
+        self.reload_progress();
+    }
+
+    /// Reads the current save again, the slot picker takes care of it while no slot is selected.
+    fn reload_progress(&mut self) {
+        if let Some(id) = self.current_slot {
+            self.select_slot(id);
+            return
+        }
+        if self.context.config.uses_save_slots() {
+            return
+        }
+        self.detach_world_from_save();
+        let config = &self.context.config;
+        let result = self.context.storage
+            .switch_to(&config.key_value_storage_path, &self.context.logger)
+            .and_then(|_| self.context.inventory.switch_to(&config.inventory_path, &self.context.logger));
+
+        match result {
+            Ok(_) => self.teleport_to_previous(),
+            Err(error) => self.show_error(&error)
+        }
+    }
+
     fn show_dialogue(&mut self, npc_id: &u32, npc_name: &str, dialogue: &Dialogue) {
         if self.dialogue_menu.is_open() {
             return
         }
-        self.dialogue_menu.show(*npc_id, npc_name, dialogue);
+        self.dialogue_menu.show(*npc_id, npc_name, dialogue, &self.context.strings);
     }    
 
-    fn exit(&mut self) {
-        println!("Got exit request!");
+    /// Saves progress and waits for it to be written, the engine stops running.
+    pub fn exit(&mut self) {
+        log_info!(self.context.logger, Subsystem::Engine, "Got exit request!");
+        self.save_hero_position();
+        self.save_overlay();
+        self.save_slot_metadata();
+        self.context.storage.close();
+        self.context.inventory.close();
         self.is_running = false;
     }
 
-    fn save(&self) {
+    fn save(&mut self) {
+        self.save_hero_position();
+
         if self.creative_mode {
-            set_value_for_key(&StorageKey::latest_world(), self.world.id);     
             self.world.save();
+        } else {
+            self.save_overlay();
         }
+        self.save_slot_metadata();
+        self.back_up_progress();
     }
 
-    fn teleport(&mut self, destination: &Destination) {
-        self.loading_screen.animate_world_transition();
+    /// Backups are only taken on explicit saves and slot switches, not on every write.
+    fn back_up_progress(&mut self) {
+        let result = self.context.storage.back_up().and_then(|_| self.context.inventory.back_up());
+
+        if let Err(error) = result {
+            self.show_error(&error);
+        }
+    }
+
+    pub(super) fn save_overlay(&mut self) {
+        if let Err(error) = self.world.save_overlay() {
+            self.show_error(&error);
+        }
+    }
+
+    pub fn teleport(&mut self, destination: &Destination) {
+        self.enter_world(destination, None)
+    }
 
+    /// With a `restored_direction` the destination is a saved hero position rather than a teleport target.
+    pub(super) fn enter_world(&mut self, destination: &Destination, restored_direction: Option<Direction>) {
         if self.creative_mode {
             self.world.save();
+        } else {
+            self.save_overlay();
         }
+
+        let mut new_world = match World::load_or_create(destination.world, self.context.clone()) {
+            Ok(world) => world,
+            Err(error) => {
+                self.apply_state_update(&EngineStateUpdate::Error(error));
+                return
+            }
+        };
+        self.loading_screen.animate_world_transition();
             
         if self.world.id != WORLD_ID_NONE {
-            set_value_for_key(&StorageKey::previous_world(), self.world.id);
+            self.context.storage.set_value_for_key(&StorageKey::previous_world(), self.world.id);
         }
         
-        let mut new_world = World::load_or_create(destination.world);
         new_world.set_creative_mode(self.creative_mode);
-        new_world.setup(
-            self.previous_world(), 
-            &self.world.cached_hero_props.direction, 
-            destination.x, 
-            destination.y
-        );
+        match restored_direction {
+            Some(direction) => new_world.setup_restoring(self.previous_world(), &direction, destination.x, destination.y),
+            None => new_world.setup(
+                self.previous_world(), 
+                &self.world.cached_hero_props.direction, 
+                destination.x, 
+                destination.y
+            )
+        }
         new_world.update(0.001);
         let hero_frame = new_world.cached_hero_props.frame;
         self.world = new_world;
@@ -277,12 +629,13 @@ impl GameEngine {
         self.keyboard.on_world_changed();
         self.mouse.on_world_changed();
 
-        set_value_for_key(&StorageKey::latest_world(), self.world.id);
+        self.save_hero_position();
+        self.save_slot_metadata();
     }
 
     fn previous_world(&self) -> u32 {
         if self.world.id == WORLD_ID_NONE { 
-            get_value_for_key(&StorageKey::previous_world()).unwrap_or(WORLD_ID_NONE)
+            self.context.storage.get_value_for_key(&StorageKey::previous_world()).unwrap_or(WORLD_ID_NONE)
         } else {
             self.world.id
         }
@@ -301,13 +654,155 @@ impl GameEngine {
 
 #[cfg(test)]
 mod tests {    
+    use std::{env, fs, process};
+
+    use crate::{config::Config, game_engine::{context::GameContext, inventory::InventoryStorage, simulation::SimulationMode, state_updates::EngineStateUpdate, storage::{KeyValueStorage, StorageKey, StorageValue}}, testing::harness::TestHarness, utils::{directions::Direction, files::backup_path}};
+
     use super::GameEngine;
 
     #[test]
     fn can_launch_game_headless() {
-        let mut engine = GameEngine::new();
+        let mut engine = GameEngine::with_context(GameContext::for_tests(), SimulationMode::real_time());
         engine.start();
         assert_ne!(engine.world.bounds.w, 10);
         assert_ne!(engine.world.bounds.h, 10);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn same_seed_produces_same_world() {
+        let simulation = SimulationMode::Deterministic { seed: 42, time_step: 1.0 / 60.0 };
+
+        let run = || {
+            let mut engine = GameEngine::with_context(GameContext::for_tests(), simulation);
+            engine.start();
+            engine.world.creep_spawn_enabled = true;
+            engine.world.creep_spawn_interval = 0.5;
+            (0..600).for_each(|_| engine.update(0.5));
+
+            let entities = engine.world.entities.borrow();
+            entities.iter().map(|e| (e.id, e.species_id, e.frame)).collect::<Vec<_>>()
+        };
+        let first_run = run();
+
+        assert!(first_run.iter().filter(|(id, _, _)| *id >= 0x8000_0000).count() > 1);
+        assert_eq!(first_run, run());
+    }
+
+    #[test]
+    fn hero_resumes_exactly_where_the_game_was_left() {
+        let mut game = TestHarness::new();
+        game.teleport(1002, 0, 0).walk(Direction::Right, 2).face(Direction::Left);
+        game.engine.apply_state_update(&EngineStateUpdate::SaveGame);
+        let hero = game.hero_frame();
+
+        let relaunch = |values| {
+            let context = GameContext::with_storage(Config::for_tests(), KeyValueStorage::from_values(values), InventoryStorage::in_memory()).unwrap();
+            TestHarness::with_context(context)
+        };
+        let mut game = relaunch(game.engine.context.storage.values());
+        game.assert_world(1002).assert_hero_at(hero.x, hero.y);
+        assert_eq!(game.engine.world.cached_hero_props.direction, Direction::Left);
+
+        let mut values = game.engine.context.storage.values();
+        values.insert(StorageKey::hero_x(), StorageValue::Int(0));
+        values.insert(StorageKey::hero_y(), StorageValue::Int(0));
+        let mut game = relaunch(values);
+        game.assert_world(1002);
+        assert_ne!((game.hero_frame().x, game.hero_frame().y), (0, 0));
+    }
+
+    #[test]
+    fn broken_world_is_reported_and_never_replaced() {
+        let levels_path = env::temp_dir().join(format!("nokemon-levels-{}", process::id()));
+        fs::create_dir_all(&levels_path).unwrap();
+        fs::write(levels_path.join("4242.json"), "{ \"id\": 4242, ").unwrap();
+
+        let config = Config { levels_path: levels_path.clone(), ..Config::for_tests() };
+        let context = GameContext::with_storage(config, KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+        let world_id = game.engine.world.id;
+
+        game.teleport(4242, 1, 1).assert_world(world_id);
+
+        let error = game.engine.last_error.clone().unwrap();
+        assert_eq!(error.file_name(), "4242.json");
+        assert_eq!(fs::read_to_string(levels_path.join("4242.json")).unwrap(), "{ \"id\": 4242, ");
+        fs::remove_dir_all(levels_path).unwrap();
+    }
+
+    #[test]
+    fn damaged_save_can_be_restored_from_backup() {
+        let folder = env::temp_dir().join(format!("nokemon-backups-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let save_path = folder.join("save.json");
+        fs::write(&save_path, "{ \"always\": 1, \"resto").unwrap();
+        fs::write(backup_path(&save_path, 1), "{ \"restored\": 1 }").unwrap();
+
+        let config = Config { key_value_storage_path: save_path.clone(), inventory_path: folder.join("inventory.json"), ..Config::for_tests() };
+        let mut game = TestHarness::with_context(GameContext::new(config).unwrap());
+
+        assert_eq!(game.engine.last_error.clone().unwrap().file_name(), "save.json");
+        assert!(game.engine.confirmation_dialog.is_open());
+        game.assert_storage("restored", None);
+
+        game.choose("confirmation.confirm").wait(0.5);
+        game.assert_storage("restored", Some(StorageValue::Int(1)));
+        assert!(folder.join("save.json.damaged").exists());
+
+        game.engine.exit();
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn progress_is_saved_even_when_the_backup_is_declined() {
+        let folder = env::temp_dir().join(format!("nokemon-declined-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let save_path = folder.join("save.json");
+        fs::write(&save_path, "{ \"always\": 1, \"resto").unwrap();
+        fs::write(backup_path(&save_path, 1), "{ \"restored\": 1 }").unwrap();
+
+        let config = Config { key_value_storage_path: save_path.clone(), inventory_path: folder.join("inventory.json"), ..Config::for_tests() };
+        let mut game = TestHarness::with_context(GameContext::new(config).unwrap());
+        assert!(game.engine.confirmation_dialog.is_open());
+
+        game.choose("confirmation.cancel").wait(0.5);
+        game.assert_storage("restored", None);
+        game.engine.context.storage.set("declined.progress", true);
+        game.engine.exit();
+
+        assert!(fs::read_to_string(&save_path).unwrap().contains("declined.progress"));
+        assert_eq!(fs::read_to_string(folder.join("save.json.damaged")).unwrap(), "{ \"always\": 1, \"resto");
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn exiting_waits_for_pending_saves() {
+        let folder = env::temp_dir().join(format!("nokemon-exit-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let save_path = folder.join("save.json");
+
+        let config = Config { key_value_storage_path: save_path.clone(), inventory_path: folder.join("inventory.json"), saves_path: folder.join("saves"), ..Config::for_tests() };
+        let mut game = TestHarness::with_context(GameContext::new(config).unwrap());
+        game.engine.context.storage.set("exit.flushed", true);
+        game.engine.exit();
+
+        assert!(!game.engine.is_running);
+        assert!(fs::read_to_string(&save_path).unwrap().contains("exit.flushed"));
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn dropping_the_engine_waits_for_pending_saves() {
+        let folder = env::temp_dir().join(format!("nokemon-drop-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let save_path = folder.join("save.json");
+
+        let config = Config { key_value_storage_path: save_path.clone(), inventory_path: folder.join("inventory.json"), ..Config::for_tests() };
+        let game = TestHarness::with_context(GameContext::new(config).unwrap());
+        (0..50).for_each(|value| game.engine.context.storage.set_value_for_key("drop.flushed", value));
+        drop(game);
+
+        assert!(fs::read_to_string(&save_path).unwrap().contains("\"drop.flushed\": 49"));
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{constants::UNLIMITED_LIFESPAN, dialogues::models::{Dialogue, EntityDialogues}, entities::species::{species_by_id, EntityType}, features::{animated_sprite::AnimatedSprite, destination::Destination, directions::MovementDirections}, utils::{directions::Direction, rect::IntRect, vector::Vector2d}};
+use crate::{constants::{DAMAGE_BLINK_INTERVAL, SPRITE_SHEET_BLANK, UNLIMITED_LIFESPAN}, dialogues::{conditions::ConditionContext, models::{Dialogue, EntityDialogues}}, entities::species::{EntityType, SpeciesRegistry}, features::{animated_sprite::AnimatedSprite, destination::Destination, directions::MovementDirections}, utils::{directions::Direction, rect::IntRect, vector::Vector2d}};
 
-use super::{locks::LockType, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::get_value_for_key, world::World};
+use super::{context::GameContext, locks::LockType, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::{KeyValueStorage, StorageScope}, world::World};
 
 #[derive(Debug, Copy, Clone)]
 pub struct EntityProps {
@@ -12,6 +12,7 @@ pub struct EntityProps {
     pub speed: f32,
     pub hittable_frame: IntRect,
     pub is_invulnerable: bool,
+    pub hp: u32,
 }
 
 impl Default for EntityProps {
@@ -23,6 +24,7 @@ impl Default for EntityProps {
             speed: 0.0,
             hittable_frame: IntRect::square_from_origin(1),
             is_invulnerable: false,
+            hp: 0,
         }
     }
 }
@@ -59,6 +61,13 @@ pub struct Entity {
 
     #[serde(default)]
     pub melee_attacks_hero: bool,
+
+    #[serde(default)]
+    pub ranged_attacks_hero: bool,
+
+    /// Time left aiming before firing, creeps stand still meanwhile
+    #[serde(default)]
+    pub time_to_fire: f32,
     
     #[serde(default)]
     pub is_dying: bool,
@@ -78,9 +87,24 @@ pub struct Entity {
     #[serde(default)]
     pub parent_id: u32,  
 
+    /// Targets a projectile can still go through
+    #[serde(default)]
+    pub remaining_pierce: u32,
+
+    /// Latest target of a projectile, so that it's not hit again while going through it
+    #[serde(default)]
+    pub last_hit_id: EntityId,
+
     #[serde(default)]
     pub is_invulnerable: bool,
 
+    #[serde(default)]
+    pub hp: u32,
+
+    /// Time left blinking after a hit, the hero can't be hit again meanwhile
+    #[serde(default)]
+    pub time_invulnerable: f32,
+
     #[serde(default)]
     pub demands_attention: bool,
 }
@@ -109,35 +133,47 @@ impl Entity {
             EntityType::Bullet => self.update_bullet(world, time_since_last_update),
             EntityType::RailObject => self.update_rail(world, time_since_last_update),
             EntityType::Hint => self.update_hint(world, time_since_last_update),
+            EntityType::Effect => vec![],
         };        
         self.sprite.update(time_since_last_update); 
+        self.time_invulnerable = (self.time_invulnerable - time_since_last_update).max(0.0);
         let mut more_updates = self.check_remaining_lifespan(time_since_last_update);
         updates.append(&mut more_updates);
         updates
     }
 
-    pub fn setup(&mut self, creative_mode: bool) {      
-        species_by_id(self.species_id).reload_props(self);
+    pub fn setup(&mut self, context: &GameContext, creative_mode: bool) {      
+        context.species.get(self.species_id).reload_props(self, &context.strings);
+        let storage = &context.storage;
         
         match self.entity_type {
-            EntityType::Hero => self.setup_hero(creative_mode),
+            EntityType::Hero => self.setup_hero(storage, creative_mode),
             EntityType::Npc => self.setup_npc(),
             EntityType::Building => self.setup_generic(creative_mode),
             EntityType::StaticObject => self.setup_generic(creative_mode),
             EntityType::PickableObject | EntityType::Bundle => self.setup_generic(creative_mode),
             EntityType::Teleporter => self.setup_teleporter(creative_mode),
             EntityType::PushableObject => self.setup_generic(creative_mode),
-            EntityType::Gate => self.setup_gate(creative_mode),
-            EntityType::InverseGate => self.setup_inverse_gate(),
-            EntityType::PressurePlate => self.setup_pressure_plate(),
+            EntityType::Gate => self.setup_gate(storage, creative_mode),
+            EntityType::InverseGate => self.setup_inverse_gate(storage),
+            EntityType::PressurePlate => self.setup_pressure_plate(storage),
             EntityType::Bullet => self.setup_bullet(),
             EntityType::RailObject => self.setup_rail(),
-            EntityType::Hint => self.setup_hint(creative_mode),
+            EntityType::Hint => self.setup_hint(&context.species, creative_mode),
+            EntityType::Effect => {}
         }
     }
 
     pub fn sprite_sheet(&self) -> u32 {
-        self.sprite.sheet_id
+        if self.is_blinking() {
+            SPRITE_SHEET_BLANK
+        } else {
+            self.sprite.sheet_id
+        }
+    }
+
+    fn is_blinking(&self) -> bool {
+        self.time_invulnerable > 0.0 && (self.time_invulnerable / DAMAGE_BLINK_INTERVAL) as i32 % 2 == 1
     }
 
     pub fn texture_source_rect(&self) -> IntRect {
@@ -148,22 +184,21 @@ impl Entity {
         self.time_immobilized = seconds;
     }
 
-    pub fn reset_speed(&mut self) {        
-        self.current_speed = self.speed_multiplier * species_by_id(self.species_id).base_speed;
+    pub fn reset_speed(&mut self, species: &SpeciesRegistry) {        
+        self.current_speed = self.speed_multiplier * species.get(self.species_id).base_speed;
     }    
     
-    pub fn next_dialogue(&self) -> Option<Dialogue> {
-        for option in &self.dialogues {
-            let value = get_value_for_key(&option.key);
-            if value == Some(option.expected_value) || (option.expected_value == 0 && value.is_none()) {
-                return Some(option.clone())
-            }
-        }
-        None
+    pub fn next_dialogue(&self, world: &World) -> Option<Dialogue> {
+        let context = ConditionContext {
+            storage: &world.context.storage,
+            inventory: &world.context.inventory,
+            scope: StorageScope::new(world.id, self.id),
+        };
+        self.dialogues.iter().find(|option| option.is_available(&context)).cloned()
     }
 
-    pub fn is_related_pressure_plate_down(&self) -> bool {
-        get_value_for_key(self.lock_type.pressure_plate()).unwrap_or(1) == 0
+    pub fn is_related_pressure_plate_down(&self, storage: &KeyValueStorage) -> bool {
+        storage.get_value_for_key(self.lock_type.pressure_plate()).unwrap_or(1) == 0
     }
 }
 
@@ -0,0 +1,48 @@
+use std::{fmt, io, path::{Path, PathBuf}};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    FileNotFound(PathBuf),
+    Io(PathBuf, String),
+    InvalidData(PathBuf, String),
+}
+
+pub type GameResult<T> = Result<T, GameError>;
+
+impl GameError {
+    pub fn io(path: &Path, error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::NotFound {
+            GameError::FileNotFound(path.to_path_buf())
+        } else {
+            GameError::Io(path.to_path_buf(), error.to_string())
+        }
+    }
+
+    pub fn invalid_data(path: &Path, error: impl fmt::Display) -> Self {
+        GameError::InvalidData(path.to_path_buf(), error.to_string())
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            GameError::FileNotFound(path) => path,
+            GameError::Io(path, _) => path,
+            GameError::InvalidData(path, _) => path,
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path().file_name().unwrap_or_default().to_string_lossy().into_owned()
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::FileNotFound(path) => write!(f, "Missing file {}", path.display()),
+            GameError::Io(path, error) => write!(f, "Could not read {}: {}", path.display(), error),
+            GameError::InvalidData(path, error) => write!(f, "Invalid data in {}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
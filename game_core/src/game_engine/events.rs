@@ -0,0 +1,163 @@
+use std::{ffi::{c_char, c_void, CString}, sync::RwLock};
+
+use super::state_updates::{EngineStateUpdate, WorldStateUpdate};
+
+pub type ListenerId = u32;
+pub type Listener = Box<dyn Fn(&GameEvent) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum StateUpdate<'a> {
+    World(&'a WorldStateUpdate),
+    Engine(&'a EngineStateUpdate),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GameEvent<'a> {
+    pub world_id: u32,
+    pub timestamp: f32,
+    pub update: StateUpdate<'a>,
+}
+
+/// Notifies listeners of every state update applied by the world or the engine.
+/// Listeners are called synchronously and must not (un)subscribe from within the callback.
+pub struct EventBus {
+    listeners: RwLock<Vec<(ListenerId, Listener)>>,
+    next_listener_id: RwLock<ListenerId>,
+    elapsed_time: RwLock<f32>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            listeners: RwLock::new(vec![]),
+            next_listener_id: RwLock::new(1),
+            elapsed_time: RwLock::new(0.0),
+        }
+    }
+
+    pub fn subscribe<F>(&self, listener: F) -> ListenerId where F: Fn(&GameEvent) + Send + Sync + 'static {
+        let mut next_id = self.next_listener_id.write().unwrap();
+        let id = *next_id;
+        *next_id += 1;
+        self.listeners.write().unwrap().push((id, Box::new(listener)));
+        id
+    }
+
+    pub fn unsubscribe(&self, id: ListenerId) {
+        self.listeners.write().unwrap().retain(|(listener_id, _)| *listener_id != id);
+    }
+
+    pub fn advance(&self, time_since_last_update: f32) {
+        *self.elapsed_time.write().unwrap() += time_since_last_update;
+    }
+
+    pub fn emit(&self, world_id: u32, update: StateUpdate) {
+        let listeners = self.listeners.read().unwrap();
+        if listeners.is_empty() {
+            return
+        }
+        let event = GameEvent {
+            world_id,
+            timestamp: *self.elapsed_time.read().unwrap(),
+            update
+        };
+        listeners.iter().for_each(|(_, listener)| listener(&event));
+    }
+}
+
+impl GameEvent<'_> {
+    pub fn name(&self) -> String {
+        let description = self.description();
+        description.split(['(', ' ', '{']).next().unwrap_or_default().to_owned()
+    }
+
+    pub fn description(&self) -> String {
+        match self.update {
+            StateUpdate::World(update) => format!("{:?}", update),
+            StateUpdate::Engine(update) => format!("{:?}", update),
+        }
+    }
+
+    pub fn is_engine_update(&self) -> bool {
+        matches!(self.update, StateUpdate::Engine(_))
+    }
+}
+
+#[repr(C)]
+pub struct CGameEvent {
+    pub world_id: u32,
+    pub timestamp: f32,
+    pub is_engine_update: bool,
+    pub name: *const c_char,
+    pub description: *const c_char,
+}
+
+pub type CGameEventCallback = extern "C" fn(event: *const CGameEvent, user_data: *mut c_void);
+
+struct CListener {
+    callback: CGameEventCallback,
+    user_data: *mut c_void,
+}
+
+// The host owns `user_data` and is responsible for it being usable from the engine's thread.
+unsafe impl Send for CListener {}
+unsafe impl Sync for CListener {}
+
+impl CListener {
+    fn notify(&self, event: &GameEvent) {
+        let name = CString::new(event.name()).unwrap_or_default();
+        let description = CString::new(event.description()).unwrap_or_default();
+        let c_event = CGameEvent {
+            world_id: event.world_id,
+            timestamp: event.timestamp,
+            is_engine_update: event.is_engine_update(),
+            name: name.as_ptr(),
+            description: description.as_ptr(),
+        };
+        (self.callback)(&c_event, self.user_data);
+    }
+}
+
+impl EventBus {
+    pub fn subscribe_c(&self, callback: CGameEventCallback, user_data: *mut c_void) -> ListenerId {
+        let listener = CListener { callback, user_data };
+        self.subscribe(move |event| listener.notify(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{constants::WORLD_ID_DEMO, game_engine::state_updates::EngineStateUpdate, testing::harness::TestHarness};
+
+    use super::StateUpdate;
+
+    #[test]
+    fn listeners_receive_applied_updates() {
+        let mut game = TestHarness::new();
+        let received = Arc::new(Mutex::new(vec![]));
+        let sink = received.clone();
+
+        let id = game.engine.context.events.subscribe(move |event| {
+            if let StateUpdate::Engine(EngineStateUpdate::DisplayLongText(_)) = event.update {
+                sink.lock().unwrap().push((event.world_id, event.timestamp, event.name()));
+            }
+        });
+        game.open_menu().choose("game.menu.status");
+        game.engine.context.events.unsubscribe(id);
+        game.engine.context.events.emit(WORLD_ID_DEMO, StateUpdate::Engine(&EngineStateUpdate::DisplayLongText("".to_owned())));
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0, WORLD_ID_DEMO);
+        assert!(received[0].1 > 0.0);
+        assert_eq!(received[0].2, "DisplayLongText");
+    }
+}
@@ -1,95 +1,155 @@
-use std::{fs::File, io::{BufReader, Write}, sync::{mpsc::{self, Sender}, RwLock}, thread};
-use lazy_static::lazy_static;
-use serde_json;
-use crate::{config::config, entities::species::{species_by_id, EntityType}, game_engine::entity::Entity};
+use std::{fs::File, io::{BufReader, ErrorKind}, path::{Path, PathBuf}, sync::{mpsc::{self, Sender}, RwLock}, thread::{self, JoinHandle}};
+use serde_json::json;
+use super::{errors::{GameError, GameResult}, migrations::{from_reader, DataKind, InventoryFile}};
+use crate::{log_debug, log_error, utils::{files::{back_up, write_atomically}, logger::{Logger, Subsystem}}, constants::UNASSIGNED_ENTITY_ID, game_engine::entity::Entity, utils::ids::IdGenerator};
 
-lazy_static! {
-    pub static ref INVENTORY: RwLock<Vec<Entity>> = RwLock::new(load_inventory());
+type SaveThread = (Sender<Vec<Entity>>, JoinHandle<()>, PathBuf, Logger);
 
-    static ref SAVE_THREAD: (Sender<Vec<Entity>>, thread::JoinHandle<()>) = {
-        let (tx, rx) = mpsc::channel::<Vec<Entity>>();
-
-        let handle = thread::spawn(move || {
-            while let Ok(inventory) = rx.recv() {
-                save_inventory(&inventory);
-            }
-        });
-        (tx, handle)
-    };
+pub struct InventoryStorage {
+    items: RwLock<Vec<Entity>>,
+    save_thread: RwLock<Option<SaveThread>>,
 }
 
-pub fn add_to_inventory(entity: Entity) {
-    if matches!(entity.entity_type, EntityType::Bundle) {
-        let bundle_species = species_by_id(entity.species_id);
+impl InventoryStorage {
+    pub fn load(path: &Path, logger: &Logger) -> GameResult<Self> {
+        let items = load_inventory(path)?;
+        Ok(Self::with_items(items, Some(spawn_save_thread(path.to_path_buf(), logger.clone()))))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::with_items(vec![], None)
+    }
+
+    pub fn from_items(items: Vec<Entity>) -> Self {
+        Self::with_items(items, None)
+    }
 
-        for species_id in bundle_species.bundle_contents {
-            let item = species_by_id(species_id).make_entity();
-            add_to_inventory(item);
+    fn with_items(items: Vec<Entity>, save_thread: Option<SaveThread>) -> Self {
+        Self {
+            items: RwLock::new(items),
+            save_thread: RwLock::new(save_thread),
+        }
+    }
+
+    /// Replaces all items with the ones stored at `path` and saves there from now on.
+    /// Pending writes to the previous file are completed first.
+    pub fn switch_to(&self, path: &Path, logger: &Logger) -> GameResult<()> {
+        self.close();
+        let items = load_inventory(path)?;
+        *self.items.write().unwrap() = items;
+        *self.save_thread.write().unwrap() = Some(spawn_save_thread(path.to_path_buf(), logger.clone()));
+        Ok(())
+    }
+
+    pub fn add(&self, mut entity: Entity, ids: &IdGenerator) {
+        if entity.id == UNASSIGNED_ENTITY_ID {
+            entity.id = ids.next_id();
         }
-    } else {
         {
-            let mut inventory = INVENTORY.write().unwrap();
+            let mut inventory = self.items.write().unwrap();
             inventory.push(entity);
         }
-        let inventory = INVENTORY.read().unwrap().clone();
-        let tx = &SAVE_THREAD.0;
-        tx.send(inventory).expect("Failed to send inventory data to save thread");
+        self.save();
     }
-}
 
-pub fn remove_from_inventory(id: u32) {
-    {
-        let mut inventory = INVENTORY.write().unwrap();
-        if let Some(pos) = inventory.iter().position(|x| x.id == id) {
-            inventory.remove(pos);
+    pub fn remove(&self, id: u32) {
+        {
+            let mut inventory = self.items.write().unwrap();
+            if let Some(pos) = inventory.iter().position(|x| x.id == id) {
+                inventory.remove(pos);
+            }
         }
+        self.save();
     }
 
-    let inventory = INVENTORY.read().unwrap().clone();
-    let tx = &SAVE_THREAD.0;
-    tx.send(inventory).expect("Failed to send inventory data to save thread");
-}
+    pub fn remove_one_of_species(&self, species_id: u32) {
+        {
+            let mut inventory = self.items.write().unwrap();
+            if let Some(pos) = inventory.iter().position(|x| x.species_id == species_id) {
+                inventory.remove(pos);
+            }
+        }
+        self.save();
+    }
 
-pub fn remove_one_of_species_from_inventory(id: u32) {
-    {
-        let mut inventory = INVENTORY.write().unwrap();
-        if let Some(pos) = inventory.iter().position(|x| x.species_id == id) {
-            inventory.remove(pos);
+    pub fn replace_items(&self, items: Vec<Entity>) {
+        *self.items.write().unwrap() = items;
+        self.save();
+    }
+
+    pub fn items(&self) -> Vec<Entity> {
+        let inventory = self.items.read().unwrap();
+        inventory.clone()
+    }
+
+    pub fn contains_species(&self, species_id: u32) -> bool {
+        self.items.read().unwrap().iter().any(|e| e.species_id == species_id)
+    }
+
+    /// Waits for pending writes, changes made afterwards are kept in memory only.
+    pub fn close(&self) {
+        if let Some((tx, handle, _, _)) = self.save_thread.write().unwrap().take() {
+            drop(tx);
+            let _ = handle.join();
         }
     }
 
-    let inventory = INVENTORY.read().unwrap().clone();
-    let tx = &SAVE_THREAD.0;
-    tx.send(inventory).expect("Failed to send inventory data to save thread");
+    /// Adds the save file, once pending writes are done, to its rolling backups.
+    pub fn back_up(&self) -> GameResult<()> {
+        let Some((path, logger)) = self.save_thread.read().unwrap().as_ref().map(|(_, _, path, logger)| (path.clone(), logger.clone())) else {
+            return Ok(())
+        };
+        self.close();
+        let result = back_up(&path);
+        *self.save_thread.write().unwrap() = Some(spawn_save_thread(path, logger));
+        result
+    }
+
+    fn save(&self) {
+        if let Some((tx, _, _, _)) = self.save_thread.read().unwrap().as_ref() {
+            let inventory = self.items.read().unwrap().clone();
+            tx.send(inventory).expect("Failed to send inventory data to save thread");
+        }
+    }
 }
 
-pub fn get_inventory() -> Vec<Entity> {
-    let inventory = INVENTORY.read().unwrap();
-    inventory.clone()
+/// Pending writes are completed before the inventory goes away, so its file is never left half written.
+impl Drop for InventoryStorage {
+    fn drop(&mut self) {
+        self.close();
+    }
 }
 
-pub fn inventory_contains_species(species_id: u32) -> bool {
-    INVENTORY.read().unwrap().iter().any(|e| e.species_id == species_id)
+fn spawn_save_thread(path: PathBuf, logger: Logger) -> SaveThread {
+    let (tx, rx) = mpsc::channel::<Vec<Entity>>();
+
+    let (thread_path, thread_logger) = (path.clone(), logger.clone());
+    let handle = thread::spawn(move || {
+        while let Ok(inventory) = rx.recv() {
+            save_inventory(&thread_logger, &inventory, &thread_path);
+        }
+    });
+    (tx, handle, path, logger)
 }
 
-fn load_inventory() -> Vec<Entity> {
-    let file = File::open(config().inventory_path.clone()).expect("Failed to open inventory.json file");
-    let reader = BufReader::new(file);
-    serde_json::from_reader(reader).expect("Failed to deserialize inventory file from JSON")
+fn load_inventory(path: &Path) -> GameResult<Vec<Entity>> {
+    match File::open(path) {
+        Ok(file) => from_reader::<InventoryFile>(DataKind::Inventory, path, BufReader::new(file)).map(|data| data.items),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(GameError::io(path, e))
+    }
 }
 
-fn save_inventory(inventory: &Vec<Entity>) {
-    if let Ok(serialized_inventory) = serde_json::to_string_pretty(inventory) {
-        if let Ok(mut file) = File::create(config().inventory_path.clone()) {
-            if let Err(e) = file.write_all(serialized_inventory.as_bytes()) {
-                eprintln!("Failed to write inventory file: {}", e);
-            } else {
-                println!("Inventory saved successfully to inventory.json");
-            }
+fn save_inventory(logger: &Logger, inventory: &Vec<Entity>, path: &Path) {
+    let data = json!({ "version": DataKind::Inventory.latest_version(), "items": inventory });
+
+    if let Ok(serialized_inventory) = serde_json::to_string_pretty(&data) {
+        if let Err(e) = write_atomically(path, serialized_inventory.as_bytes()) {
+            log_error!(logger, Subsystem::Inventory, "Failed to write inventory file: {}", e);
         } else {
-            eprintln!("Failed to create inventory file");
+            log_debug!(logger, Subsystem::Inventory, "Inventory saved successfully to inventory.json");
         }
     } else {
-        eprintln!("Failed to serialize inventory data");
+        log_error!(logger, Subsystem::Inventory, "Failed to serialize inventory data");
     }
 }
@@ -18,6 +18,12 @@ pub struct KeyboardEventsProvider {
     pub currently_pressed_character: Option<char>,
 }
 
+impl Default for KeyboardEventsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl KeyboardEventsProvider {
     pub const fn new() -> Self {
         Self {
@@ -98,6 +104,7 @@ impl HoldableKey {
 }
 
 impl KeyboardEventsProvider {
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         up_pressed: bool,
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{entities::{known_species::{SPECIES_KEY_BLUE, SPECIES_KEY_GREEN, SPECIES_KEY_RED, SPECIES_KEY_SILVER, SPECIES_KEY_YELLOW}, species::SpeciesId}, lang::localizable::LocalizableText};
+use crate::{entities::{known_species::{SPECIES_KEY_BLUE, SPECIES_KEY_GREEN, SPECIES_KEY_RED, SPECIES_KEY_SILVER, SPECIES_KEY_YELLOW}, species::SpeciesId}, lang::localizable::{LocalizableText, LocalizedStrings}};
 
 pub const PRESSURE_PLATE_YELLOW: &str = "pressure_plate_down_yellow";
 pub const PRESSURE_PLATE_RED: &str = "pressure_plate_down_red";
@@ -22,15 +22,15 @@ pub enum LockType {
 }
 
 impl LockType {
-    pub fn localized_name(&self) -> String {
+    pub fn localized_name(&self, strings: &LocalizedStrings) -> String {
         match self {
-            LockType::None => "lock.name.none".localized(),
-            LockType::Yellow => "lock.name.yellow".localized(),
-            LockType::Red => "lock.name.red".localized(),
-            LockType::Blue => "lock.name.blue".localized(),
-            LockType::Green => "lock.name.green".localized(),
-            LockType::Silver => "lock.name.silver".localized(),
-            LockType::Permanent => "lock.name.permanent".localized(),
+            LockType::None => "lock.name.none".localized(strings),
+            LockType::Yellow => "lock.name.yellow".localized(strings),
+            LockType::Red => "lock.name.red".localized(strings),
+            LockType::Blue => "lock.name.blue".localized(strings),
+            LockType::Green => "lock.name.green".localized(strings),
+            LockType::Silver => "lock.name.silver".localized(strings),
+            LockType::Permanent => "lock.name.permanent".localized(strings),
         }
     }
 
@@ -0,0 +1,265 @@
+use std::{fs, io::Read, path::{Path, PathBuf}};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, ser::PrettyFormatter, Serializer, Value};
+
+use crate::{entities::species::Species, utils::files::write_atomically, worlds::{world_overlay::WorldOverlay, world_serde::WorldData}};
+
+use super::{entity::Entity, errors::{GameError, GameResult}, save_bundle::SaveBundle};
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// Kinds of data files that carry a `version` and can be upgraded from older versions.
+/// Files without a `version` are version 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataKind {
+    World,
+    Species,
+    Inventory,
+    Overlay,
+    Bundle,
+}
+
+impl DataKind {
+    /// Migration at index `n` upgrades a file from version `n` to `n + 1`, add new ones at the end.
+    fn migrations(&self) -> &'static [Migration] {
+        match self {
+            DataKind::World => &[v0_add_version],
+            DataKind::Species => &[species_v0_wrap_list],
+            DataKind::Inventory => &[inventory_v0_wrap_list],
+            DataKind::Overlay => &[v0_add_version],
+            DataKind::Bundle => &[v0_add_version],
+        }
+    }
+
+    pub fn latest_version(&self) -> u32 {
+        self.migrations().len() as u32
+    }
+
+    /// Kind of a file in the data folder, judging by its name and the folder it's in.
+    pub fn of_file(path: &Path) -> Option<Self> {
+        if path.extension().is_none_or(|extension| extension != "json") {
+            return None
+        }
+        let name = path.file_stem()?.to_str()?;
+        let folder = path.parent().and_then(Path::file_name).and_then(|folder| folder.to_str());
+        match (folder, name) {
+            (Some("overlays"), _) if name.parse::<u32>().is_ok() => return Some(DataKind::Overlay),
+            (Some("bundles"), _) => return Some(DataKind::Bundle),
+            _ => {}
+        }
+        match name {
+            "species" => Some(DataKind::Species),
+            "inventory" => Some(DataKind::Inventory),
+            _ if name.parse::<u32>().is_ok() => Some(DataKind::World),
+            _ => None
+        }
+    }
+}
+
+pub fn version_of(value: &Value) -> u32 {
+    value.get("version").and_then(Value::as_u64).unwrap_or_default() as u32
+}
+
+/// Upgrades `value` to the latest version of `kind`.
+pub fn migrate(kind: DataKind, mut value: Value) -> Result<Value, String> {
+    let version = version_of(&value);
+    let latest = kind.latest_version();
+
+    if version > latest {
+        return Err(format!("Version {} is newer than the latest supported one, {}", version, latest))
+    }
+    for migration in &kind.migrations()[version as usize..] {
+        value = migration(value)?;
+    }
+    Ok(value)
+}
+
+/// Parses a file of the given kind, upgrading it first if needed.
+pub fn from_reader<T: DeserializeOwned>(kind: DataKind, path: &Path, reader: impl Read) -> GameResult<T> {
+    let value: Value = serde_json::from_reader(reader).map_err(|e| GameError::invalid_data(path, e))?;
+    let value = migrate(kind, value).map_err(|e| GameError::invalid_data(path, e))?;
+    serde_json::from_value(value).map_err(|e| GameError::invalid_data(path, e))
+}
+
+/// Serializes a file of the given kind with its latest version in front.
+pub fn to_versioned_json<T: Serialize>(kind: DataKind, data: &T) -> serde_json::Result<String> {
+    let value = with_version(serde_json::to_value(data)?, kind.latest_version());
+    serde_json::to_string_pretty(&value)
+}
+
+fn with_version(mut value: Value, version: u32) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object.shift_insert(0, "version".to_owned(), json!(version));
+    }
+    value
+}
+
+/// Rewrites every outdated world in `folder`, and species, inventory, overlay and save bundle files
+/// in its subfolders too, to the latest version, returns the paths of the files that changed.
+pub fn migrate_data_folder(folder: &Path) -> GameResult<Vec<PathBuf>> {
+    migrate_folder(folder, true)
+}
+
+fn migrate_folder(folder: &Path, has_worlds: bool) -> GameResult<Vec<PathBuf>> {
+    let mut rewritten = vec![];
+    let mut entries: Vec<PathBuf> = fs::read_dir(folder)
+        .map_err(|e| GameError::io(folder, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            rewritten.extend(migrate_folder(&path, false)?);
+        } else if let Some(kind) = DataKind::of_file(&path) {
+            if kind == DataKind::World && !has_worlds {
+                continue
+            }
+            if migrate_file(kind, &path)? {
+                rewritten.push(path);
+            }
+        }
+    }
+    Ok(rewritten)
+}
+
+fn migrate_file(kind: DataKind, path: &Path) -> GameResult<bool> {
+    let contents = fs::read_to_string(path).map_err(|e| GameError::io(path, e))?;
+    let value: Value = serde_json::from_str(&contents).map_err(|e| GameError::invalid_data(path, e))?;
+    if version_of(&value) == kind.latest_version() {
+        return Ok(false)
+    }
+    let value = migrate(kind, value).map_err(|e| GameError::invalid_data(path, e))?;
+
+    let is_valid = match kind {
+        DataKind::World => serde_json::from_value::<WorldData>(value.clone()).map(|_| ()),
+        DataKind::Species => serde_json::from_value::<SpeciesFile>(value.clone()).map(|_| ()),
+        DataKind::Inventory => serde_json::from_value::<InventoryFile>(value.clone()).map(|_| ()),
+        DataKind::Overlay => serde_json::from_value::<WorldOverlay>(value.clone()).map(|_| ()),
+        DataKind::Bundle => serde_json::from_value::<SaveBundle>(value.clone()).map(|_| ()),
+    };
+    is_valid.map_err(|e| GameError::invalid_data(path, e))?;
+
+    let mut serialized = vec![];
+    let formatter = PrettyFormatter::with_indent(indentation_of(&contents).as_bytes());
+    value.serialize(&mut Serializer::with_formatter(&mut serialized, formatter)).map_err(|e| GameError::invalid_data(path, e))?;
+    write_atomically(path, &serialized)?;
+    Ok(true)
+}
+
+/// Indentation of the first nested line, hand written files don't always use the two spaces of serde.
+fn indentation_of(contents: &str) -> &str {
+    let line = contents.lines().nth(1).unwrap_or_default();
+    match &line[..line.len() - line.trim_start().len()] {
+        "" => "  ",
+        indentation => indentation
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SpeciesFile {
+    pub species: Vec<Species>,
+}
+
+#[derive(Deserialize)]
+pub struct InventoryFile {
+    pub items: Vec<Entity>,
+}
+
+fn v0_add_version(value: Value) -> Result<Value, String> {
+    if !value.is_object() {
+        return Err("Expected an object".to_owned())
+    }
+    Ok(with_version(value, 1))
+}
+
+fn species_v0_wrap_list(value: Value) -> Result<Value, String> {
+    if !value.is_array() {
+        return Err("Expected a list of species".to_owned())
+    }
+    Ok(json!({ "version": 1, "species": value }))
+}
+
+fn inventory_v0_wrap_list(value: Value) -> Result<Value, String> {
+    if !value.is_array() {
+        return Err("Expected a list of items".to_owned())
+    }
+    Ok(json!({ "version": 1, "items": value }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use serde_json::json;
+
+    use crate::config::Config;
+
+    use super::{migrate, migrate_data_folder, version_of, DataKind};
+
+    #[test]
+    fn upgrades_unversioned_files_and_rejects_newer_ones() {
+        let species = migrate(DataKind::Species, json!([{ "id": 1 }])).unwrap();
+        assert_eq!(species, json!({ "version": 1, "species": [{ "id": 1 }] }));
+        assert_eq!(migrate(DataKind::Species, species.clone()).unwrap(), species);
+
+        let world = migrate(DataKind::World, json!({ "id": 1001 })).unwrap();
+        assert_eq!(version_of(&world), DataKind::World.latest_version());
+
+        assert!(migrate(DataKind::Inventory, json!({ "version": 99, "items": [] })).is_err());
+        assert!(migrate(DataKind::Inventory, json!({ "items": [] })).is_err());
+    }
+
+    #[test]
+    fn rewrites_outdated_files_in_the_data_folder() {
+        let folder = env::temp_dir().join(format!("nokemon-migrations-{}", process::id()));
+        fs::create_dir_all(folder.join("saves")).unwrap();
+        fs::write(folder.join("1001.json"), "{ \"id\": 1001 }").unwrap();
+        fs::write(folder.join("saves").join("inventory.json"), "[]").unwrap();
+        fs::write(folder.join("save.json"), "{ \"always\": 1 }").unwrap();
+        fs::create_dir_all(folder.join("overlays")).unwrap();
+        fs::write(folder.join("overlays").join("1001.json"), "{\n    \"added\": []\n}").unwrap();
+        fs::create_dir_all(folder.join("saves").join("slot_1").join("overlays")).unwrap();
+        fs::write(folder.join("saves").join("slot_1").join("1001.json"), "{}").unwrap();
+        fs::write(folder.join("saves").join("slot_1").join("overlays").join("1001.json"), "{}").unwrap();
+
+        fs::create_dir_all(folder.join("saves").join("bundles")).unwrap();
+        let bundle = json!({
+            "build": { "version": "0.1.0", "os": "macos", "arch": "aarch64", "debug": true, "creative_mode": false },
+            "exported_at": 0, "current_world": 1001, "hero_x": 0, "hero_y": 0, "playtime": 0.0, "storage": {}, "inventory": []
+        });
+        fs::write(folder.join("saves").join("bundles").join("bundle_1.json"), bundle.to_string()).unwrap();
+        fs::write(folder.join("species.json"), "[]").unwrap();
+
+        let rewritten = migrate_data_folder(&folder).unwrap();
+        assert_eq!(rewritten, vec![
+            folder.join("1001.json"),
+            folder.join("overlays").join("1001.json"),
+            folder.join("saves").join("bundles").join("bundle_1.json"),
+            folder.join("saves").join("inventory.json"),
+            folder.join("saves").join("slot_1").join("overlays").join("1001.json"),
+            folder.join("species.json"),
+        ]);
+        assert!(migrate_data_folder(&folder).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(folder.join("save.json")).unwrap(), "{ \"always\": 1 }");
+        assert_eq!(fs::read_to_string(folder.join("overlays").join("1001.json")).unwrap(), "{\n    \"version\": 1,\n    \"added\": []\n}");
+        assert_eq!(fs::read_to_string(folder.join("species.json")).unwrap(), "{\n  \"version\": 1,\n  \"species\": []\n}");
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn shipped_data_files_are_up_to_date() {
+        let folder = Config::for_tests().levels_path;
+        let mut checked = 0;
+
+        for entry in fs::read_dir(&folder).unwrap() {
+            let path = entry.unwrap().path();
+            let Some(kind) = DataKind::of_file(&path) else { continue };
+            let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(version_of(&value), kind.latest_version(), "{} needs to be migrated", path.display());
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+}
@@ -1,9 +1,20 @@
+pub mod context;
 pub mod entity;
 pub mod engine;
+pub mod errors;
+pub mod events;
 pub mod keyboard_events_provider;
 pub mod inventory;
 pub mod locks;
+pub mod migrations;
 pub mod mouse_events_provider;
+pub mod replay;
+pub mod respawn;
+pub mod save_bundle;
+pub mod save_slots;
+pub mod simulation;
+pub mod snapshot;
+pub mod spatial_grid;
 pub mod state_updates;
 pub mod storage;
 pub mod visible_entities;
@@ -9,6 +9,12 @@ pub struct MouseEventsProvider {
     pub has_moved: bool,
 }
 
+impl Default for MouseEventsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MouseEventsProvider {
     pub const fn new() -> Self {
         Self {            
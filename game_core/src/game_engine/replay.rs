@@ -0,0 +1,384 @@
+use std::{collections::BTreeMap, fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, log_error, utils::{logger::Subsystem, rect::IntRect}, worlds::world_overlay::WorldOverlay};
+
+use super::{context::GameContext, engine::GameEngine, entity::Entity, errors::{GameError, GameResult}, inventory::InventoryStorage, simulation::SimulationMode, storage::{KeyValueStorage, StoredValues}};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub simulation: SimulationMode,
+    pub storage: StoredValues,
+    pub inventory: Vec<Entity>,
+    /// Time-based ids of real time sessions can't be derived from the seed
+    #[serde(default)]
+    pub first_id: Option<u32>,
+    /// Save slot picked when the recording began, storage and inventory are the ones of this slot
+    #[serde(default)]
+    pub slot: Option<u32>,
+    /// Play-mode changes of the save, by world id
+    #[serde(default)]
+    pub overlays: BTreeMap<u32, WorldOverlay>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    CreativeMode {
+        enabled: bool,
+    },
+    Start,
+    Keyboard {
+        up_pressed: bool,
+        right_pressed: bool,
+        down_pressed: bool,
+        left_pressed: bool,
+        up_down: bool,
+        right_down: bool,
+        down_down: bool,
+        left_down: bool,
+        escape_pressed: bool,
+        menu_pressed: bool,
+        confirm_pressed: bool,
+        attack_pressed: bool,
+        backspace_pressed: bool,
+        current_char: Option<char>,
+        time_since_last_update: f32,
+    },
+    Mouse {
+        left_down: bool,
+        left_pressed: bool,
+        right_pressed: bool,
+        x: f32,
+        y: f32,
+        rendering_scale: f32,
+    },
+    WindowSizeChanged {
+        width: f32,
+        height: f32,
+        scale: f32,
+        font_size: f32,
+        line_spacing: f32,
+    },
+    Update {
+        time_since_last_update: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplaySummary {
+    pub world_id: u32,
+    pub hero_frame: IntRect,
+    pub inventory: Vec<u32>,
+    pub storage: StoredValues,
+    pub next_id: u32,
+}
+
+impl ReplaySummary {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Writes a recording as JSON lines: the header first, then one input event per line.
+pub struct InputRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    creative_mode: Option<InputEvent>,
+    window_size: Option<InputEvent>,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path, header: &RecordingHeader) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_line(&mut writer, header)?;
+        Ok(Self { path: path.to_owned(), writer, creative_mode: None, window_size: None })
+    }
+
+    /// Starts the recording over from a new header, keeping the latest creative mode and window size.
+    /// Inputs given to the slot picker don't matter to the game, so the replay begins after it.
+    pub fn restart(&mut self, header: &RecordingHeader) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        write_line(&mut writer, header)?;
+        for event in self.creative_mode.iter().chain(self.window_size.iter()) {
+            write_line(&mut writer, event)?;
+        }
+        self.writer = writer;
+        Ok(())
+    }
+
+    pub fn record(&mut self, event: &InputEvent) -> io::Result<()> {
+        match event {
+            InputEvent::CreativeMode { .. } => self.creative_mode = Some(event.clone()),
+            InputEvent::WindowSizeChanged { .. } => self.window_size = Some(event.clone()),
+            _ => {}
+        }
+        write_line(&mut self.writer, event)?;
+        if matches!(event, InputEvent::Update { .. }) {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn write_line<T: Serialize>(writer: &mut BufWriter<File>, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")
+}
+
+impl GameEngine {
+    /// Snapshots storage, inventory and overlays, then records every input the engine receives.
+    /// Call right after creating the engine so that the replay begins from the same state.
+    pub fn start_recording(&mut self, path: &Path) -> GameResult<()> {
+        let header = self.recording_header()?;
+        self.recorder = Some(InputRecorder::create(path, &header).map_err(|e| GameError::io(path, e))?);
+        Ok(())
+    }
+
+    /// Once a slot is picked the recording begins again from its storage, inventory and overlays.
+    pub(super) fn restart_recording(&mut self) {
+        if self.recorder.is_none() {
+            return
+        }
+        let header = match self.recording_header() {
+            Ok(header) => header,
+            Err(e) => {
+                log_error!(self.context.logger, Subsystem::Replay, "Failed to restart recording: {}", e);
+                return
+            }
+        };
+        let Some(recorder) = &mut self.recorder else { return };
+
+        if let Err(e) = recorder.restart(&header).and_then(|_| recorder.record(&InputEvent::Start)) {
+            log_error!(self.context.logger, Subsystem::Replay, "Failed to restart recording: {}", e);
+        }
+    }
+
+    fn recording_header(&self) -> GameResult<RecordingHeader> {
+        Ok(RecordingHeader {
+            simulation: self.simulation,
+            storage: self.context.storage.values(),
+            inventory: self.context.inventory.items(),
+            first_id: Some(self.context.ids.peek()),
+            slot: self.current_slot,
+            overlays: self.context.overlays.all()?,
+        })
+    }
+
+    pub fn summary(&self) -> ReplaySummary {
+        ReplaySummary {
+            world_id: self.world.id,
+            hero_frame: self.world.cached_hero_props.frame,
+            inventory: self.context.inventory.items().iter().map(|item| item.species_id).collect(),
+            storage: self.context.storage.values(),
+            next_id: self.context.ids.peek(),
+        }
+    }
+
+    pub fn apply_input(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::CreativeMode { enabled } => self.set_creative_mode(enabled),
+            InputEvent::Start => self.start(),
+            InputEvent::Keyboard {
+                up_pressed, right_pressed, down_pressed, left_pressed,
+                up_down, right_down, down_down, left_down,
+                escape_pressed, menu_pressed, confirm_pressed, attack_pressed, backspace_pressed,
+                current_char, time_since_last_update
+            } => self.update_keyboard(
+                up_pressed, right_pressed, down_pressed, left_pressed,
+                up_down, right_down, down_down, left_down,
+                escape_pressed, menu_pressed, confirm_pressed, attack_pressed, backspace_pressed,
+                current_char, time_since_last_update
+            ),
+            InputEvent::Mouse { left_down, left_pressed, right_pressed, x, y, rendering_scale } => {
+                self.update_mouse(left_down, left_pressed, right_pressed, x, y, rendering_scale)
+            }
+            InputEvent::WindowSizeChanged { width, height, scale, font_size, line_spacing } => {
+                self.window_size_changed(width, height, scale, font_size, line_spacing)
+            }
+            InputEvent::Update { time_since_last_update } => {
+                self.update(time_since_last_update)
+            }
+        }
+    }
+}
+
+/// Runs a recording without a window and returns the final state of the game.
+/// Storage, inventory and overlays are kept in memory and save slots are left out, so the replay never touches the save files.
+pub fn replay(config: Config, path: &Path) -> GameResult<ReplaySummary> {
+    let config = Config { saves_path: PathBuf::new(), ..config };
+    let file = File::open(path).map_err(|e| GameError::io(path, e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header: RecordingHeader = match lines.next() {
+        Some(line) => parse(path, &line.map_err(|e| GameError::io(path, e))?)?,
+        None => return Err(GameError::invalid_data(path, "Recording is empty"))
+    };
+
+    let storage = KeyValueStorage::from_values(header.storage);
+    let inventory = InventoryStorage::from_items(header.inventory);
+    let context = GameContext::with_storage(config, storage, inventory)?;
+    context.overlays.replace_all(header.overlays)?;
+    let mut engine = GameEngine::with_context(context, header.simulation);
+    if let Some(first_id) = header.first_id {
+        engine.context.ids.restart_at(first_id);
+    }
+
+    for line in lines {
+        let line = line.map_err(|e| GameError::io(path, e))?;
+        if line.is_empty() {
+            continue;
+        }
+        let event: InputEvent = parse(path, &line)?;
+        engine.apply_input(&event);
+
+        if !engine.is_running {
+            break;
+        }
+    }
+    Ok(engine.summary())
+}
+
+fn parse<'a, T: Deserialize<'a>>(path: &Path, line: &'a str) -> GameResult<T> {
+    serde_json::from_str(line).map_err(|e| GameError::invalid_data(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::{BufRead, BufReader}};
+
+    use crate::{config::Config, constants::WORLD_ID_DEMO, game_engine::{context::GameContext, engine::GameEngine, inventory::InventoryStorage, save_slots::SaveSlot, simulation::SimulationMode, storage::KeyValueStorage}, testing::harness::TestHarness, worlds::world_overlay::WorldOverlay};
+
+    const SPECIES_SIGN: u32 = 10000;
+
+    use super::{replay, RecordingHeader};
+
+    #[test]
+    fn replay_matches_recorded_session() {
+        let path = env::temp_dir().join(format!("nokemon-replay-{}.jsonl", std::process::id()));
+        let simulation = SimulationMode::Deterministic { seed: 7, time_step: 1.0 / 60.0 };
+
+        let mut engine = GameEngine::with_context(GameContext::for_tests(), simulation);
+        engine.start_recording(&path).unwrap();
+        engine.start();
+        engine.window_size_changed(800.0, 600.0, 1.0, 12.0, 4.0);
+
+        for frame in 0..240 {
+            let walking_right = frame < 120;
+            engine.update_keyboard(
+                false, false, false, false,
+                !walking_right, walking_right, false, false,
+                false, false, false, false, false,
+                None, 1.0 / 60.0
+            );
+            engine.update(1.0 / 60.0);
+        }
+        let expected = engine.summary();
+        drop(engine);
+
+        let replayed = replay(Config::for_tests(), &path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(replayed, expected);
+    }
+
+    #[test]
+    fn replay_of_real_time_sessions_reuses_the_recorded_ids() {
+        let path = env::temp_dir().join(format!("nokemon-replay-ids-{}.jsonl", std::process::id()));
+
+        let mut engine = GameEngine::with_context(GameContext::for_tests(), SimulationMode::real_time());
+        engine.context.ids.restart_at(12345);
+        engine.start_recording(&path).unwrap();
+        engine.start();
+        engine.update(0.5);
+        let expected = engine.summary();
+        drop(engine);
+
+        let replayed = replay(Config::for_tests(), &path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(replayed, expected);
+    }
+
+    #[test]
+    fn replay_begins_after_the_slot_picker_and_leaves_the_slots_alone() {
+        let path = env::temp_dir().join(format!("nokemon-replay-slots-{}.jsonl", std::process::id()));
+        let saves_path = env::temp_dir().join(format!("nokemon-replay-saves-{}", std::process::id()));
+        let simulation = SimulationMode::Deterministic { seed: 3, time_step: 1.0 / 60.0 };
+        let config = Config { saves_path: saves_path.clone(), ..Config::for_tests() };
+        let context = GameContext::with_storage(config.clone(), KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+
+        let mut engine = GameEngine::with_context(context, simulation);
+        engine.start_recording(&path).unwrap();
+        engine.start();
+        engine.window_size_changed(800.0, 600.0, 1.0, 12.0, 4.0);
+        assert!(engine.slot_picker.is_open());
+
+        for frame in 0..360 {
+            let picking = engine.slot_picker.is_open();
+            let walking_down = !picking && frame < 240;
+            engine.update_keyboard(
+                false, false, false, false,
+                false, false, walking_down, false,
+                false, false, picking && frame % 10 == 0, false, false,
+                None, 1.0 / 60.0
+            );
+            engine.update(1.0 / 60.0);
+        }
+        assert_eq!(engine.current_slot, Some(1));
+        let expected = engine.summary();
+        drop(engine);
+
+        let first_line = BufReader::new(fs::File::open(&path).unwrap()).lines().next().unwrap().unwrap();
+        let header: RecordingHeader = serde_json::from_str(&first_line).unwrap();
+        let slot = SaveSlot::new(1, &saves_path);
+        let slot_files = || [slot.storage_path(), slot.metadata_path()].map(|file| fs::read(file).ok());
+        let stored_before = slot_files();
+
+        let replayed = replay(config, &path).unwrap();
+        let stored_after = slot_files();
+        let _ = fs::remove_file(&path);
+        fs::remove_dir_all(&saves_path).unwrap();
+
+        assert_eq!(header.slot, Some(1));
+        assert_eq!(replayed, expected);
+        assert_eq!(stored_after, stored_before);
+    }
+
+    #[test]
+    fn replay_applies_the_overlays_of_the_recorded_save() {
+        let path = env::temp_dir().join(format!("nokemon-replay-overlays-{}.jsonl", std::process::id()));
+        let simulation = SimulationMode::Deterministic { seed: 5, time_step: 1.0 / 60.0 };
+        let start = TestHarness::new().hero_frame();
+
+        let context = GameContext::for_tests();
+        let mut sign = context.make_entity(SPECIES_SIGN);
+        sign.id = 900_700;
+        sign.frame.x = start.x + 2;
+        sign.frame.y = start.y + 1;
+        context.overlays.set(WORLD_ID_DEMO, WorldOverlay { added: vec![sign], ..Default::default() }).unwrap();
+
+        let mut engine = GameEngine::with_context(context, simulation);
+        engine.start_recording(&path).unwrap();
+        engine.start();
+        engine.window_size_changed(800.0, 600.0, 1.0, 12.0, 4.0);
+
+        for frame in 0..240 {
+            let walking_right = frame >= 60;
+            engine.update_keyboard(
+                false, frame == 60, false, false,
+                false, walking_right, false, false,
+                false, false, false, false, false,
+                None, 1.0 / 60.0
+            );
+            engine.update(1.0 / 60.0);
+        }
+        let expected = engine.summary();
+        drop(engine);
+
+        let replayed = replay(Config::for_tests(), &path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(expected.hero_frame.x, start.x + 1);
+        assert_eq!(replayed, expected);
+    }
+}
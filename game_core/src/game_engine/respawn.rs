@@ -0,0 +1,114 @@
+use crate::{entities::known_species::SPECIES_HERO, features::{death_screen::DeathScreenOption, destination::Destination}, log_info, utils::logger::Subsystem};
+
+use super::engine::GameEngine;
+
+impl GameEngine {
+    /// The last quick save is the checkpoint, if there's one.
+    pub(super) fn show_death_screen(&mut self) {
+        let has_checkpoint = self.quick_save_path().exists();
+        self.death_screen.show(has_checkpoint);
+    }
+
+    /// Brings the hero back to life, reloading the world so that creeps and bullets are gone,
+    /// then applies the penalties of `Config::death_penalties`.
+    pub fn respawn(&mut self, option: DeathScreenOption) {
+        log_info!(self.context.logger, Subsystem::Engine, "Respawning, {:?}", option);
+        self.death_screen.close();
+
+        match option {
+            DeathScreenOption::ContinueFromCheckpoint => {
+                if !self.quick_load() {
+                    self.return_to_entrance()
+                }
+            }
+            DeathScreenOption::ReturnToEntrance => self.return_to_entrance(),
+            DeathScreenOption::Quit => self.return_to_entrance(),
+        }
+        self.apply_death_penalties();
+
+        if option == DeathScreenOption::Quit {
+            self.exit()
+        }
+    }
+
+    fn return_to_entrance(&mut self) {
+        let (x, y, direction) = self.world.entrance;
+        self.enter_world(&Destination::new(self.world.id, x, y), Some(direction));
+    }
+
+    fn apply_death_penalties(&mut self) {
+        let penalties = self.context.config.death_penalties;
+        let max_hp = self.context.species.get(SPECIES_HERO).hp;
+        let hp = (max_hp as f32 * penalties.respawn_hp).ceil() as u32;
+        self.world.set_hero_hp(hp.clamp(1, max_hp.max(1)));
+
+        if penalties.loses_consumables {
+            let items = self.context.inventory.items().into_iter().filter(|item| !item.is_consumable).collect();
+            self.context.inventory.replace_items(items);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::{config::{Config, DeathPenalties}, constants::HERO_ENTITY_ID, entities::known_species::{SPECIES_HERO, SPECIES_KUNAI, SPECIES_ZOMBIE}, game_engine::{context::GameContext, inventory::InventoryStorage, state_updates::WorldStateUpdate, storage::KeyValueStorage}, testing::harness::TestHarness, utils::directions::Direction};
+
+    const SPECIES_PILL: u32 = 8000;
+
+    #[test]
+    fn dying_returns_to_the_world_entrance_with_the_configured_penalties() {
+        let saves_path = env::temp_dir().join(format!("nokemon-respawn-{}", process::id()));
+        let death_penalties = DeathPenalties { respawn_hp: 0.5, loses_consumables: true };
+        let config = Config { saves_path: saves_path.clone(), death_penalties, ..Config::for_tests() };
+        let context = GameContext::with_storage(config, KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+        assert!(game.engine.select_slot(1));
+
+        game.teleport(1002, 0, 0).wait(0.5);
+        let entrance = game.engine.world.cached_hero_props.frame;
+        game.walk(Direction::Right, 2);
+        game.engine.context.inventory.add(game.engine.context.make_entity(SPECIES_KUNAI), &game.engine.context.ids);
+        game.engine.context.inventory.add(game.engine.context.make_entity(SPECIES_PILL), &game.engine.context.ids);
+
+        let hero = game.hero_frame();
+        let mut zombie = game.engine.context.make_entity(SPECIES_ZOMBIE);
+        zombie.frame.x = hero.x;
+        zombie.frame.y = hero.y;
+        game.engine.world.set_hero_hp(1);
+        game.engine.world.apply_state_updates(vec![WorldStateUpdate::AddEntity(Box::new(zombie))]);
+        game.wait(0.1);
+        assert!(game.engine.death_screen.is_open());
+
+        game.choose("death_screen.entrance").wait(0.5);
+
+        assert!(!game.engine.death_screen.is_open());
+        assert_eq!(game.engine.world.cached_hero_props.frame, entrance);
+        let max_hp = game.engine.context.species.get(SPECIES_HERO).hp;
+        assert_eq!(game.engine.world.cached_hero_props.hp, max_hp / 2);
+        let index = game.engine.world.index_for_entity(HERO_ENTITY_ID).unwrap();
+        assert_eq!(game.engine.world.entities.borrow()[index].hp, max_hp / 2);
+        assert!(!game.engine.world.entities.borrow().iter().any(|e| e.species_id == SPECIES_ZOMBIE));
+        assert!(game.engine.context.inventory.contains_species(SPECIES_KUNAI));
+        assert!(!game.engine.context.inventory.contains_species(SPECIES_PILL));
+
+        game.engine.exit();
+        fs::remove_dir_all(saves_path).unwrap();
+    }
+
+    #[test]
+    fn resumed_games_keep_the_entrance_of_the_world() {
+        let mut game = TestHarness::new();
+        game.teleport(1002, 0, 0).wait(0.5);
+        let entrance = game.engine.world.entrance;
+        game.walk(Direction::Right, 2);
+        game.engine.exit();
+
+        let context = GameContext::with_storage(Config::for_tests(), KeyValueStorage::from_values(game.engine.context.storage.values()), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+        game.assert_world(1002);
+        assert_ne!((game.hero_frame().x, game.hero_frame().y), (entrance.0, entrance.1));
+        assert_eq!(game.engine.world.entrance, entrance);
+    }
+}
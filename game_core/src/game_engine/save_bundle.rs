@@ -0,0 +1,173 @@
+use std::{collections::BTreeMap, env, fs::{self, File}, io::BufReader, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{features::destination::Destination, lang::localizable::LocalizableText, log_info, menus::toasts::Toast, utils::{files::write_atomically, logger::Subsystem}, worlds::world_overlay::WorldOverlay};
+
+use super::{engine::GameEngine, entity::Entity, errors::{GameError, GameResult}, migrations::{from_reader, to_versioned_json, DataKind}, save_slots::now, storage::StoredValues};
+
+/// Everything needed to reproduce a save on another machine, in a single file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveBundle {
+    pub build: BuildInfo,
+    /// Seconds since the unix epoch
+    pub exported_at: u64,
+    pub current_world: u32,
+    pub hero_x: i32,
+    pub hero_y: i32,
+    pub playtime: f32,
+    pub storage: StoredValues,
+    pub inventory: Vec<Entity>,
+    #[serde(default)]
+    pub overlays: BTreeMap<u32, WorldOverlay>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub debug: bool,
+    pub creative_mode: bool,
+}
+
+impl BuildInfo {
+    fn current(creative_mode: bool) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            os: env::consts::OS.to_owned(),
+            arch: env::consts::ARCH.to_owned(),
+            debug: cfg!(debug_assertions),
+            creative_mode,
+        }
+    }
+}
+
+impl SaveBundle {
+    pub fn read(path: &Path) -> GameResult<Self> {
+        let file = File::open(path).map_err(|e| GameError::io(path, e))?;
+        from_reader(DataKind::Bundle, path, BufReader::new(file))
+    }
+
+    pub fn write(&self, path: &Path) -> GameResult<()> {
+        let serialized = to_versioned_json(DataKind::Bundle, self).map_err(|e| GameError::invalid_data(path, e))?;
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder).map_err(|e| GameError::io(folder, e))?;
+        }
+        write_atomically(path, serialized.as_bytes())
+    }
+}
+
+impl GameEngine {
+    fn save_bundle(&mut self) -> GameResult<SaveBundle> {
+        self.save_overlay();
+        let hero = self.world.cached_hero_props.frame;
+
+        Ok(SaveBundle {
+            build: BuildInfo::current(self.creative_mode),
+            exported_at: now(),
+            current_world: self.world.id,
+            hero_x: hero.x,
+            hero_y: hero.y,
+            playtime: self.playtime,
+            storage: self.context.storage.values(),
+            inventory: self.context.inventory.items(),
+            overlays: self.context.overlays.all()?,
+        })
+    }
+
+    /// Writes the current save to a single file, returns false and reports the error if that failed.
+    pub fn export_save_bundle(&mut self, path: &Path) -> bool {
+        match self.save_bundle().and_then(|bundle| bundle.write(path)) {
+            Ok(_) => {
+                log_info!(self.context.logger, Subsystem::Engine, "Exported save bundle to {}", path.display());
+                true
+            }
+            Err(error) => {
+                self.show_error(&error);
+                false
+            }
+        }
+    }
+
+    /// Exports to a new file in `Config::bundles_path`, for the game menu.
+    pub fn export_save_bundle_to_bundles_folder(&mut self) {
+        let path = self.context.config.bundles_path().join(format!("bundle_{}.json", now()));
+
+        if self.export_save_bundle(&path) {
+            let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            self.toast.show(&Toast::regular("save_bundle.exported".localized(&self.context.strings).replace("%s", &name)));
+        }
+    }
+
+    /// Replaces the progress of the given slot with the bundle and resumes from there.
+    /// Without save slots the bundle replaces the single save and `slot` is ignored.
+    /// Returns false, and reports the error, if the bundle could not be imported.
+    pub fn import_save_bundle(&mut self, path: &Path, slot: u32) -> bool {
+        match self.import(path, slot) {
+            Ok(bundle) => {
+                log_info!(self.context.logger, Subsystem::Engine, "Imported save bundle from {}", path.display());
+                self.teleport(&Destination::new(bundle.current_world, bundle.hero_x, bundle.hero_y));
+                true
+            }
+            Err(error) => {
+                self.show_error(&error);
+                false
+            }
+        }
+    }
+
+    fn import(&mut self, path: &Path, slot: u32) -> GameResult<SaveBundle> {
+        let bundle = SaveBundle::read(path)?;
+        self.save_overlay();
+
+        if self.context.config.uses_save_slots() {
+            self.switch_to_slot(slot)?;
+        }
+        self.detach_world_from_save();
+        self.context.storage.replace_values(bundle.storage.clone());
+        self.context.inventory.replace_items(bundle.inventory.clone());
+        self.context.overlays.replace_all(bundle.overlays.clone())?;
+        self.playtime = bundle.playtime;
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::{config::Config, entities::{known_species::SPECIES_KUNAI, species::EntityType}, game_engine::{context::GameContext, inventory::InventoryStorage, state_updates::WorldStateUpdate, storage::{KeyValueStorage, StorageValue}}, testing::harness::TestHarness};
+
+    #[test]
+    fn exported_bundle_restores_the_save_into_another_slot() {
+        let folder = env::temp_dir().join(format!("nokemon-bundles-{}", process::id()));
+        let bundle_path = folder.join("bundle.json");
+
+        let mut game = TestHarness::new();
+        game.teleport(1002, 10, 10);
+        game.engine.context.storage.set_value_for_key("bundle.key", 3);
+        game.engine.context.inventory.add(game.engine.context.make_entity(SPECIES_KUNAI), &game.engine.context.ids);
+        let bundle = game.engine.world.entities.borrow().iter().find(|e| e.entity_type == EntityType::Bundle).unwrap().id;
+        game.engine.world.apply_state_updates(vec![WorldStateUpdate::RemoveEntity(bundle)]);
+        let hero = game.engine.world.cached_hero_props.frame;
+        assert!(game.engine.export_save_bundle(&bundle_path));
+
+        let config = Config { saves_path: folder.join("saves"), ..Config::for_tests() };
+        let context = GameContext::with_storage(config, KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+        assert!(game.engine.import_save_bundle(&bundle_path, 2));
+        game.wait(0.5);
+
+        assert_eq!(game.engine.current_slot, Some(2));
+        assert!(!game.engine.slot_picker.is_open());
+        game.assert_storage("bundle.key", Some(StorageValue::Int(3)));
+        assert!(game.engine.context.inventory.contains_species(SPECIES_KUNAI));
+        assert_eq!(game.engine.world.id, 1002);
+        assert_eq!((game.engine.world.cached_hero_props.frame.x, game.engine.world.cached_hero_props.frame.y), (hero.x, hero.y));
+        assert!(game.engine.world.index_for_entity(bundle).is_none());
+
+        game.engine.exit();
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
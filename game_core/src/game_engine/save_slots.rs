@@ -0,0 +1,217 @@
+use std::{fs, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, constants::{SAVE_SLOTS_COUNT, SAVE_SLOT_PREVIEW_SIZE}, utils::files::write_atomically};
+
+use super::{errors::{GameError, GameResult}, storage::{KeyValueStorage, StorageKey}, world::World};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveSlot {
+    pub id: u32,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotMetadata {
+    pub slot: u32,
+    pub playtime: f32,
+    pub current_world: u32,
+    /// Seconds since the unix epoch
+    pub last_saved: u64,
+    /// Biomes around the hero, one string per row, same encoding as world files
+    pub preview: Vec<String>,
+}
+
+pub fn save_slots(config: &Config) -> Vec<SaveSlot> {
+    (1..=SAVE_SLOTS_COUNT).map(|id| SaveSlot::new(id, &config.saves_path)).collect()
+}
+
+/// Progress of versions without save slots becomes slot 1, unless slot 1 was already used.
+/// Files are copied rather than moved, the inventory may be read-only, like in the iOS bundle.
+pub fn adopt_legacy_save(config: &Config, storage: &KeyValueStorage) -> GameResult<()> {
+    let slot = SaveSlot::new(1, &config.saves_path);
+    if slot.path.exists() || !config.key_value_storage_path.exists() {
+        return Ok(())
+    }
+    slot.prepare()?;
+    copy_if_exists(&config.key_value_storage_path, &slot.storage_path())?;
+    copy_if_exists(&config.inventory_path, &slot.inventory_path())?;
+
+    if let Ok(overlays) = fs::read_dir(config.overlays_path()) {
+        fs::create_dir_all(slot.overlays_path()).map_err(|e| GameError::io(&slot.overlays_path(), e))?;
+        for entry in overlays.flatten() {
+            copy_if_exists(&entry.path(), &slot.overlays_path().join(entry.file_name()))?;
+        }
+    }
+    let current_world = storage.get_value_for_key(&StorageKey::latest_world()).unwrap_or_default();
+    slot.save_metadata(&SlotMetadata { slot: slot.id, playtime: 0.0, current_world, last_saved: now(), preview: vec![] })
+}
+
+fn copy_if_exists(from: &Path, to: &Path) -> GameResult<()> {
+    match fs::copy(from, to) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(GameError::io(from, e))
+    }
+}
+
+impl SaveSlot {
+    pub fn new(id: u32, saves_path: &Path) -> Self {
+        Self { id, path: saves_path.join(format!("slot_{}", id)) }
+    }
+
+    pub fn storage_path(&self) -> PathBuf {
+        self.path.join("save.json")
+    }
+
+    pub fn inventory_path(&self) -> PathBuf {
+        self.path.join("inventory.json")
+    }
+
+    pub fn overlays_path(&self) -> PathBuf {
+        self.path.join("overlays")
+    }
+
+    pub fn quick_save_path(&self) -> PathBuf {
+        self.path.join("quicksave.json")
+    }
+
+    pub fn metadata_path(&self) -> PathBuf {
+        self.path.join("metadata.json")
+    }
+
+    /// Creates the slot folder, so that storage and inventory can be saved in it
+    pub fn prepare(&self) -> GameResult<()> {
+        fs::create_dir_all(&self.path).map_err(|e| GameError::io(&self.path, e))
+    }
+
+    /// None for slots that were never used
+    pub fn metadata(&self) -> GameResult<Option<SlotMetadata>> {
+        let path = self.metadata_path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map(Some).map_err(|e| GameError::invalid_data(&path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(GameError::io(&path, e))
+        }
+    }
+
+    pub fn save_metadata(&self, metadata: &SlotMetadata) -> GameResult<()> {
+        let path = self.metadata_path();
+        let serialized = serde_json::to_string_pretty(metadata).map_err(|e| GameError::invalid_data(&path, e))?;
+        self.prepare()?;
+        write_atomically(&path, serialized.as_bytes())
+    }
+}
+
+impl SlotMetadata {
+    pub fn new(slot: u32, playtime: f32, world: &World) -> Self {
+        Self {
+            slot,
+            playtime,
+            current_world: world.id,
+            last_saved: now(),
+            preview: preview_of(world),
+        }
+    }
+
+    pub fn seconds_since_last_save(&self) -> u64 {
+        now().saturating_sub(self.last_saved)
+    }
+}
+
+fn preview_of(world: &World) -> Vec<String> {
+    let hero = world.cached_hero_props.frame;
+    let half_size = SAVE_SLOT_PREVIEW_SIZE / 2;
+    let tiles = &world.biome_tiles.tiles;
+
+    (hero.y - half_size..hero.y - half_size + SAVE_SLOT_PREVIEW_SIZE).map(|row| {
+        (hero.x - half_size..hero.x - half_size + SAVE_SLOT_PREVIEW_SIZE).map(|col| {
+            if row < 0 || col < 0 {
+                return '0'
+            }
+            tiles.get(row as usize)
+                .and_then(|tiles_row| tiles_row.get(col as usize))
+                .map(|tile| tile.tile_type.to_char())
+                .unwrap_or('0')
+        }).collect()
+    }).collect()
+}
+
+/// Seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::{config::Config, game_engine::{context::GameContext, inventory::InventoryStorage, storage::KeyValueStorage}, testing::harness::TestHarness};
+
+    use super::{save_slots, SaveSlot, SlotMetadata};
+
+    #[test]
+    fn each_slot_keeps_its_own_progress() {
+        let saves_path = env::temp_dir().join(format!("nokemon-saves-{}", process::id()));
+        let config = Config { saves_path: saves_path.clone(), ..Config::for_tests() };
+        let slots = save_slots(&config);
+        let context = GameContext::with_storage(config, KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+
+        assert!(game.engine.slot_picker.is_open());
+        assert!(game.engine.select_slot(2));
+        game.engine.context.storage.set_value_for_key("slot.key", 7);
+        game.engine.playtime = 42.0;
+        game.engine.save_slot_metadata();
+
+        let metadata = slots[1].metadata().unwrap().unwrap();
+        assert_eq!(metadata, SlotMetadata { last_saved: metadata.last_saved, ..SlotMetadata::new(2, 42.0, &game.engine.world) });
+        assert!(!metadata.preview.is_empty());
+        assert!(slots[0].metadata().unwrap().is_none());
+
+        assert!(game.engine.select_slot(1));
+        assert_eq!(game.engine.context.storage.get_value_for_key("slot.key"), None);
+        assert_eq!(game.engine.playtime, 0.0);
+        game.engine.exit();
+
+        let config = Config { saves_path: saves_path.clone(), ..Config::for_tests() };
+        let context = GameContext::with_storage(config, KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+        assert!(game.engine.select_slot(2));
+        assert_eq!(game.engine.context.storage.get_value_for_key("slot.key"), Some(7));
+        assert_eq!(game.engine.playtime, 42.0);
+        assert_eq!(game.engine.world.id, metadata.current_world);
+
+        game.engine.exit();
+        fs::remove_dir_all(saves_path).unwrap();
+    }
+
+    #[test]
+    fn legacy_save_becomes_the_first_slot_once() {
+        let folder = env::temp_dir().join(format!("nokemon-legacy-{}", process::id()));
+        fs::create_dir_all(folder.join("overlays")).unwrap();
+        fs::write(folder.join("save.json"), "{ \"latest_world\": 1002, \"legacy.key\": 3 }").unwrap();
+        fs::write(folder.join("inventory.json"), "[]").unwrap();
+        fs::write(folder.join("overlays").join("1002.json"), "{}").unwrap();
+
+        let config = Config { key_value_storage_path: folder.join("save.json"), inventory_path: folder.join("inventory.json"), saves_path: folder.join("saves"), ..Config::for_tests() };
+        let slot = SaveSlot::new(1, &config.saves_path);
+        let mut game = TestHarness::with_context(GameContext::new(config.clone()).unwrap());
+
+        assert_eq!(slot.metadata().unwrap().unwrap().current_world, 1002);
+        assert!(slot.inventory_path().exists());
+        assert!(slot.overlays_path().join("1002.json").exists());
+        assert!(game.engine.select_slot(1));
+        assert_eq!(game.engine.context.storage.get_value_for_key("legacy.key"), Some(3));
+
+        game.engine.context.storage.set("slot.key", true);
+        game.engine.exit();
+        let mut game = TestHarness::with_context(GameContext::new(config).unwrap());
+        assert!(game.engine.select_slot(1));
+        assert_eq!(game.engine.context.storage.get_value_for_key("slot.key"), Some(1));
+
+        game.engine.exit();
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
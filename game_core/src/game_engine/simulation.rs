@@ -0,0 +1,41 @@
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::ids::IdGenerator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SimulationMode {
+    RealTime { seed: u64 },
+    Deterministic { seed: u64, time_step: f32 },
+}
+
+impl SimulationMode {
+    pub fn real_time() -> Self {
+        SimulationMode::RealTime { seed: rand::random() }
+    }
+
+    pub fn seed(&self) -> u64 {
+        match self {
+            SimulationMode::RealTime { seed } => *seed,
+            SimulationMode::Deterministic { seed, time_step: _ } => *seed,
+        }
+    }
+
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed())
+    }
+
+    pub fn ids(&self) -> IdGenerator {
+        match self {
+            SimulationMode::RealTime { seed: _ } => IdGenerator::new(),
+            SimulationMode::Deterministic { seed, time_step: _ } => IdGenerator::seeded(*seed),
+        }
+    }
+
+    pub fn time_step(&self, time_since_last_update: f32) -> f32 {
+        match self {
+            SimulationMode::RealTime { seed: _ } => time_since_last_update,
+            SimulationMode::Deterministic { seed: _, time_step } => *time_step,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{dialogues::menu::DialogueMenu, lang::localizable::LocalizableText, log_info, menus::{entity_options::EntityOptionsMenu, toasts::Toast}, utils::{files::write_atomically, logger::Subsystem, rect::IntRect, vector::Vector2d}, worlds::world_overlay::WorldOverlay};
+
+use super::{engine::GameEngine, entity::Entity, errors::{GameError, GameResult}, save_slots::SaveSlot, storage::StoredValues, world::World};
+
+/// Runtime state of the engine and of the current world, enough to resume mid-fight.
+/// Tiles are not included, they come from the world file and its overlay.
+/// Menus, dialogues and toasts are not included either, they are all closed on load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub playtime: f32,
+    pub camera_viewport: IntRect,
+    pub camera_viewport_offset: Vector2d,
+    pub time_to_next_spawn: f32,
+    #[serde(default)]
+    pub creep_spawner_seed: u64,
+    pub world: WorldSnapshot,
+    pub storage: StoredValues,
+    pub inventory: Vec<Entity>,
+    #[serde(default)]
+    pub overlays: BTreeMap<u32, WorldOverlay>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub id: u32,
+    pub total_elapsed_time: f32,
+    pub time_since_off_screen_update: f32,
+    pub creep_spawn_enabled: bool,
+    pub creep_spawn_interval: f32,
+    pub pressure_plate_down_red: bool,
+    pub pressure_plate_down_green: bool,
+    pub pressure_plate_down_blue: bool,
+    pub pressure_plate_down_silver: bool,
+    pub pressure_plate_down_yellow: bool,
+    /// All entities, hero included, with their runtime state
+    pub entities: Vec<Entity>,
+}
+
+impl EngineSnapshot {
+    pub fn read(path: &Path) -> GameResult<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| GameError::io(path, e))?;
+        serde_json::from_str(&contents).map_err(|e| GameError::invalid_data(path, e))
+    }
+
+    pub fn write(&self, path: &Path) -> GameResult<()> {
+        let serialized = serde_json::to_string(self).map_err(|e| GameError::invalid_data(path, e))?;
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder).map_err(|e| GameError::io(folder, e))?;
+        }
+        write_atomically(path, serialized.as_bytes())
+    }
+}
+
+impl WorldSnapshot {
+    fn of(world: &World) -> Self {
+        Self {
+            id: world.id,
+            total_elapsed_time: world.total_elapsed_time,
+            time_since_off_screen_update: world.time_since_off_screen_update,
+            creep_spawn_enabled: world.creep_spawn_enabled,
+            creep_spawn_interval: world.creep_spawn_interval,
+            pressure_plate_down_red: world.pressure_plate_down_red,
+            pressure_plate_down_green: world.pressure_plate_down_green,
+            pressure_plate_down_blue: world.pressure_plate_down_blue,
+            pressure_plate_down_silver: world.pressure_plate_down_silver,
+            pressure_plate_down_yellow: world.pressure_plate_down_yellow,
+            entities: world.entities.borrow().clone(),
+        }
+    }
+
+    fn apply_to(&self, world: &mut World) {
+        world.total_elapsed_time = self.total_elapsed_time;
+        world.time_since_off_screen_update = self.time_since_off_screen_update;
+        world.creep_spawn_enabled = self.creep_spawn_enabled;
+        world.creep_spawn_interval = self.creep_spawn_interval;
+        world.pressure_plate_down_red = self.pressure_plate_down_red;
+        world.pressure_plate_down_green = self.pressure_plate_down_green;
+        world.pressure_plate_down_blue = self.pressure_plate_down_blue;
+        world.pressure_plate_down_silver = self.pressure_plate_down_silver;
+        world.pressure_plate_down_yellow = self.pressure_plate_down_yellow;
+        *world.entities.borrow_mut() = self.entities.clone();
+        world.reindex_entities();
+        world.update_tiles_hitmap();
+        world.update_hitmaps();
+        world.visible_entities = world.compute_visible_entities(&world.bounds);
+    }
+}
+
+impl GameEngine {
+    /// Quick save of the current slot, or next to the single save without slots.
+    pub fn quick_save_path(&self) -> PathBuf {
+        match self.current_slot {
+            Some(id) => SaveSlot::new(id, &self.context.config.saves_path).quick_save_path(),
+            None => self.context.config.quick_save_path()
+        }
+    }
+
+    fn snapshot(&mut self) -> GameResult<EngineSnapshot> {
+        self.save_overlay();
+
+        Ok(EngineSnapshot {
+            playtime: self.playtime,
+            camera_viewport: self.camera_viewport,
+            camera_viewport_offset: self.camera_viewport_offset,
+            time_to_next_spawn: self.creep_spawner.time_to_next_spawn,
+            creep_spawner_seed: self.creep_spawner.reseed(),
+            world: WorldSnapshot::of(&self.world),
+            storage: self.context.storage.values(),
+            inventory: self.context.inventory.items(),
+            overlays: self.context.overlays.all()?,
+        })
+    }
+
+    /// Writes the full engine state to `quick_save_path`, returns false and reports the error if that failed.
+    pub fn quick_save(&mut self) -> bool {
+        let path = self.quick_save_path();
+
+        match self.snapshot().and_then(|snapshot| snapshot.write(&path)) {
+            Ok(_) => {
+                log_info!(self.context.logger, Subsystem::Engine, "Quick saved to {}", path.display());
+                true
+            }
+            Err(error) => {
+                self.show_error(&error);
+                false
+            }
+        }
+    }
+
+    /// Puts the engine back in the state of the last quick save, menus and dialogues are closed.
+    /// Returns false if there's no quick save, or if it could not be loaded, reporting the error.
+    pub fn quick_load(&mut self) -> bool {
+        let path = self.quick_save_path();
+        if !path.exists() {
+            return false
+        }
+        match EngineSnapshot::read(&path).and_then(|snapshot| self.restore(snapshot)) {
+            Ok(_) => {
+                log_info!(self.context.logger, Subsystem::Engine, "Quick loaded from {}", path.display());
+                true
+            }
+            Err(error) => {
+                self.show_error(&error);
+                false
+            }
+        }
+    }
+
+    pub(super) fn quick_save_from_menu(&mut self) {
+        if self.quick_save() {
+            self.toast.show(&Toast::regular("quick_save.saved".localized(&self.context.strings)));
+        }
+    }
+
+    pub(super) fn quick_load_from_menu(&mut self) {
+        if !self.quick_save_path().exists() {
+            self.toast.show(&Toast::regular("quick_save.missing".localized(&self.context.strings)));
+        } else if self.quick_load() {
+            self.toast.show(&Toast::regular("quick_save.loaded".localized(&self.context.strings)));
+        }
+    }
+
+    fn restore(&mut self, snapshot: EngineSnapshot) -> GameResult<()> {
+        self.detach_world_from_save();
+        self.context.storage.replace_values(snapshot.storage);
+        self.context.inventory.replace_items(snapshot.inventory);
+        self.context.overlays.replace_all(snapshot.overlays)?;
+
+        let mut world = World::load_or_create(snapshot.world.id, self.context.clone())?;
+        world.creative_mode = self.creative_mode;
+        snapshot.world.apply_to(&mut world);
+        world.load_entrance();
+        world.update(0.001);
+        self.world = world;
+
+        self.playtime = snapshot.playtime;
+        self.camera_viewport = snapshot.camera_viewport;
+        self.camera_viewport_offset = snapshot.camera_viewport_offset;
+        self.world.visible_bounds = self.camera_viewport;
+        self.creep_spawner.time_to_next_spawn = snapshot.time_to_next_spawn;
+        self.creep_spawner.restore_seed(snapshot.creep_spawner_seed);
+
+        self.menu.close();
+        self.menu.current_world_id = self.world.id;
+        self.dialogue_menu = DialogueMenu::new();
+        self.entity_options_menu = EntityOptionsMenu::new(&self.context.strings);
+        self.long_text_display.close();
+        self.death_screen.close();
+        self.keyboard.on_world_changed();
+        self.mouse.on_world_changed();
+        self.save_slot_metadata();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::{config::Config, entities::species::EntityType, game_engine::{context::GameContext, inventory::InventoryStorage, storage::{KeyValueStorage, StorageValue}}, testing::harness::TestHarness, utils::directions::Direction};
+
+    #[test]
+    fn quick_load_restores_the_engine_as_it_was_saved() {
+        let saves_path = env::temp_dir().join(format!("nokemon-quicksave-{}", process::id()));
+        let config = Config { saves_path: saves_path.clone(), ..Config::for_tests() };
+        let context = GameContext::with_storage(config, KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+        assert!(game.engine.select_slot(1));
+        assert!(!game.engine.quick_load());
+
+        game.teleport(1002, 0, 0).walk(Direction::Right, 2).face(Direction::Left);
+        game.engine.context.storage.set_value_for_key("quick.key", 1);
+        game.engine.world.pressure_plate_down_red = true;
+        let hero = game.engine.world.cached_hero_props.frame;
+        let entities = game.engine.world.entities.borrow().len();
+        let playtime = game.engine.playtime;
+        assert!(game.engine.quick_save());
+
+        game.engine.context.storage.set_value_for_key("quick.key", 2);
+        game.walk(Direction::Right, 1).teleport(1001, 0, 0);
+        assert!(game.engine.quick_load());
+
+        assert_eq!(game.engine.world.id, 1002);
+        assert_eq!(game.engine.world.cached_hero_props.frame, hero);
+        assert_eq!(game.engine.world.cached_hero_props.direction, Direction::Left);
+        assert_eq!(game.engine.world.entities.borrow().len(), entities);
+        assert!(game.engine.world.pressure_plate_down_red);
+        assert_eq!(game.engine.playtime, playtime);
+        game.assert_storage("quick.key", Some(StorageValue::Int(1)));
+
+        game.engine.exit();
+        fs::remove_dir_all(saves_path).unwrap();
+    }
+
+    #[test]
+    fn creeps_spawn_the_same_way_after_a_quick_load() {
+        let saves_path = env::temp_dir().join(format!("nokemon-quickspawn-{}", process::id()));
+        let config = Config { saves_path: saves_path.clone(), ..Config::for_tests() };
+        let context = GameContext::with_storage(config, KeyValueStorage::in_memory(), InventoryStorage::in_memory()).unwrap();
+        let mut game = TestHarness::with_context(context);
+        assert!(game.engine.select_slot(1));
+        game.teleport(1002, 0, 0).wait(0.5);
+        game.engine.world.creep_spawn_enabled = true;
+        game.engine.world.creep_spawn_interval = 0.2;
+        assert!(game.engine.quick_save());
+
+        let creeps = |game: &mut TestHarness| {
+            game.wait(2.0);
+            game.engine.world.entities.borrow().iter()
+                .filter(|entity| entity.entity_type == EntityType::Npc && entity.id >= 0x8000_0000)
+                .map(|entity| (entity.species_id, entity.frame))
+                .collect::<Vec<_>>()
+        };
+        let spawned = creeps(&mut game);
+        assert!(!spawned.is_empty());
+
+        assert!(game.engine.quick_load());
+        assert_eq!(creeps(&mut game), spawned);
+
+        game.engine.exit();
+        fs::remove_dir_all(saves_path).unwrap();
+    }
+}
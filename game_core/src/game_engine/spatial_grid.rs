@@ -0,0 +1,107 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::utils::rect::IntRect;
+
+use super::entity::EntityId;
+
+const CELL_SIZE: i32 = 8;
+
+/// Buckets entity ids by coarse cells of `CELL_SIZE` tiles, so that area queries
+/// only look at the entities around the area instead of the whole world.
+/// Frames are inclusive of their right and bottom edge, same as the viewport checks.
+#[derive(Debug, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<(i32, i32), Vec<EntityId>>,
+    ranges: HashMap<EntityId, IntRect>,
+}
+
+impl SpatialGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, id: EntityId, frame: &IntRect) {
+        self.remove(id);
+        let range = cells_range(frame);
+        for_each_cell(&range, |cell| self.cells.entry(cell).or_default().push(id));
+        self.ranges.insert(id, range);
+    }
+
+    pub fn remove(&mut self, id: EntityId) {
+        if let Some(range) = self.ranges.remove(&id) {
+            for_each_cell(&range, |cell| {
+                if let Some(ids) = self.cells.get_mut(&cell) {
+                    ids.retain(|other| *other != id);
+                    if ids.is_empty() {
+                        self.cells.remove(&cell);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Cheap when the entity is still within the same cells, which is the case most frames.
+    pub fn update(&mut self, id: EntityId, frame: &IntRect) {
+        if self.ranges.get(&id) != Some(&cells_range(frame)) {
+            self.insert(id, frame);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.ranges.clear();
+    }
+
+    /// Ids of the entities in the cells touched by `area`, callers still need to check the exact frames.
+    pub fn candidates(&self, area: &IntRect) -> BTreeSet<EntityId> {
+        let mut ids = BTreeSet::new();
+        for_each_cell(&cells_range(area), |cell| {
+            if let Some(cell_ids) = self.cells.get(&cell) {
+                ids.extend(cell_ids.iter().copied());
+            }
+        });
+        ids
+    }
+}
+
+fn cells_range(frame: &IntRect) -> IntRect {
+    let min_col = frame.x.div_euclid(CELL_SIZE);
+    let min_row = frame.y.div_euclid(CELL_SIZE);
+    let max_col = (frame.x + frame.w.max(0)).div_euclid(CELL_SIZE);
+    let max_row = (frame.y + frame.h.max(0)).div_euclid(CELL_SIZE);
+    IntRect::new(min_col, min_row, max_col - min_col, max_row - min_row)
+}
+
+fn for_each_cell(range: &IntRect, mut f: impl FnMut((i32, i32))) {
+    for row in range.y..=(range.y + range.h) {
+        for col in range.x..=(range.x + range.w) {
+            f((col, row));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::rect::IntRect;
+
+    use super::SpatialGrid;
+
+    #[test]
+    fn finds_entities_around_an_area_and_follows_moves() {
+        let mut grid = SpatialGrid::new();
+        grid.insert(1, &IntRect::new(2, 2, 1, 1));
+        grid.insert(2, &IntRect::new(40, 40, 2, 2));
+        grid.insert(3, &IntRect::new(15, 3, 1, 2));
+
+        let ids: Vec<u32> = grid.candidates(&IntRect::new(0, 0, 10, 10)).into_iter().collect();
+        assert_eq!(ids, vec![1, 3]);
+
+        grid.update(1, &IntRect::new(41, 41, 1, 1));
+        grid.remove(3);
+
+        let ids: Vec<u32> = grid.candidates(&IntRect::new(0, 0, 10, 10)).into_iter().collect();
+        assert!(ids.is_empty());
+        let ids: Vec<u32> = grid.candidates(&IntRect::new(38, 38, 4, 4)).into_iter().collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+}
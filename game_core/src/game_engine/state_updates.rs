@@ -1,6 +1,8 @@
-use crate::{dialogues::models::Dialogue, entities::{npcs::NpcId, species::SpeciesId}, features::destination::Destination, maps::{biome_tiles::Biome, constructions_tiles::Construction}, menus::toasts::Toast, utils::vector::Vector2d};
+use std::path::PathBuf;
 
-use super::{entity::{Entity, EntityId, EntityProps}, locks::LockType};
+use crate::{dialogues::models::Dialogue, entities::{npcs::NpcId, species::SpeciesId}, features::{destination::Destination, health::DamageType}, maps::{biome_tiles::Biome, constructions_tiles::Construction}, menus::toasts::Toast, utils::{directions::Direction, vector::Vector2d}};
+
+use super::{errors::GameError, entity::{Entity, EntityId, EntityProps}, locks::LockType, storage::StorageValue};
 
 #[derive(Debug, Clone)]
 pub enum WorldStateUpdate {
@@ -20,7 +22,14 @@ pub enum WorldStateUpdate {
     ConstructionTileChange(usize, usize, Construction),
     EngineUpdate(EngineStateUpdate),
     HandleHit(EntityId, EntityId),
-    SetPressurePlateState(LockType, bool)
+    /// Species of the swing and the entity it hit
+    HandleMeleeHit(SpeciesId, EntityId),
+    EquipWeapon(SpeciesId),
+    /// Damage before armor and resistances, and the direction the hero is pushed towards
+    DamageHero(u32, DamageType, Direction),
+    SetPressurePlateState(LockType, bool),
+    /// Key as written in level files, `world:` and `entity:` point to the current world and to the entity
+    SetStorageValue(EntityId, String, StorageValue)
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +37,9 @@ pub enum EngineStateUpdate {
     CenterCamera(i32, i32, Vector2d),
     Teleport(Destination),
     SaveGame,
+    QuickSave,
+    QuickLoad,
+    ExportSave,
     Exit,
     ShowEntityOptions(Box<Entity>),
     ShowInventoryOptions(Box<Entity>),
@@ -39,17 +51,19 @@ pub enum EngineStateUpdate {
     DisplayLongText(String),
     DeathScreen,
     ResumeGame,
+    Error(GameError),
+    RestoreBackup(PathBuf),
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{entities::{known_species::SPECIES_HERO, species::make_entity_by_species}, game_engine::engine::GameEngine};
+    use crate::{entities::known_species::SPECIES_HERO, game_engine::{context::GameContext, engine::GameEngine, simulation::SimulationMode}};
 
     #[test]
     fn entity_can_relay_world_state_updates() {
-        let mut engine = GameEngine::new();
+        let mut engine = GameEngine::with_context(GameContext::for_tests(), SimulationMode::real_time());
         engine.start();
-        let hero = make_entity_by_species(SPECIES_HERO);
+        let hero = engine.context.make_entity(SPECIES_HERO);
         let (hero_index, _) = engine.world.add_entity(hero);
 
         let mut entities = engine.world.entities.borrow_mut();
@@ -61,9 +75,9 @@ mod tests {
 
     #[test]
     fn entity_can_relay_engine_state_updates() {
-        let mut engine = GameEngine::new();
+        let mut engine = GameEngine::with_context(GameContext::for_tests(), SimulationMode::real_time());
         engine.start();
-        let hero = make_entity_by_species(SPECIES_HERO);
+        let hero = engine.context.make_entity(SPECIES_HERO);
         engine.world.add_entity(hero);
 
         engine.world.update(1.0);
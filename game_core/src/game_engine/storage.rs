@@ -1,9 +1,10 @@
-use std::{collections::BTreeMap, fs::File, io::{BufReader, Write}, sync::{mpsc::{self, Sender}, RwLock}, thread};
-use lazy_static::lazy_static;
+use std::{collections::BTreeMap, fs::File, io::{BufReader, ErrorKind}, path::{Path, PathBuf}, sync::{mpsc::{self, Sender}, RwLock}, thread::{self, JoinHandle}};
 
-use crate::config::config;
+use serde::{Deserialize, Serialize};
 
-use super::{locks::{PRESSURE_PLATE_BLUE, PRESSURE_PLATE_GREEN, PRESSURE_PLATE_RED, PRESSURE_PLATE_SILVER, PRESSURE_PLATE_YELLOW}, world::World};
+use crate::{log_debug, log_error, utils::{files::{back_up, write_atomically}, logger::{Logger, Subsystem}}};
+
+use super::{entity::EntityId, errors::{GameError, GameResult}, locks::{PRESSURE_PLATE_BLUE, PRESSURE_PLATE_GREEN, PRESSURE_PLATE_RED, PRESSURE_PLATE_SILVER, PRESSURE_PLATE_YELLOW}, world::World};
 
 pub struct StorageKey {}
 
@@ -19,70 +20,281 @@ impl StorageKey {
     pub fn latest_world() -> String {
         "latest_world".to_owned()
     }
+
+    pub fn hero_x() -> String {
+        "hero.x".to_owned()
+    }
+
+    pub fn hero_y() -> String {
+        "hero.y".to_owned()
+    }
+
+    pub fn hero_direction() -> String {
+        "hero.direction".to_owned()
+    }
+
+    pub fn hero_hp() -> String {
+        "hero.hp".to_owned()
+    }
+
+    pub fn hero_weapon() -> String {
+        "hero.weapon".to_owned()
+    }
+
+    pub fn in_world(world_id: u32, key: &str) -> String {
+        format!("world.{}.{}", world_id, key)
+    }
+
+    pub fn of_entity(entity_id: EntityId, key: &str) -> String {
+        format!("entity.{}.{}", entity_id, key)
+    }
 }
 
-fn load_stored_values() -> BTreeMap<String, u32> {
-    let file = File::open(config().key_value_storage_path.clone()).expect("Failed to open save.json file");
-    let reader = BufReader::new(file);
-    serde_json::from_reader(reader).expect("Failed to deserialize save file from JSON")
+/// Where `world:` and `entity:` keys, as written in level files, point to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageScope {
+    pub world_id: u32,
+    pub entity_id: EntityId,
 }
 
-fn save_stored_values(data: &BTreeMap<String, u32>) {
-    if let Ok(serialized_world) = serde_json::to_string_pretty(data) {
-        if let Ok(mut file) = File::create(config().key_value_storage_path.clone()) {
-            if let Err(e) = file.write_all(serialized_world.as_bytes()) {
-                eprintln!("Failed to write save file: {}", e);
-            } else {
-                println!("Data saved successfully to storage.json");
-            }
+impl StorageScope {
+    pub fn new(world_id: u32, entity_id: EntityId) -> Self {
+        Self { world_id, entity_id }
+    }
+
+    /// `world:key` and `entity:key` become keys of the current world and entity, other keys are global.
+    pub fn resolve(&self, key: &str) -> String {
+        if let Some(key) = key.strip_prefix("world:") {
+            StorageKey::in_world(self.world_id, key)
+        } else if let Some(key) = key.strip_prefix("entity:") {
+            StorageKey::of_entity(self.entity_id, key)
         } else {
-            eprintln!("Failed to create save file");
+            key.to_owned()
         }
-    } else {
-        eprintln!("Failed to serialize data");
     }
 }
 
-lazy_static! {
-    static ref KEY_VALUE_STORAGE: RwLock<BTreeMap<String, u32>> = RwLock::new(load_stored_values());
-    
-    static ref SAVE_THREAD: (Sender<BTreeMap<String, u32>>, thread::JoinHandle<()>) = {
-        let (tx, rx) = mpsc::channel::<BTreeMap<String, u32>>();
-        
-        let handle = thread::spawn(move || {
-            while let Ok(data) = rx.recv() {
-                save_stored_values(&data);
-            }
-        });
-        
-        (tx, handle)
-    };
+/// Stored as plain json, so numbers saved by older versions read as `Int`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StorageValue {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+    List(Vec<StorageValue>),
 }
 
-pub fn get_value_for_key(key: &str) -> Option<u32> {
-    if key == StorageKey::always() {
-        return Some(1)
+impl StorageValue {
+    /// Bools count as 0 and 1, like they always did before values had a type.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            StorageValue::Bool(value) => Some(if *value { 1 } else { 0 }),
+            StorageValue::Int(value) => Some(*value),
+            StorageValue::Text(_) | StorageValue::List(_) => None
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            StorageValue::Bool(value) => *value,
+            StorageValue::Int(value) => *value != 0,
+            StorageValue::Text(value) => !value.is_empty(),
+            StorageValue::List(values) => !values.is_empty()
+        }
     }
-    let storage = KEY_VALUE_STORAGE.read().unwrap();
-    storage.get(key).cloned()
 }
 
-pub fn set_value_for_key(key: &str, value: u32) {
-    {
-        let mut storage = KEY_VALUE_STORAGE.write().unwrap();
-        storage.insert(key.to_owned(), value);
+impl From<bool> for StorageValue {
+    fn from(value: bool) -> Self {
+        StorageValue::Bool(value)
+    }
+}
+
+impl From<u32> for StorageValue {
+    fn from(value: u32) -> Self {
+        StorageValue::Int(value as i64)
+    }
+}
+
+impl From<i64> for StorageValue {
+    fn from(value: i64) -> Self {
+        StorageValue::Int(value)
+    }
+}
+
+impl From<&str> for StorageValue {
+    fn from(value: &str) -> Self {
+        StorageValue::Text(value.to_owned())
+    }
+}
+
+impl From<String> for StorageValue {
+    fn from(value: String) -> Self {
+        StorageValue::Text(value)
+    }
+}
+
+impl From<Vec<StorageValue>> for StorageValue {
+    fn from(values: Vec<StorageValue>) -> Self {
+        StorageValue::List(values)
+    }
+}
+
+pub type StoredValues = BTreeMap<String, StorageValue>;
+
+type SaveThread = (Sender<StoredValues>, JoinHandle<()>, PathBuf, Logger);
+
+pub struct KeyValueStorage {
+    values: RwLock<StoredValues>,
+    save_thread: RwLock<Option<SaveThread>>,
+}
+
+impl KeyValueStorage {
+    pub fn load(path: &Path, logger: &Logger) -> GameResult<Self> {
+        let values = load_stored_values(path)?;
+        Ok(Self::with_values(values, Some(spawn_save_thread(path.to_path_buf(), logger.clone()))))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::with_values(BTreeMap::new(), None)
+    }
+
+    pub fn from_values(values: StoredValues) -> Self {
+        Self::with_values(values, None)
+    }
+
+    fn with_values(values: StoredValues, save_thread: Option<SaveThread>) -> Self {
+        Self {
+            values: RwLock::new(values),
+            save_thread: RwLock::new(save_thread),
+        }
+    }
+
+    /// Replaces all values with the ones stored at `path` and saves there from now on.
+    /// Pending writes to the previous file are completed first.
+    pub fn switch_to(&self, path: &Path, logger: &Logger) -> GameResult<()> {
+        self.close();
+        let values = load_stored_values(path)?;
+        *self.values.write().unwrap() = values;
+        *self.save_thread.write().unwrap() = Some(spawn_save_thread(path.to_path_buf(), logger.clone()));
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<StorageValue> {
+        if key == StorageKey::always() {
+            return Some(StorageValue::Int(1))
+        }
+        let storage = self.values.read().unwrap();
+        storage.get(key).cloned()
+    }
+
+    pub fn set(&self, key: &str, value: impl Into<StorageValue>) {
+        {
+            let mut storage = self.values.write().unwrap();
+            storage.insert(key.to_owned(), value.into());
+        }
+        self.save();
+    }
+
+    pub fn remove(&self, key: &str) {
+        {
+            let mut storage = self.values.write().unwrap();
+            storage.remove(key);
+        }
+        self.save();
+    }
+
+    /// Integer value of the key, `None` for missing keys and values that are not numbers.
+    pub fn get_value_for_key(&self, key: &str) -> Option<u32> {
+        self.get(key).and_then(|value| value.as_int()).and_then(|value| u32::try_from(value).ok())
+    }
+
+    pub fn set_value_for_key(&self, key: &str, value: u32) {
+        self.set(key, value)
+    }
+
+    pub fn replace_values(&self, values: StoredValues) {
+        *self.values.write().unwrap() = values;
+        self.save();
+    }
+
+    /// Waits for pending writes, changes made afterwards are kept in memory only.
+    pub fn close(&self) {
+        if let Some((tx, handle, _, _)) = self.save_thread.write().unwrap().take() {
+            drop(tx);
+            let _ = handle.join();
+        }
+    }
+
+    /// Adds the save file, once pending writes are done, to its rolling backups.
+    pub fn back_up(&self) -> GameResult<()> {
+        let Some((path, logger)) = self.save_thread.read().unwrap().as_ref().map(|(_, _, path, logger)| (path.clone(), logger.clone())) else {
+            return Ok(())
+        };
+        self.close();
+        let result = back_up(&path);
+        *self.save_thread.write().unwrap() = Some(spawn_save_thread(path, logger));
+        result
+    }
+
+    fn save(&self) {
+        if let Some((tx, _, _, _)) = self.save_thread.read().unwrap().as_ref() {
+            let storage = self.values.read().unwrap().clone();
+            tx.send(storage).expect("Failed to send data to save thread");
+        }
+    }
+
+    pub fn values(&self) -> StoredValues {
+        self.values.read().unwrap().clone()
+    }
+}
+
+/// Pending writes are completed before the storage goes away, so its file is never left half written.
+impl Drop for KeyValueStorage {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn spawn_save_thread(path: PathBuf, logger: Logger) -> SaveThread {
+    let (tx, rx) = mpsc::channel::<StoredValues>();
+
+    let (thread_path, thread_logger) = (path.clone(), logger.clone());
+    let handle = thread::spawn(move || {
+        while let Ok(data) = rx.recv() {
+            save_stored_values(&thread_logger, &data, &thread_path);
+        }
+    });
+    (tx, handle, path, logger)
+}
+
+fn load_stored_values(path: &Path) -> GameResult<StoredValues> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(BufReader::new(file)).map_err(|e| GameError::invalid_data(path, e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(GameError::io(path, e))
+    }
+}
+
+fn save_stored_values(logger: &Logger, data: &StoredValues, path: &Path) {
+    if let Ok(serialized_world) = serde_json::to_string_pretty(data) {
+        if let Err(e) = write_atomically(path, serialized_world.as_bytes()) {
+            log_error!(logger, Subsystem::Storage, "Failed to write save file: {}", e);
+        } else {
+            log_debug!(logger, Subsystem::Storage, "Data saved successfully to storage.json");
+        }
+    } else {
+        log_error!(logger, Subsystem::Storage, "Failed to serialize data");
     }
-    let storage = KEY_VALUE_STORAGE.read().unwrap().clone();
-    let tx = &SAVE_THREAD.0;
-    tx.send(storage).expect("Failed to send data to save thread");
 }
 
 pub fn save_pressure_plate_states(world: &World) {
-    set_value_for_key(PRESSURE_PLATE_YELLOW, world.pressure_plate_down_yellow.to_int());
-    set_value_for_key(PRESSURE_PLATE_RED, world.pressure_plate_down_red.to_int());
-    set_value_for_key(PRESSURE_PLATE_BLUE, world.pressure_plate_down_blue.to_int());
-    set_value_for_key(PRESSURE_PLATE_GREEN, world.pressure_plate_down_green.to_int());
-    set_value_for_key(PRESSURE_PLATE_SILVER, world.pressure_plate_down_silver.to_int());
+    let storage = &world.context.storage;
+    storage.set_value_for_key(PRESSURE_PLATE_YELLOW, world.pressure_plate_down_yellow.to_int());
+    storage.set_value_for_key(PRESSURE_PLATE_RED, world.pressure_plate_down_red.to_int());
+    storage.set_value_for_key(PRESSURE_PLATE_BLUE, world.pressure_plate_down_blue.to_int());
+    storage.set_value_for_key(PRESSURE_PLATE_GREEN, world.pressure_plate_down_green.to_int());
+    storage.set_value_for_key(PRESSURE_PLATE_SILVER, world.pressure_plate_down_silver.to_int());
 }
 
 trait IntConvertible {
@@ -93,4 +305,4 @@ impl IntConvertible for bool {
     fn to_int(&self) -> u32 {
         if *self { 1 } else { 0 }
     }
-}
\ No newline at end of file
+}
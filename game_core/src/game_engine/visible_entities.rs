@@ -1,26 +1,46 @@
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 use crate::{constants::HERO_ENTITY_ID, utils::rect::IntRect};
 
 use super::world::World;
 
 impl World {
-    pub fn compute_visible_entities(&self, viewport: &IntRect) -> HashSet<(usize, u32)> {
-        let min_row = viewport.y;
-        let max_row = viewport.y + viewport.h;
-        let min_col = viewport.x;
-        let max_col = viewport.x + viewport.w;
-
-        self.entities.borrow().iter()
-            .enumerate()
-            .filter_map(|(index, e)| {
-                let id = e.id;
-                let frame = e.frame;
+    pub fn compute_visible_entities(&self, viewport: &IntRect) -> BTreeSet<(usize, u32)> {
+        let mut visible = self.entities_in(viewport);
+
+        if let Some(index) = self.index_for_entity(HERO_ENTITY_ID) {
+            visible.insert((index, HERO_ENTITY_ID));
+        }
+        visible
+    }
+
+    /// Entities around the viewport that keep updating while off screen, every `off_screen_update_interval`.
+    pub fn compute_active_entities(&self, viewport: &IntRect) -> BTreeSet<(usize, u32)> {
+        let margin = self.active_region_margin.max(0);
+        let region = IntRect::new(viewport.x - margin, viewport.y - margin, viewport.w + 2 * margin, viewport.h + 2 * margin);
+
+        self.entities_in(&region)
+            .difference(&self.visible_entities)
+            .copied()
+            .collect()
+    }
+
+    fn entities_in(&self, area: &IntRect) -> BTreeSet<(usize, u32)> {
+        let min_row = area.y;
+        let max_row = area.y + area.h;
+        let min_col = area.x;
+        let max_col = area.x + area.w;
+
+        let entities = self.entities.borrow();
+        self.spatial_grid.candidates(area).into_iter()
+            .filter_map(|id| {
+                let index = self.index_for_entity(id)?;
+                let frame = entities.get(index)?.frame;
                 let max_y = frame.y + frame.h;
                 let max_x = frame.x + frame.w;
-                let is_inside_viewport = max_y >= min_row && frame.y <= max_row && max_x >= min_col && frame.x <= max_col;
+                let is_inside_area = max_y >= min_row && frame.y <= max_row && max_x >= min_col && frame.x <= max_col;
 
-                if id == HERO_ENTITY_ID || is_inside_viewport {
+                if is_inside_area {
                     Some((index, id))
                 } else {
                     None
@@ -28,4 +48,4 @@ impl World {
             })
             .collect()
     }
-}
\ No newline at end of file
+}
@@ -1,19 +1,25 @@
-use std::{cell::RefCell, collections::HashSet, fmt::{self, Debug}};
+use std::{cell::RefCell, collections::{BTreeSet, HashMap}, fmt::{self, Debug}, sync::Arc};
 
-use common_macros::hash_set;
-use crate::{constants::{ANIMATIONS_FPS, HERO_ENTITY_ID, SPRITE_SHEET_ANIMATED_OBJECTS, WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::{known_species::SPECIES_HERO, species::EntityType}, features::{animated_sprite::AnimatedSprite, hitmap::{EntityIdsMap, Hitmap, WeightsMap}}, maps::{biome_tiles::{Biome, BiomeTile}, constructions_tiles::{Construction, ConstructionTile}, tiles::TileSet}, utils::{directions::Direction, rect::IntRect, vector::Vector2d}};
+use crate::{constants::{ACTIVE_REGION_MARGIN, ANIMATIONS_FPS, OFF_SCREEN_UPDATE_INTERVAL, HERO_ENTITY_ID, SPRITE_SHEET_ANIMATED_OBJECTS, UNASSIGNED_ENTITY_ID, WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::{known_species::SPECIES_HERO, species::{EntityType, Species}}, features::{animated_sprite::AnimatedSprite, hitmap::{EntityIdsMap, Hitmap, WeightsMap}}, maps::{biome_tiles::{Biome, BiomeTile}, constructions_tiles::{Construction, ConstructionTile}, tiles::TileSet}, worlds::world_overlay::WorldChanges, utils::{directions::Direction, rect::IntRect, vector::Vector2d}};
 
-use super::{entity::{Entity, EntityId, EntityProps}, keyboard_events_provider::{KeyboardEventsProvider, NO_KEYBOARD_EVENTS}, locks::LockType, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::save_pressure_plate_states};
+use super::{context::GameContext, events::StateUpdate, entity::{Entity, EntityId, EntityProps}, keyboard_events_provider::{KeyboardEventsProvider, NO_KEYBOARD_EVENTS}, locks::LockType, spatial_grid::SpatialGrid, state_updates::{EngineStateUpdate, WorldStateUpdate}, storage::{save_pressure_plate_states, StorageScope}};
 
 pub struct World {
     pub id: u32,
+    pub context: Arc<GameContext>,
     pub total_elapsed_time: f32,
     pub bounds: IntRect,
     pub visible_bounds: IntRect,
     pub biome_tiles: TileSet<BiomeTile>,
     pub constructions_tiles: TileSet<ConstructionTile>,
     pub entities: RefCell<Vec<Entity>>,    
-    pub visible_entities: HashSet<(usize, u32)>,
+    pub entity_indexes: HashMap<EntityId, usize>,
+    pub spatial_grid: SpatialGrid,
+    pub visible_entities: BTreeSet<(usize, u32)>,
+    pub active_entities: BTreeSet<(usize, u32)>,
+    pub active_region_margin: i32,
+    pub off_screen_update_interval: f32,
+    pub time_since_off_screen_update: f32,
     pub cached_hero_props: EntityProps,
     pub hitmap: Hitmap,
     pub tiles_hitmap: Hitmap,
@@ -32,19 +38,29 @@ pub struct World {
     pub pressure_plate_down_blue: bool,
     pub pressure_plate_down_silver: bool,
     pub pressure_plate_down_yellow: bool,
+    pub changes: WorldChanges,
+    /// Where the hero came in with the latest teleport, x, y and direction
+    pub entrance: (i32, i32, Direction),
 }
 
 impl World {
-    pub fn new(id: u32) -> Self {
+    pub fn new(id: u32, context: Arc<GameContext>) -> Self {
         Self {
             id,
+            context,
             total_elapsed_time: 0.0,
             bounds: IntRect::square_from_origin(150),
             visible_bounds: IntRect::square_from_origin(150),
             biome_tiles: TileSet::empty(),
             constructions_tiles: TileSet::empty(),
             entities: RefCell::new(vec![]),
-            visible_entities: hash_set![],
+            entity_indexes: HashMap::new(),
+            spatial_grid: SpatialGrid::new(),
+            visible_entities: BTreeSet::new(),
+            active_entities: BTreeSet::new(),
+            active_region_margin: ACTIVE_REGION_MARGIN,
+            off_screen_update_interval: OFF_SCREEN_UPDATE_INTERVAL,
+            time_since_off_screen_update: 0.0,
             cached_hero_props: EntityProps::default(),
             hitmap: vec![vec![false; WORLD_SIZE_COLUMNS]; WORLD_SIZE_ROWS],
             tiles_hitmap: vec![vec![false; WORLD_SIZE_COLUMNS]; WORLD_SIZE_ROWS],
@@ -63,19 +79,26 @@ impl World {
             pressure_plate_down_blue: false,
             pressure_plate_down_silver: false,
             pressure_plate_down_yellow: false,
+            changes: WorldChanges::default(),
+            entrance: (0, 0, Direction::Down),
         }
     }
 
-    pub fn add_entity(&mut self, entity: Entity) -> (usize, u32) {
+    pub fn add_entity(&mut self, mut entity: Entity) -> (usize, u32) {
+        if entity.id == UNASSIGNED_ENTITY_ID {
+            entity.id = self.context.ids.next_id();
+        }
         let id = entity.id;
         let mut entities = self.entities.borrow_mut();        
         entities.push(entity);
         let new_index = entities.len() - 1;
-        entities[new_index].setup(self.creative_mode);
+        entities[new_index].setup(&self.context, self.creative_mode);
+        self.entity_indexes.insert(id, new_index);
+        self.spatial_grid.insert(id, &entities[new_index].frame);
         (new_index, id)
     }
 
-    fn remove_entity_by_id(&mut self, id: u32) {
+    pub fn remove_entity_by_id(&mut self, id: u32) {
         if id != HERO_ENTITY_ID {
             if let Some(index) = self.index_for_entity(id) {
                 self.remove_entity_at_index(index);
@@ -84,14 +107,34 @@ impl World {
     }
 
     fn remove_entity_at_index(&mut self, index: usize) {
-        self.entities.borrow_mut().swap_remove(index);
+        let mut entities = self.entities.borrow_mut();
+        let removed = entities.swap_remove(index);
+
+        if self.entity_indexes.get(&removed.id) == Some(&index) {
+            self.entity_indexes.remove(&removed.id);
+            self.spatial_grid.remove(removed.id);
+        }
+        if let Some(moved) = entities.get(index) {
+            if self.entity_indexes.get(&moved.id) == Some(&entities.len()) {
+                self.entity_indexes.insert(moved.id, index);
+            }
+        }
     }
 
-    fn index_for_entity(&self, id: u32) -> Option<usize> {
-        self.entities.borrow().iter()
-            .enumerate()
-            .find(|(_, entity)|{ entity.id == id })
-            .map(|(index, _)| index)
+    pub fn index_for_entity(&self, id: u32) -> Option<usize> {
+        self.entity_indexes.get(&id).copied()
+    }
+
+    /// Rebuilds the id map and the spatial grid, for when entities were changed directly.
+    pub fn reindex_entities(&mut self) {
+        let entities = self.entities.borrow();
+        self.entity_indexes.clear();
+        self.spatial_grid.clear();
+
+        for (index, entity) in entities.iter().enumerate() {
+            self.entity_indexes.insert(entity.id, index);
+            self.spatial_grid.insert(entity.id, &entity.frame);
+        }
     }
 
     pub fn update_rl(
@@ -106,9 +149,16 @@ impl World {
         self.has_attack_key_been_pressed = keyboard.has_attack_key_been_pressed;
         self.has_confirmation_key_been_pressed = keyboard.has_confirmation_been_pressed;
 
+        self.time_since_off_screen_update += time_since_last_update;
+        let off_screen_time = self.time_since_off_screen_update;
+        let updates_off_screen = off_screen_time >= self.off_screen_update_interval;
+        if updates_off_screen {
+            self.time_since_off_screen_update = 0.0;
+        }
+
         let mut entities = self.entities.borrow_mut();
 
-        let state_updates: Vec<WorldStateUpdate> = self.visible_entities.iter()
+        let mut state_updates: Vec<WorldStateUpdate> = self.visible_entities.iter()
             .flat_map(|(index, _)| {
                 if let Some(entity) = entities.get_mut(*index) {
                     entity.update(self, time_since_last_update)
@@ -118,11 +168,31 @@ impl World {
             })
             .collect();
 
+        if updates_off_screen {
+            let off_screen_updates: Vec<WorldStateUpdate> = self.active_entities.iter()
+                .flat_map(|(index, _)| {
+                    if let Some(entity) = entities.get_mut(*index) {
+                        entity.update(self, off_screen_time)
+                    } else {
+                        vec![]
+                    }
+                })
+                .collect();
+            state_updates.extend(off_screen_updates);
+        }
+
         self.biome_tiles.update(time_since_last_update);
 
+        for (index, id) in self.visible_entities.iter().chain(&self.active_entities) {
+            if let Some(entity) = entities.get(*index) {
+                self.spatial_grid.update(*id, &entity.frame);
+            }
+        }
+
         drop(entities);
         let updates = self.apply_state_updates(state_updates);
         self.visible_entities = self.compute_visible_entities(viewport);
+        self.active_entities = self.compute_active_entities(viewport);
         self.update_hitmaps();
         updates
     } 
@@ -145,22 +215,26 @@ impl World {
         tile
     }
 
-    fn log_update(&self, update: &WorldStateUpdate) {
-        match update {
-            WorldStateUpdate::EngineUpdate(_) => {},
-            WorldStateUpdate::CacheHeroProps(_) => {},
-            _ => println!("World update: {:#?}", update)
-        }        
+    fn notify_update(&self, update: &WorldStateUpdate) {
+        if !matches!(update, WorldStateUpdate::EngineUpdate(_)) {
+            self.context.events.emit(self.id, StateUpdate::World(update));
+        }
     }
 
     fn apply_state_update(&mut self, update: WorldStateUpdate) -> Option<EngineStateUpdate> {
-        self.log_update(&update);
+        self.notify_update(&update);
 
         match update {
             WorldStateUpdate::AddEntity(entity) => { 
-                self.add_entity(*entity); 
+                let (_, id) = self.add_entity(*entity); 
+                if !self.creative_mode {
+                    self.record_addition(id)
+                }
             }
             WorldStateUpdate::RemoveEntity(id) => {
+                if !self.creative_mode {
+                    self.record_removal(id)
+                }
                 self.remove_entity_by_id(id)
             }
             WorldStateUpdate::RemoveEntityAtCoordinates(row, col) => {
@@ -203,7 +277,16 @@ impl World {
                 self.change_destination_y(entity_id, y)
             }
             WorldStateUpdate::HandleHit(bullet_id, target_id) => {
-                self.handle_hit(bullet_id, target_id)
+                return self.handle_hit(bullet_id, target_id)
+            }
+            WorldStateUpdate::HandleMeleeHit(species_id, target_id) => {
+                self.hit(target_id, &self.context.species.get(species_id).clone());
+            }
+            WorldStateUpdate::EquipWeapon(species_id) => {
+                return self.equip_weapon(species_id)
+            }
+            WorldStateUpdate::DamageHero(damage, damage_type, direction) => {
+                return self.damage_hero(damage, damage_type, direction)
             }
             WorldStateUpdate::SetPressurePlateState(lock_type, is_down) => {
                 match lock_type {
@@ -217,40 +300,71 @@ impl World {
                 }                
                 save_pressure_plate_states(self)
             }
+            WorldStateUpdate::SetStorageValue(entity_id, key, value) => {
+                let key = StorageScope::new(self.id, entity_id).resolve(&key);
+                self.context.storage.set(&key, value)
+            }
         };
         None
     }
 
-    fn handle_hit(&mut self, bullet_id: EntityId, target_id: EntityId) {
-        let mut did_hit = false;
-        let mut entities = self.entities.borrow_mut();
+    fn handle_hit(&mut self, bullet_id: EntityId, target_id: EntityId) -> Option<EngineStateUpdate> {
+        let bullet_index = self.index_for_entity(bullet_id)?;
+        let (species_id, direction) = {
+            let bullet = &self.entities.borrow()[bullet_index];
+            (bullet.species_id, bullet.direction)
+        };
+        let weapon = self.context.species.get(species_id).clone();
 
-        if let Some(target) = entities.iter_mut().find(|e| e.id == target_id) {    
-            if !target.is_dying && !target.is_invulnerable {
-                did_hit = true;
-                target.direction = Direction::Unknown;
-                target.current_speed = 0.0;
-                target.is_rigid = false;
-                target.is_dying = true;
-                target.remaining_lifespan = 10.0 / ANIMATIONS_FPS;                
-                target.frame = IntRect::new(target.frame.x, target.frame.y, 1, 1).offset_y(if target.frame.h > 1 { 1 } else { 0 });
-                target.sprite = AnimatedSprite::new(
-                    SPRITE_SHEET_ANIMATED_OBJECTS, 
-                    IntRect::new(0, 10, 1, 1), 
-                    5
-                );
-            }
+        if target_id == HERO_ENTITY_ID {
+            self.remove_entity_by_id(bullet_id);
+            return self.damage_hero(weapon.damage, weapon.damage_type, direction)
         }
-        drop(entities);
+        if !self.hit(target_id, &weapon) {
+            return None
+        }
+        let mut entities = self.entities.borrow_mut();
+        let bullet = &mut entities[bullet_index];
 
-        if did_hit {
+        if bullet.remaining_pierce > 0 {
+            bullet.remaining_pierce -= 1;
+            bullet.last_hit_id = target_id;
+        } else {
+            drop(entities);
             self.remove_entity_by_id(bullet_id)
         }
+        None
+    }
+
+    /// Deals the damage of the weapon to the target, returns false if it could not be hit.
+    fn hit(&mut self, target_id: EntityId, weapon: &Species) -> bool {
+        let mut entities = self.entities.borrow_mut();
+        let Some(target) = self.index_for_entity(target_id).and_then(|index| entities.get_mut(index)) else {
+            return false
+        };
+        if target.is_dying || target.is_invulnerable {
+            return false
+        }
+        if target.receive_damage(weapon.damage, weapon.damage_type, &self.context.species) {
+            target.direction = Direction::Unknown;
+            target.current_speed = 0.0;
+            target.is_rigid = false;
+            target.is_dying = true;
+            target.remaining_lifespan = 10.0 / ANIMATIONS_FPS;                
+            target.frame = IntRect::new(target.frame.x, target.frame.y, 1, 1).offset_y(if target.frame.h > 1 { 1 } else { 0 });
+            target.sprite = AnimatedSprite::new(
+                SPRITE_SHEET_ANIMATED_OBJECTS, 
+                IntRect::new(0, 10, 1, 1), 
+                5
+            );
+            self.spatial_grid.update(target_id, &target.frame);
+        }
+        true
     }
 
     fn stop_hero_movement(&mut self) {
         let mut entities = self.entities.borrow_mut();
-        if let Some(entity) = entities.iter_mut().find(|e| e.id == HERO_ENTITY_ID) {            
+        if let Some(entity) = self.index_for_entity(HERO_ENTITY_ID).and_then(|index| entities.get_mut(index)) {            
             entity.offset = Vector2d::zero();
             entity.current_speed = 0.0;
         }
@@ -258,28 +372,28 @@ impl World {
 
     fn toggle_demand_attention(&mut self, id: u32) {
         let mut entities = self.entities.borrow_mut();
-        if let Some(entity) = entities.iter_mut().find(|e| e.id == id) {
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get_mut(index)) {
             entity.demands_attention = !entity.demands_attention
         }
     }
 
     fn rename_entity(&mut self, id: u32, name: String) {
         let mut entities = self.entities.borrow_mut();
-        if let Some(entity) = entities.iter_mut().find(|e| e.id == id) {
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get_mut(index)) {
             entity.name = name;
         }
     }
 
     fn change_lock(&mut self, id: u32, lock_type: LockType) {
         let mut entities = self.entities.borrow_mut();
-        if let Some(entity) = entities.iter_mut().find(|e| e.id == id) {
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get_mut(index)) {
             entity.lock_type = lock_type;
         }
     }
 
     fn change_destination_world(&mut self, id: u32, world: u32) {
         let mut entities = self.entities.borrow_mut();
-        if let Some(entity) = entities.iter_mut().find(|e| e.id == id) {
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get_mut(index)) {
             if let Some(destination) = entity.destination.as_mut() {
                 destination.world = world;
             }
@@ -288,7 +402,7 @@ impl World {
 
     fn change_destination_x(&mut self, id: u32, x: i32) {
         let mut entities = self.entities.borrow_mut();
-        if let Some(entity) = entities.iter_mut().find(|e| e.id == id) {
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get_mut(index)) {
             if let Some(destination) = entity.destination.as_mut() {
                 destination.x = x;
             }
@@ -297,7 +411,7 @@ impl World {
 
     fn change_destination_y(&mut self, id: u32, y: i32) {
         let mut entities = self.entities.borrow_mut();
-        if let Some(entity) = entities.iter_mut().find(|e| e.id == id) {
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get_mut(index)) {
             if let Some(destination) = entity.destination.as_mut() {
                 destination.y = y;
             }
@@ -314,6 +428,16 @@ impl World {
         self.update_tiles_hitmap();
     }  
     
+    pub fn destinations_near(&self, area: &IntRect) -> BTreeSet<u32> {
+        let entities = self.entities.borrow();
+        self.spatial_grid.candidates(area).into_iter()
+            .filter_map(|id| entities.get(self.index_for_entity(id)?))
+            .filter(|e| matches!(e.entity_type, EntityType::Teleporter))
+            .filter_map(|e| e.destination.as_ref().map(|d| d.world))
+            .filter(|world_id| *world_id != self.id)
+            .collect()
+    }
+
     pub fn find_teleporter_for_destination(&self, destination_world: u32) -> Option<IntRect> {
         self.entities.borrow().iter()
             .find(|t| {
@@ -363,12 +487,13 @@ impl World {
     }
 
     fn find_non_hero_entity_id_at_coords(&self, row: usize, col: usize) -> Option<(usize, u32)> {
-        self.entities.borrow().iter()
-            .enumerate()
-            .find(|(_, entity)| {
+        let entities = self.entities.borrow();
+        self.spatial_grid.candidates(&IntRect::new(col as i32, row as i32, 0, 0)).into_iter()
+            .filter_map(|id| self.index_for_entity(id).map(|index| (index, id)))
+            .find(|(index, _)| {
+                let entity = &entities[*index];
                 entity.species_id != SPECIES_HERO && entity.frame.contains_or_touches_tile(col as i32, row as i32)
             })
-            .map(|(index, e)| (index, e.id))
     }
 
     fn remove_entities_by_coords(&mut self, row: usize, col: usize) {
@@ -393,4 +518,76 @@ impl World {
         let viewport = self.bounds;
         self.update_rl(time_since_last_update, &viewport, keyboard)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{entities::species::EntityType, game_engine::keyboard_events_provider::NO_KEYBOARD_EVENTS, testing::fixtures::{fixture_species, WorldFixture}, utils::{directions::Direction, rect::IntRect}};
+
+    use super::{World, WorldStateUpdate};
+
+    const SPECIES_FIXTURE_SIGN: u32 = 900_301;
+    const SPECIES_FIXTURE_BULLET: u32 = 900_302;
+
+    #[test]
+    fn lookups_stay_in_sync_after_removals() {
+        let mut world = WorldFixture::new(900_300)
+            .biomes(&["1111111111"; 10])
+            .entities(&[
+                "s.........",
+                "..........",
+                ".........s",
+                "..........",
+                "s.........",
+            ])
+            .legend('s', SPECIES_FIXTURE_SIGN)
+            .species(fixture_species(SPECIES_FIXTURE_SIGN, EntityType::StaticObject, false))
+            .build();
+
+        let ids: Vec<u32> = world.entities.borrow().iter().map(|e| e.id).collect();
+        world.apply_state_updates(vec![WorldStateUpdate::RemoveEntity(ids[0])]);
+
+        assert_eq!(world.index_for_entity(ids[0]), None);
+        for id in &ids[1..] {
+            let index = world.index_for_entity(*id).unwrap();
+            assert_eq!(world.entities.borrow()[index].id, *id);
+        }
+
+        let visible = world.compute_visible_entities(&IntRect::new(0, 0, 4, 4));
+        let visible_ids: Vec<u32> = visible.iter().map(|(_, id)| *id).collect();
+        assert_eq!(visible_ids, vec![ids[2]]);
+    }
+
+    fn world_with_bullet_at(x: usize) -> World {
+        let world = WorldFixture::new(900_302)
+            .biomes(&["1111111111111111111111111111111111111111"; 3])
+            .entities(&["", &format!("{}b", ".".repeat(x))])
+            .legend('b', SPECIES_FIXTURE_BULLET)
+            .species(fixture_species(SPECIES_FIXTURE_BULLET, EntityType::Bullet, false))
+            .build();
+
+        let mut entities = world.entities.borrow_mut();
+        entities[0].direction = Direction::Right;
+        entities[0].current_speed = 1.0;
+        drop(entities);
+        world
+    }
+
+    fn run_for_one_second(world: &mut World) -> i32 {
+        let viewport = IntRect::new(0, 0, 5, 3);
+        (0..60).for_each(|_| _ = world.update_rl(1.0 / 60.0, &viewport, &NO_KEYBOARD_EVENTS));
+        let x = world.entities.borrow()[0].frame.x;
+        x
+    }
+
+    #[test]
+    fn off_screen_entities_keep_moving_inside_the_active_region() {
+        let mut world = world_with_bullet_at(15);
+        assert!(run_for_one_second(&mut world) > 16);
+        assert!(world.visible_entities.is_empty());
+
+        let mut world = world_with_bullet_at(15);
+        world.active_region_margin = 0;
+        assert!(run_for_one_second(&mut world) <= 16);
+    }
+}
@@ -1,61 +1,73 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use lazy_static::lazy_static;
-
-use crate::config::config;
+use crate::game_engine::errors::{GameError, GameResult};
 
 pub trait LocalizableText {
-    fn localized(&self) -> String; 
+    fn localized(&self, strings: &LocalizedStrings) -> String; 
 }
 
 impl LocalizableText for String {
-    fn localized(&self) -> String {
-        if let Some(strings) = LOCALIZED_STRINGS.get(config().current_lang.as_str()) {
-            if let Some(localized_string) = strings.get(self) {
-                return localized_string.clone();
-            }
-        }
-        self.clone()
+    fn localized(&self, strings: &LocalizedStrings) -> String {
+        strings.localized(self)
     }
 }
 
 impl LocalizableText for &str {
-    fn localized(&self) -> String {
-        self.to_string().localized()
+    fn localized(&self, strings: &LocalizedStrings) -> String {
+        strings.localized(self)
     }
 }
 
-lazy_static! {
-    pub static ref LOCALIZED_STRINGS: HashMap<String, HashMap<String, String>> = load_localized_strings();
+/// Keys missing from the current language are shown as they are
+#[derive(Debug, Clone)]
+pub struct LocalizedStrings {
+    current_lang: String,
+    strings: HashMap<String, HashMap<String, String>>,
 }
 
-fn load_localized_strings() -> HashMap<String, HashMap<String, String>> {
-    let mut localized_strings = HashMap::new();    
-    println!("Lang folder: {:#?}", config().localized_strings_path.clone());
-    let paths = fs::read_dir(config().localized_strings_path.clone())
-        .expect("Failed to read localized strings")
-        .flatten()
-        .map(|p| p.path());
-
-    for file_path in paths {        
-        if file_path.extension() == Some(std::ffi::OsStr::new("stringx")) {
-            if let Some(locale) = file_path.file_stem().and_then(|os_str| os_str.to_str()) {
-                let strings = load_strings_from_file(&file_path);
-                localized_strings.insert(locale.to_string(), strings);
+impl LocalizedStrings {
+    pub fn new(current_lang: &str) -> Self {
+        Self { current_lang: current_lang.to_owned(), strings: HashMap::new() }
+    }
+
+    pub fn load(path: &Path, current_lang: &str) -> GameResult<Self> {
+        let mut localized_strings = Self::new(current_lang);
+        let paths = fs::read_dir(path)
+            .map_err(|e| GameError::io(path, e))?
+            .flatten()
+            .map(|p| p.path());
+
+        for file_path in paths {        
+            if file_path.extension() == Some(std::ffi::OsStr::new("stringx")) {
+                if let Some(locale) = file_path.file_stem().and_then(|os_str| os_str.to_str()) {
+                    let strings = load_strings_from_file(&file_path)?;
+                    localized_strings.register(locale, strings);
+                }
             }
         }
+        Ok(localized_strings)
+    }
+
+    pub fn register(&mut self, lang: &str, strings: HashMap<String, String>) {
+        self.strings.entry(lang.to_owned()).or_default().extend(strings);
+    }
+
+    pub fn localized(&self, key: &str) -> String {
+        self.strings
+            .get(&self.current_lang)
+            .and_then(|strings| strings.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_owned())
     }
-    localized_strings
 }
 
-fn load_strings_from_file(file_path: &Path) -> HashMap<String, String> {
-    let content = fs::read_to_string(file_path)
-        .unwrap_or_else(|_| panic!("Failed to read localization file: {:?}", file_path));
-    parse_strings_content(&content)
+fn load_strings_from_file(file_path: &Path) -> GameResult<HashMap<String, String>> {
+    let content = fs::read_to_string(file_path).map_err(|e| GameError::io(file_path, e))?;
+    parse_strings_content(&content).map_err(|e| GameError::invalid_data(file_path, e))
 }
 
-fn parse_strings_content(content: &str) -> HashMap<String, String> {
+fn parse_strings_content(content: &str) -> Result<HashMap<String, String>, String> {
     let mut strings_map = HashMap::new();
     let mut pos = 0;
     let content_chars: Vec<char> = content.chars().collect();
@@ -69,33 +81,33 @@ fn parse_strings_content(content: &str) -> HashMap<String, String> {
         }
 
         if content_chars[pos] == '"' {
-            let key = parse_string(&content_chars, &mut pos);
+            let key = parse_string(&content_chars, &mut pos)?;
             skip_whitespace(&content_chars, &mut pos);
 
             if pos >= len || content_chars[pos] != '=' {
-                panic!("Expected '=' after key at position {}", pos);
+                return Err(format!("Expected '=' after key at position {}", pos));
             }
             pos += 1; 
 
             skip_whitespace(&content_chars, &mut pos);
 
-            let value = if content_chars[pos] == '"' {
+            let value = if pos < len && content_chars[pos] == '"' {
                 if pos + 2 < len && content_chars[pos + 1] == '"' && content_chars[pos + 2] == '"' {
-                    parse_multiline_string(&content_chars, &mut pos)
+                    parse_multiline_string(&content_chars, &mut pos)?
                 } else {
-                    parse_string(&content_chars, &mut pos)
+                    parse_string(&content_chars, &mut pos)?
                 }
             } else {
-                panic!("Expected '\"' at position {}", pos);
+                return Err(format!("Expected '\"' at position {}", pos));
             };
 
             strings_map.insert(key, value);
         } else {
-            panic!("Expected '\"' at position {}", pos);
+            return Err(format!("Expected '\"' at position {}", pos));
         }
     }
 
-    strings_map
+    Ok(strings_map)
 }
 
 fn skip_whitespace(chars: &[char], pos: &mut usize) {
@@ -104,9 +116,9 @@ fn skip_whitespace(chars: &[char], pos: &mut usize) {
     }
 }
 
-fn parse_string(chars: &[char], pos: &mut usize) -> String {
+fn parse_string(chars: &[char], pos: &mut usize) -> Result<String, String> {
     if chars[*pos] != '"' {
-        panic!("Expected '\"' at position {}", pos);
+        return Err(format!("Expected '\"' at position {}", pos));
     }
     *pos += 1; 
 
@@ -115,11 +127,11 @@ fn parse_string(chars: &[char], pos: &mut usize) -> String {
         let c = chars[*pos];
         if c == '"' {
             *pos += 1; 
-            return result;
+            return Ok(result);
         } else if c == '\\' {
             *pos += 1;
             if *pos >= chars.len() {
-                panic!("Unexpected end of input after escape character at position {}", pos);
+                return Err(format!("Unexpected end of input after escape character at position {}", pos));
             }
             let escaped_char = chars[*pos];
             match escaped_char {
@@ -134,12 +146,12 @@ fn parse_string(chars: &[char], pos: &mut usize) -> String {
         }
         *pos += 1;
     }
-    panic!("Unterminated string starting at position {}", pos);
+    Err(format!("Unterminated string starting at position {}", pos))
 }
 
-fn parse_multiline_string(chars: &[char], pos: &mut usize) -> String {
+fn parse_multiline_string(chars: &[char], pos: &mut usize) -> Result<String, String> {
     if *pos + 2 >= chars.len() || chars[*pos] != '"' || chars[*pos + 1] != '"' || chars[*pos + 2] != '"' {
-        panic!("Expected '\"\"\"' at position {}", pos);
+        return Err(format!("Expected '\"\"\"' at position {}", pos));
     }
     *pos += 3;
 
@@ -154,13 +166,13 @@ fn parse_multiline_string(chars: &[char], pos: &mut usize) -> String {
             if result.ends_with('\n') {
                 result.pop();
             }
-            return result;
+            return Ok(result);
         } else {
             result.push(chars[*pos]);
             *pos += 1;
         }
     }
-    panic!("Unterminated multiline string starting at position {}", pos);
+    Err(format!("Unterminated multiline string starting at position {}", pos))
 }
 
 #[cfg(test)]
@@ -174,7 +186,7 @@ mod tests {
 "key2" = "value2"
 "#;
 
-        let parsed = parse_strings_content(content);
+        let parsed = parse_strings_content(content).unwrap();
         assert_eq!(parsed.get("key1").unwrap(), "value1");
         assert_eq!(parsed.get("key2").unwrap(), "value2");
     }
@@ -189,7 +201,7 @@ Line3
 """
 "#;
 
-        let parsed = parse_strings_content(content);
+        let parsed = parse_strings_content(content).unwrap();
         assert_eq!(
             parsed.get("multiline.key").unwrap(),
             "Line1\nLine2\nLine3"
@@ -209,7 +221,7 @@ value
 "some other example" = "some other value"
 "#;
 
-        let parsed = parse_strings_content(content);
+        let parsed = parse_strings_content(content).unwrap();
         assert_eq!(parsed.get("example").unwrap(), "value");
         assert_eq!(parsed.get("some other example").unwrap(), "some other value");
         assert_eq!(
@@ -219,32 +231,29 @@ value
     }
 
     #[test]
-    #[should_panic(expected = "Expected '=' after key")]
     fn test_parse_strings_content_missing_equal() {
         let content = r#"
 "key1"  "value1"
 "#;
 
-        parse_strings_content(content);
+        assert!(parse_strings_content(content).unwrap_err().starts_with("Expected '=' after key"));
     }
 
     #[test]
-    #[should_panic(expected = "Unterminated string")]
     fn test_parse_strings_content_unterminated_string() {
         let content = r#"
 "key1" = "value1
 "#;
 
-        parse_strings_content(content);
+        assert!(parse_strings_content(content).unwrap_err().starts_with("Unterminated string"));
     }
 
     #[test]
-    #[should_panic(expected = "Expected '\"' at position")]
     fn test_parse_strings_content_invalid_start() {
         let content = r#"
 key1 = "value1"
 "#;
 
-        parse_strings_content(content);
+        assert!(parse_strings_content(content).unwrap_err().starts_with("Expected '\"' at position"));
     }
 }
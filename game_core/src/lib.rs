@@ -1,9 +1,9 @@
-use std::{cmp::Ordering, ffi::{c_char, CStr}, path::PathBuf, ptr};
+use std::{cell::RefCell, cmp::Ordering, ffi::{c_char, c_void, CStr, CString}, path::PathBuf, ptr};
 
-use config::initialize_config_paths;
-use game_engine::{engine::GameEngine, entity::Entity};
+use config::{Config, DeathPenalties};
+use game_engine::{engine::GameEngine, entity::Entity, errors::GameError, events::{CGameEventCallback, ListenerId}, simulation::SimulationMode};
 use maps::{biome_tiles::BiomeTile, constructions_tiles::ConstructionTile};
-use utils::{rect::IntRect, vector::Vector2d};
+use utils::{logger::{LogLevel, LoggerConfig, Subsystem}, rect::IntRect, vector::Vector2d};
 
 pub mod config;
 pub mod constants;
@@ -15,67 +15,247 @@ pub mod lang;
 pub mod maps;
 pub mod menus;
 pub mod prefabs;
+#[cfg(test)]
+pub mod testing;
 pub mod ui;
 pub mod utils;
 pub mod worlds;
 
-static mut ENGINE: *mut GameEngine = std::ptr::null_mut();
+#[no_mangle]
+pub extern "C" fn test_integration() {
+    println!("Helloooo");
+}
 
-pub fn engine() -> &'static GameEngine {
-    unsafe {
-        &*ENGINE
-    }
+#[repr(C)]
+pub struct EngineConfig {
+    pub current_lang: *const c_char,
+    pub levels_path: *const c_char,
+    pub species_path: *const c_char,
+    pub inventory_path: *const c_char,
+    pub key_value_storage_path: *const c_char,
+    pub localized_strings_path: *const c_char,
+    /// Optional, null keeps a single save at `key_value_storage_path` and `inventory_path`
+    pub saves_path: *const c_char,
+    /// Runs the engine in deterministic mode when greater than zero
+    pub fixed_time_step: f32,
+    pub seed: u64,
+    /// Share of the maximum hp the hero respawns with, zero or less brings it back with full hp
+    pub respawn_hp: f32,
+    /// Consumable items are lost when the hero respawns
+    pub loses_consumables: bool,
+    /// Minimum level of the entries the engine keeps, see `configure_logging`
+    pub log_level: LogLevel,
+    pub prints_log_to_console: bool,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
 }
 
-fn engine_mut() -> &'static mut GameEngine {
-    unsafe {
-        &mut *ENGINE
+fn set_last_error(error: Option<&GameError>) {
+    LAST_ERROR.with(|last_error| {
+        *last_error.borrow_mut() = error.and_then(|e| CString::new(e.to_string()).ok())
+    })
+}
+
+/// # Safety
+/// All paths in `config` must be valid, nul-terminated strings.
+/// The returned handle must be released with `nokemon_engine_free`.
+/// Returns null if the game data could not be loaded, see `nokemon_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn nokemon_engine_new(config: EngineConfig) -> *mut GameEngine {
+    let engine_config = Config {
+        current_lang: to_string(config.current_lang),
+        levels_path: to_path(config.levels_path),
+        species_path: to_path(config.species_path),
+        inventory_path: to_path(config.inventory_path),
+        key_value_storage_path: to_path(config.key_value_storage_path),
+        localized_strings_path: to_path(config.localized_strings_path),
+        saves_path: if config.saves_path.is_null() { PathBuf::new() } else { to_path(config.saves_path) },
+        death_penalties: DeathPenalties {
+            respawn_hp: if config.respawn_hp > 0.0 { config.respawn_hp } else { DeathPenalties::default().respawn_hp },
+            loses_consumables: config.loses_consumables,
+        },
+        logging: LoggerConfig { level: config.log_level, prints_to_console: config.prints_log_to_console, ..Default::default() },
+    };
+    let simulation = if config.fixed_time_step > 0.0 {
+        SimulationMode::Deterministic { seed: config.seed, time_step: config.fixed_time_step }
+    } else {
+        SimulationMode::real_time()
+    };
+    match GameEngine::new(engine_config, simulation) {
+        Ok(engine) => {
+            set_last_error(None);
+            Box::into_raw(Box::new(engine))
+        }
+        Err(error) => {
+            set_last_error(Some(&error));
+            ptr::null_mut()
+        }
     }
 }
 
+/// # Safety
+/// `engine` must be null or a live handle returned by `nokemon_engine_new`.
+/// Returns the latest loading error not reported yet, or null if there is none.
+/// Pass a null `engine` to read why `nokemon_engine_new` failed.
+/// The string is owned by the library and stays valid until the next call on the same thread.
 #[no_mangle]
-pub extern "C" fn test_integration() {
-    println!("Helloooo");
+pub unsafe extern "C" fn nokemon_last_error(engine: *mut GameEngine) -> *const c_char {
+    if !engine.is_null() {
+        set_last_error((*engine).last_error.take().as_ref());
+    }
+    LAST_ERROR.with(|last_error| {
+        last_error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr())
+    })
 }
 
+/// # Safety
+/// `engine` must come from `nokemon_engine_new` and must not be used afterwards.
 #[no_mangle]
-pub extern "C" fn initialize_game(creative_mode: bool) {
-    unsafe {
-        let boxed = Box::new(GameEngine::new());
-        ENGINE = Box::into_raw(boxed);      
+pub unsafe extern "C" fn nokemon_engine_free(engine: *mut GameEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
     }
-    let engine = engine_mut();
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+/// Sets the minimum level for every subsystem of this engine, entries are kept in memory and printed only if `prints_to_console`.
+#[no_mangle]
+pub unsafe extern "C" fn configure_logging(engine: *mut GameEngine, level: LogLevel, prints_to_console: bool) {
+    let engine = &*engine;
+    engine.context.logger.configure(LoggerConfig { level, prints_to_console, ..Default::default() })
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`
+/// and `path` a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn start_recording(engine: *mut GameEngine, path: *const c_char) -> bool {
+    (*engine).start_recording(&to_path(path)).is_ok()
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+/// `callback` receives every applied state update, the event and its strings are only valid during the call.
+#[no_mangle]
+pub unsafe extern "C" fn add_event_listener(engine: *mut GameEngine, callback: CGameEventCallback, user_data: *mut c_void) -> ListenerId {
+    let engine = &*engine;
+    engine.context.events.subscribe_c(callback, user_data)
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+#[no_mangle]
+pub unsafe extern "C" fn remove_event_listener(engine: *mut GameEngine, listener_id: ListenerId) {
+    let engine = &*engine;
+    engine.context.events.unsubscribe(listener_id)
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+#[no_mangle]
+pub unsafe extern "C" fn initialize_game(engine: *mut GameEngine, creative_mode: bool) {
+    let engine = &mut *engine;
     engine.set_creative_mode(creative_mode);
     engine.start();
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+/// Loads the given save slot, skipping the slot picker shown by `initialize_game` when `saves_path` is set.
+/// Returns false if the slot could not be loaded, see `nokemon_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn select_save_slot(engine: *mut GameEngine, slot: u32) -> bool {
+    let engine = &mut *engine;
+    engine.select_slot(slot)
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`
+/// and `path` a valid, nul-terminated string.
+/// Writes storage, inventory, world changes, hero position and build info of the current save to a single file.
+/// Returns false if the bundle could not be written, see `nokemon_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn export_save_bundle(engine: *mut GameEngine, path: *const c_char) -> bool {
+    let engine = &mut *engine;
+    engine.export_save_bundle(&to_path(path))
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`
+/// and `path` a valid, nul-terminated string.
+/// Replaces the progress of `slot` with the bundle at `path` and resumes from there,
+/// `slot` is ignored when `saves_path` is not set.
+/// Returns false if the bundle could not be imported, see `nokemon_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn import_save_bundle(engine: *mut GameEngine, path: *const c_char, slot: u32) -> bool {
+    let engine = &mut *engine;
+    engine.import_save_bundle(&to_path(path), slot)
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+/// Writes the full state of the engine and of the current world next to the current save.
+/// Returns false if the quick save could not be written, see `nokemon_last_error`.
+#[no_mangle]
+pub unsafe extern "C" fn quick_save(engine: *mut GameEngine) -> bool {
+    let engine = &mut *engine;
+    engine.quick_save()
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+/// Resumes from the last quick save of the current save, closing any open menu.
+/// Returns false if there is no quick save or it could not be loaded, see `nokemon_last_error`.
 #[no_mangle]
-pub extern "C" fn is_creative_mode() -> bool {
-    engine().creative_mode
+pub unsafe extern "C" fn quick_load(engine: *mut GameEngine) -> bool {
+    let engine = &mut *engine;
+    engine.quick_load()
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn is_game_running() -> bool {
-    engine().is_running
+pub unsafe extern "C" fn is_creative_mode(engine: *const GameEngine) -> bool {
+    (*engine).creative_mode
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn stop_game() {
-    engine_mut().is_running = false
+pub unsafe extern "C" fn is_game_running(engine: *const GameEngine) -> bool {
+    (*engine).is_running
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+/// Saves progress and waits for pending writes before the game stops.
 #[no_mangle]
-pub extern "C" fn window_size_changed(width: f32, height: f32, scale: f32, font_size: f32, line_spacing: f32) {
-    engine_mut().window_size_changed(width, height, scale, font_size, line_spacing)
+pub unsafe extern "C" fn stop_game(engine: *mut GameEngine) {
+    (*engine).exit()
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn update_game(time_since_last_update: f32) {
-    engine_mut().update(time_since_last_update)
+pub unsafe extern "C" fn window_size_changed(engine: *mut GameEngine, width: f32, height: f32, scale: f32, font_size: f32, line_spacing: f32) {
+    (*engine).window_size_changed(width, height, scale, font_size, line_spacing)
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn update_keyboard(
+pub unsafe extern "C" fn update_game(engine: *mut GameEngine, time_since_last_update: f32) {
+    (*engine).update(time_since_last_update)
+}
+
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
+#[no_mangle]
+pub unsafe extern "C" fn update_keyboard(
+    engine: *mut GameEngine,
     up_pressed: bool,
     right_pressed: bool,
     down_pressed: bool,
@@ -92,7 +272,7 @@ pub extern "C" fn update_keyboard(
     current_char: u32,
     time_since_last_update: f32
 ) {
-    engine_mut().keyboard.update(
+    (*engine).update_keyboard(
         up_pressed, right_pressed, down_pressed, left_pressed, 
         up_down, right_down, down_down, left_down, 
         escape_pressed, menu_pressed, confirm_pressed, attack_pressed, backspace_pressed, 
@@ -101,8 +281,11 @@ pub extern "C" fn update_keyboard(
     );
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn update_mouse(
+pub unsafe extern "C" fn update_mouse(
+    engine: *mut GameEngine,
     mouse_left_down: bool, 
     mouse_left_pressed: bool, 
     mouse_right_pressed: bool, 
@@ -110,7 +293,7 @@ pub extern "C" fn update_mouse(
     mouse_y: f32,
     rendering_scale: f32
 ) {
-    engine_mut().mouse.update(
+    (*engine).update_mouse(
         mouse_left_down, 
         mouse_left_pressed, mouse_right_pressed, 
         mouse_x, mouse_y, 
@@ -126,8 +309,8 @@ pub struct RenderableItem {
     pub frame: IntRect
 }
 
-pub fn renderables_vec() -> Vec<RenderableItem> {
-    let world = &engine().world;
+pub fn renderables_vec(engine: &GameEngine) -> Vec<RenderableItem> {
+    let world = &engine.world;
     let visible_entities = &world.visible_entities;
     let entities_map = world.entities.borrow();    
 
@@ -165,100 +348,97 @@ pub fn renderables_vec() -> Vec<RenderableItem> {
         .collect()
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn renderables(length: *mut usize) -> *mut RenderableItem {
-    let items = renderables_vec();
+pub unsafe extern "C" fn renderables(engine: *const GameEngine, length: *mut usize) -> *mut RenderableItem {
+    let items = renderables_vec(&*engine);
 
     let len = items.len();
-    unsafe {
-        ptr::write(length, len);
-    }
+    ptr::write(length, len);
 
     let ptr = items.as_ptr() as *mut RenderableItem;
     std::mem::forget(items);
     ptr
 }
 
+/// # Safety
+/// `ptr` and `length` must come from a single call to `renderables`.
 #[no_mangle]
-pub extern "C" fn free_renderables(ptr: *mut RenderableItem, length: usize) {
+pub unsafe extern "C" fn free_renderables(ptr: *mut RenderableItem, length: usize) {
     if !ptr.is_null() {
-        unsafe {
-            let _ = Vec::from_raw_parts(ptr, length, length);
-        }
+        let _ = Vec::from_raw_parts(ptr, length, length);
     }
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn initialize_config(
-    current_lang: *const c_char,
-    levels_path: *const c_char,
-    species_path: *const c_char,
-    inventory_path: *const c_char,
-    key_value_storage_path: *const c_char,
-    localized_strings_path: *const c_char,
-) {
-    initialize_config_paths(
-        to_string(current_lang),
-        to_path(levels_path),
-        to_path(species_path),
-        to_path(inventory_path),
-        to_path(key_value_storage_path),
-        to_path(localized_strings_path),
-    );
-}
-
-#[no_mangle]
-pub extern "C" fn can_render_frame() -> bool {
-    let engine = engine();
-    !engine.loading_screen.is_in_progress() || engine.loading_screen.progress() > 0.4
+pub unsafe extern "C" fn can_render_frame(engine: *const GameEngine) -> bool {
+    (*engine).can_render_frame()
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn current_biome_tiles_variant() -> i32 {
-    engine().world.biome_tiles.current_variant()
+pub unsafe extern "C" fn current_biome_tiles_variant(engine: *const GameEngine) -> i32 {
+    (*engine).world.biome_tiles.current_variant()
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn current_world_width() -> i32 {
-    engine().world.bounds.w
+pub unsafe extern "C" fn current_world_width(engine: *const GameEngine) -> i32 {
+    (*engine).world.bounds.w
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn current_world_height() -> i32 {
-    engine().world.bounds.h
+pub unsafe extern "C" fn current_world_height(engine: *const GameEngine) -> i32 {
+    (*engine).world.bounds.h
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn camera_viewport() -> IntRect {
-    engine().camera_viewport
+pub unsafe extern "C" fn camera_viewport(engine: *const GameEngine) -> IntRect {
+    (*engine).camera_viewport
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn camera_viewport_offset() -> Vector2d {
-    engine().camera_viewport_offset
+pub unsafe extern "C" fn camera_viewport_offset(engine: *const GameEngine) -> Vector2d {
+    (*engine).camera_viewport_offset
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn current_world_default_tile() -> BiomeTile {
-    engine().world.default_tile()
+pub unsafe extern "C" fn current_world_default_tile(engine: *const GameEngine) -> BiomeTile {
+    (*engine).world.default_tile()
 }
 
-fn to_string(value: *const c_char) -> String {
-    unsafe { CStr::from_ptr(value) }.to_str().unwrap().to_owned()
+/// Invalid UTF-8 coming from the host app is replaced rather than panicking across the FFI boundary.
+unsafe fn to_string(value: *const c_char) -> String {
+    CStr::from_ptr(value).to_string_lossy().into_owned()
 }
 
-fn to_path(value: *const c_char) -> PathBuf {
+unsafe fn to_path(value: *const c_char) -> PathBuf {
     PathBuf::from(to_string(value))
 }
 
-pub fn biome_tiles_vec() -> &'static Vec<Vec<BiomeTile>> {
-    println!("Getting tiles for world {}", &engine().world.id);
-    &engine().world.biome_tiles.tiles
+pub fn biome_tiles_vec(engine: &GameEngine) -> &Vec<Vec<BiomeTile>> {
+    log_debug!(engine.context.logger, Subsystem::Ffi, "Getting tiles for world {}", &engine.world.id);
+    &engine.world.biome_tiles.tiles
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn get_biome_tiles(out_tiles: *mut *const BiomeTile, out_len_x: *mut usize, out_len_y: *mut usize) {
-    let tiles = biome_tiles_vec();
+pub unsafe extern "C" fn get_biome_tiles(engine: *const GameEngine, out_tiles: *mut *const BiomeTile, out_len_x: *mut usize, out_len_y: *mut usize) {
+    let tiles = biome_tiles_vec(&*engine);
     let len_y = tiles.len();
     let len_x = if len_y > 0 { tiles[0].len() } else { 0 };
 
@@ -267,28 +447,28 @@ pub extern "C" fn get_biome_tiles(out_tiles: *mut *const BiomeTile, out_len_x: *
 
     std::mem::forget(flat_tiles);
 
-    unsafe {
-        *out_tiles = ptr;
-        *out_len_x = len_x;
-        *out_len_y = len_y;
-    }
+    *out_tiles = ptr;
+    *out_len_x = len_x;
+    *out_len_y = len_y;
 }
 
+/// # Safety
+/// `tiles_ptr`, `len_x` and `len_y` must come from a single call to `get_biome_tiles`.
 #[no_mangle]
-pub extern "C" fn free_biome_tiles(tiles_ptr: *mut BiomeTile, len_x: usize, len_y: usize) {
+pub unsafe extern "C" fn free_biome_tiles(tiles_ptr: *mut BiomeTile, len_x: usize, len_y: usize) {
     let len = len_x * len_y;
-    unsafe {
-        let _ = Vec::from_raw_parts(tiles_ptr, len, len);
-    }
+    let _ = Vec::from_raw_parts(tiles_ptr, len, len);
 }
 
-pub fn construction_tiles_vec() -> &'static Vec<Vec<ConstructionTile>> {
-    &engine().world.constructions_tiles.tiles
+pub fn construction_tiles_vec(engine: &GameEngine) -> &Vec<Vec<ConstructionTile>> {
+    &engine.world.constructions_tiles.tiles
 }
 
+/// # Safety
+/// `engine` must be a live handle returned by `nokemon_engine_new`.
 #[no_mangle]
-pub extern "C" fn get_construction_tiles(out_tiles: *mut *const ConstructionTile, out_len_x: *mut usize, out_len_y: *mut usize) {
-    let tiles = construction_tiles_vec();
+pub unsafe extern "C" fn get_construction_tiles(engine: *const GameEngine, out_tiles: *mut *const ConstructionTile, out_len_x: *mut usize, out_len_y: *mut usize) {
+    let tiles = construction_tiles_vec(&*engine);
     let len_y = tiles.len();
     let len_x = if len_y > 0 { tiles[0].len() } else { 0 };
 
@@ -297,17 +477,15 @@ pub extern "C" fn get_construction_tiles(out_tiles: *mut *const ConstructionTile
 
     std::mem::forget(flat_tiles);
 
-    unsafe {
-        *out_tiles = ptr;
-        *out_len_x = len_x;
-        *out_len_y = len_y;
-    }
+    *out_tiles = ptr;
+    *out_len_x = len_x;
+    *out_len_y = len_y;
 }
 
+/// # Safety
+/// `tiles_ptr`, `len_x` and `len_y` must come from a single call to `get_construction_tiles`.
 #[no_mangle]
-pub extern "C" fn free_construction_tiles(tiles_ptr: *mut ConstructionTile, len_x: usize, len_y: usize) {
+pub unsafe extern "C" fn free_construction_tiles(tiles_ptr: *mut ConstructionTile, len_x: usize, len_y: usize) {
     let len = len_x * len_y;
-    unsafe {
-        let _ = Vec::from_raw_parts(tiles_ptr, len, len);
-    }
+    let _ = Vec::from_raw_parts(tiles_ptr, len, len);
 }
\ No newline at end of file
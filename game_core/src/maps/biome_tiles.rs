@@ -225,6 +225,7 @@ impl Biome {
         self == &other || (self.is_grass() && other.is_grass())
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     fn is_grass(&self) -> bool {
         match self {
             Biome::Grass => true,
@@ -353,8 +354,14 @@ impl<'de> Deserialize<'de> for TileSet<BiomeTile> {
         }
 
         let data = TileSetData::deserialize(deserializer)?;
+        Ok(TileSet::<BiomeTile>::from_rows(data.sheet_id, &data.tiles))
+    }
+}
 
-        let mut tiles: Vec<Vec<BiomeTile>> = data.tiles.into_iter().map(|tile_row| {
+impl TileSet<BiomeTile> {
+    /// One character per tile, as in level files
+    pub fn from_rows(sheet_id: u32, rows: &[String]) -> Self {
+        let mut tiles: Vec<Vec<BiomeTile>> = rows.iter().map(|tile_row| {
             tile_row.chars().map(|tile_char| {
                 BiomeTile::from_data(tile_char)
             }).collect()
@@ -374,6 +381,6 @@ impl<'de> Deserialize<'de> for TileSet<BiomeTile> {
             }
         }
 
-        Ok(TileSet::with_tiles(data.sheet_id, tiles))
+        TileSet::with_tiles(sheet_id, tiles)
     }
 }
\ No newline at end of file
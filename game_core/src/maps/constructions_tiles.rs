@@ -47,6 +47,7 @@ impl SpriteTile for ConstructionTile {
 }
 
 impl ConstructionTile {
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_obstacle(&self) -> bool {
         match self.tile_type {
             Construction::Nothing => false,
@@ -146,7 +147,7 @@ impl TileSet<ConstructionTile> {
 }
 
 impl Construction {
-    fn from_char(c: char) -> Self {
+    pub const fn from_char(c: char) -> Self {
         match c {
             '0' => Construction::Nothing,
             '1' => Construction::WoodenFence,
@@ -233,8 +234,14 @@ impl<'de> Deserialize<'de> for TileSet<ConstructionTile> {
         }
 
         let data = TileSetData::deserialize(deserializer)?;
+        Ok(TileSet::<ConstructionTile>::from_rows(data.sheet_id, &data.tiles))
+    }
+}
 
-        let mut tiles: Vec<Vec<ConstructionTile>> = data.tiles.into_iter().map(|tile_row| {
+impl TileSet<ConstructionTile> {
+    /// One character per tile, as in level files
+    pub fn from_rows(sheet_id: u32, rows: &[String]) -> Self {
+        let mut tiles: Vec<Vec<ConstructionTile>> = rows.iter().map(|tile_row| {
             tile_row.chars().map(|tile_char| {
                 ConstructionTile::from_data(tile_char)
             }).collect()
@@ -254,6 +261,6 @@ impl<'de> Deserialize<'de> for TileSet<ConstructionTile> {
             }
         }
 
-        Ok(TileSet::with_tiles(data.sheet_id, tiles))
+        TileSet::with_tiles(sheet_id, tiles)
     }
 }
\ No newline at end of file
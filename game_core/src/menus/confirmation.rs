@@ -1,5 +1,5 @@
 
-use crate::{constants::SPRITE_SHEET_MENU, game_engine::{keyboard_events_provider::KeyboardEventsProvider, state_updates::WorldStateUpdate}, lang::localizable::LocalizableText, ui::{components::{empty_view, BordersTextures, TextureInfo, View}, scaffold::scaffold}, utils::rect::IntRect};
+use crate::{constants::SPRITE_SHEET_MENU, game_engine::{keyboard_events_provider::KeyboardEventsProvider, state_updates::WorldStateUpdate}, lang::localizable::{LocalizableText, LocalizedStrings}, ui::{components::{empty_view, BordersTextures, TextureInfo, View}, scaffold::scaffold}, utils::rect::IntRect};
 
 use super::menu::{Menu, MenuItem, MenuUpdate};
 
@@ -15,14 +15,20 @@ enum ConfirmationOption {
 }
 
 impl MenuItem for ConfirmationOption {
-    fn title(&self) -> String {
+    fn title(&self, strings: &LocalizedStrings) -> String {
         match self {
-            ConfirmationOption::YesConfirm => "confirmation.confirm".localized(),
-            ConfirmationOption::NoCancel => "confirmation.cancel".localized(),
+            ConfirmationOption::YesConfirm => "confirmation.confirm".localized(strings),
+            ConfirmationOption::NoCancel => "confirmation.cancel".localized(strings),
         }
     }
 }
 
+impl Default for ConfirmationDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ConfirmationDialog {
     pub fn new() -> Self {
         Self {
@@ -38,6 +44,10 @@ impl ConfirmationDialog {
         self.menu.is_open
     }
 
+    pub fn options(&self, strings: &LocalizedStrings) -> Vec<String> {
+        self.menu.item_titles(strings)
+    }
+
     pub fn show(&mut self, title: &str, text: &str, on_confirm: &[WorldStateUpdate]) {
         if self.menu.title == title {
             return 
@@ -80,13 +90,13 @@ const ALERT_BORDERS_TEXTURES: BordersTextures = BordersTextures {
 };
 
 impl ConfirmationDialog {
-    pub fn ui(&self) -> View {
+    pub fn ui(&self, strings: &LocalizedStrings) -> View {
         if self.menu.is_open {       
             scaffold(
                 true, 
                 (0, 0, 0, (255.0 * self.menu.animator.current_value) as u8), 
                 Some(ALERT_BORDERS_TEXTURES),
-                self.menu.menu_contents()
+                self.menu.menu_contents(strings)
             )
         } else {
             empty_view()
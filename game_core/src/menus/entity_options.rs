@@ -1,4 +1,4 @@
-use crate::{entities::species::{EntityType, SPECIES_NONE}, game_engine::{entity::Entity, keyboard_events_provider::KeyboardEventsProvider, locks::LockType, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::LocalizableText, ui::components::View};
+use crate::{entities::species::{EntityType, SPECIES_NONE}, game_engine::{context::GameContext, entity::Entity, keyboard_events_provider::KeyboardEventsProvider, locks::LockType, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::{LocalizableText, LocalizedStrings}, ui::components::View};
 use super::{menu::{Menu, MenuItem, MenuUpdate}, text_input::TextInput};
 
 #[derive(Debug, Clone)]
@@ -9,6 +9,7 @@ pub enum EntityOptionMenuItem {
     Read(String),
     ToggleDemandAttention,
     UseItem,
+    Equip,
     ChangeLock,
     ChangeDestinationWorld,
     ChangeDestinationX,
@@ -16,25 +17,26 @@ pub enum EntityOptionMenuItem {
 }
 
 impl MenuItem for EntityOptionMenuItem {
-    fn title(&self) -> String {
+    fn title(&self, strings: &LocalizedStrings) -> String {
         match self {
-            EntityOptionMenuItem::Remove => "entity.menu.remove".localized(),
-            EntityOptionMenuItem::Rename => "entity.menu.rename".localized(),
-            EntityOptionMenuItem::PickUp => "entity.menu.pickup".localized(),
-            EntityOptionMenuItem::UseItem => "entity.menu.use".localized(),
-            EntityOptionMenuItem::Read(_) => "entity.menu.read".localized(),
-            EntityOptionMenuItem::ToggleDemandAttention => "entity.menu.toggle_demand_attention".localized(),
-            EntityOptionMenuItem::ChangeLock => "entity.menu.change_lock".localized(),
-            EntityOptionMenuItem::ChangeDestinationWorld => "entity.menu.change_destination_world".localized(),
-            EntityOptionMenuItem::ChangeDestinationX => "entity.menu.change_destination_x".localized(),
-            EntityOptionMenuItem::ChangeDestinationY => "entity.menu.change_destination_y".localized(),
+            EntityOptionMenuItem::Remove => "entity.menu.remove".localized(strings),
+            EntityOptionMenuItem::Rename => "entity.menu.rename".localized(strings),
+            EntityOptionMenuItem::PickUp => "entity.menu.pickup".localized(strings),
+            EntityOptionMenuItem::UseItem => "entity.menu.use".localized(strings),
+            EntityOptionMenuItem::Equip => "entity.menu.equip".localized(strings),
+            EntityOptionMenuItem::Read(_) => "entity.menu.read".localized(strings),
+            EntityOptionMenuItem::ToggleDemandAttention => "entity.menu.toggle_demand_attention".localized(strings),
+            EntityOptionMenuItem::ChangeLock => "entity.menu.change_lock".localized(strings),
+            EntityOptionMenuItem::ChangeDestinationWorld => "entity.menu.change_destination_world".localized(strings),
+            EntityOptionMenuItem::ChangeDestinationX => "entity.menu.change_destination_x".localized(strings),
+            EntityOptionMenuItem::ChangeDestinationY => "entity.menu.change_destination_y".localized(strings),
         }
     }
 }
 
 impl MenuItem for LockType {
-    fn title(&self) -> String {
-        self.localized_name()
+    fn title(&self, strings: &LocalizedStrings) -> String {
+        self.localized_name(strings)
     }
 }
 
@@ -58,15 +60,15 @@ pub struct EntityOptionsMenu {
 }
 
 impl EntityOptionsMenu {
-    pub fn new() -> Self {
+    pub fn new(strings: &LocalizedStrings) -> Self {
         Self {
-            entity: Box::new(SPECIES_NONE.make_entity()),
+            entity: Box::new(SPECIES_NONE.make_entity(strings)),
             time_since_last_closed: 1.0,
-            menu: Menu::new("entity.menu.title".localized(), vec![]),
+            menu: Menu::new("entity.menu.title".localized(strings), vec![]),
             state: EntityOptionsMenuState::Closed,
             text_input: TextInput::new(),
             creative_mode: false,
-            lock_menu: Menu::new("entity.menu.change_lock_title".localized(), vec![
+            lock_menu: Menu::new("entity.menu.change_lock_title".localized(strings), vec![
                 LockType::None,
                 LockType::Yellow,
                 LockType::Red,
@@ -82,14 +84,15 @@ impl EntityOptionsMenu {
         &mut self, 
         entity: Box<Entity>, 
         creative_mode: bool,
-        inventory: bool
+        inventory: bool,
+        context: &GameContext
     ) {
         if self.time_since_last_closed < 0.5 {
             return;
         }
         self.entity = entity;
         self.time_since_last_closed = 0.0;
-        self.menu.items = self.available_options(creative_mode, inventory);
+        self.menu.items = self.available_options(creative_mode, inventory, context);
 
         if self.menu.items.is_empty() {
             return
@@ -109,6 +112,15 @@ impl EntityOptionsMenu {
         self.menu.is_open
     }
 
+    /// Options of the lock picker while a lock is being changed, empty while typing
+    pub fn options(&self, strings: &LocalizedStrings) -> Vec<String> {
+        match self.state {
+            EntityOptionsMenuState::Closed => self.menu.item_titles(strings),
+            EntityOptionsMenuState::ChangingLock => self.lock_menu.item_titles(strings),
+            _ => vec![]
+        }
+    }
+
     pub fn update(&mut self, keyboard: &KeyboardEventsProvider, time_since_last_update: f32) -> MenuUpdate {
         if !self.menu.is_open {
             self.time_since_last_closed += time_since_last_update;
@@ -247,6 +259,18 @@ impl EntityOptionsMenu {
                         )
                     ]
                 },
+                EntityOptionMenuItem::Equip => {
+                    self.menu.clear_selection();
+                    self.menu.close();
+                    vec![
+                        WorldStateUpdate::EngineUpdate(
+                            EngineStateUpdate::ResumeGame
+                        ),
+                        WorldStateUpdate::EquipWeapon(
+                            self.entity.species_id
+                        )
+                    ]
+                },
                 EntityOptionMenuItem::Read(contents) => {
                     self.menu.clear_selection();
                     vec![
@@ -284,14 +308,14 @@ impl EntityOptionsMenu {
         (self.menu.is_open, vec![])
     }
 
-    pub fn ui(&self) -> View {
+    pub fn ui(&self, strings: &LocalizedStrings) -> View {
         match self.state {
-            EntityOptionsMenuState::ChangingDestinationWorld => self.text_input.ui(),
-            EntityOptionsMenuState::ChangingDestinationX => self.text_input.ui(),
-            EntityOptionsMenuState::ChangingDestinationY => self.text_input.ui(),
-            EntityOptionsMenuState::ChangingName => self.text_input.ui(),
-            EntityOptionsMenuState::ChangingLock => self.lock_menu.ui(),
-            EntityOptionsMenuState::Closed => self.menu.ui(),
+            EntityOptionsMenuState::ChangingDestinationWorld => self.text_input.ui(strings),
+            EntityOptionsMenuState::ChangingDestinationX => self.text_input.ui(strings),
+            EntityOptionsMenuState::ChangingDestinationY => self.text_input.ui(strings),
+            EntityOptionsMenuState::ChangingName => self.text_input.ui(strings),
+            EntityOptionsMenuState::ChangingLock => self.lock_menu.ui(strings),
+            EntityOptionsMenuState::Closed => self.menu.ui(strings),
         }
     }
 
@@ -303,34 +327,34 @@ impl EntityOptionsMenu {
     fn ask_for_new_name(&mut self) {
         self.state = EntityOptionsMenuState::ChangingName;
         self.text_input.clear();
-        self.text_input.title = "entity.menu.rename_title".localized();
+        self.text_input.title = "entity.menu.rename_title".to_owned();
     }
 
     fn ask_for_new_destination_world(&mut self) {
         self.state = EntityOptionsMenuState::ChangingDestinationWorld;
         self.text_input.clear();
-        self.text_input.title = "entity.menu.change_destination_world".localized();
+        self.text_input.title = "entity.menu.change_destination_world".to_owned();
     }
 
     fn ask_for_new_destination_x(&mut self) {
         self.state = EntityOptionsMenuState::ChangingDestinationX;
         self.text_input.clear();
-        self.text_input.title = "entity.menu.change_destination_x".localized();
+        self.text_input.title = "entity.menu.change_destination_x".to_owned();
     }
 
     fn ask_for_new_destination_y(&mut self) {
         self.state = EntityOptionsMenuState::ChangingDestinationY;
         self.text_input.clear();
-        self.text_input.title = "entity.menu.change_destination_y".localized();
+        self.text_input.title = "entity.menu.change_destination_y".to_owned();
     }
 
-    fn available_options(&self, creative_mode: bool, inventory: bool) -> Vec<EntityOptionMenuItem> {
+    fn available_options(&self, creative_mode: bool, inventory: bool, context: &GameContext) -> Vec<EntityOptionMenuItem> {
         if inventory {
-            self.available_options_inventory()
+            self.available_options_inventory(context)
         } else if creative_mode {
             self.available_options_creative()
         } else {
-            self.available_options_regular()
+            self.available_options_regular(&context.strings)
         }
     }
 
@@ -378,6 +402,9 @@ impl EntityOptionsMenu {
             EntityType::Hint => vec![
                 EntityOptionMenuItem::Remove,
             ],
+            EntityType::Effect => vec![
+                EntityOptionMenuItem::Remove,
+            ],
             EntityType::Bullet => vec![
                 EntityOptionMenuItem::PickUp,
                 EntityOptionMenuItem::Remove,
@@ -385,7 +412,7 @@ impl EntityOptionsMenu {
         }
     }
 
-    fn available_options_regular(&self) -> Vec<EntityOptionMenuItem> {
+    fn available_options_regular(&self, strings: &LocalizedStrings) -> Vec<EntityOptionMenuItem> {
         let mut options: Vec<EntityOptionMenuItem> = vec![];
 
         if self.entity.is_consumable {
@@ -398,20 +425,23 @@ impl EntityOptionsMenu {
             _ => {}
         }
         if let Some(contents) = self.entity.contents.clone() {
-            options.push(EntityOptionMenuItem::Read(contents.localized()));
+            options.push(EntityOptionMenuItem::Read(contents.localized(strings)));
         }
         options
     }
 
-    fn available_options_inventory(&self) -> Vec<EntityOptionMenuItem> {
+    fn available_options_inventory(&self, context: &GameContext) -> Vec<EntityOptionMenuItem> {
         let mut options: Vec<EntityOptionMenuItem> = vec![];
 
         if let Some(contents) = self.entity.contents.clone() {
-            options.push(EntityOptionMenuItem::Read(contents.localized()));
+            options.push(EntityOptionMenuItem::Read(contents.localized(&context.strings)));
         }
         if self.entity.is_consumable {
             options.push(EntityOptionMenuItem::UseItem);
         }
+        if context.species.get(self.entity.species_id).weapon.is_some() {
+            options.push(EntityOptionMenuItem::Equip);
+        }
         options
     }
 }
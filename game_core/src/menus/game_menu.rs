@@ -1,4 +1,6 @@
-use crate::{constants::WORLD_ID_NONE, game_engine::{keyboard_events_provider::KeyboardEventsProvider, mouse_events_provider::MouseEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::LocalizableText, spacing, ui::components::{Spacing, View}, utils::rect::IntRect};
+use std::sync::Arc;
+
+use crate::{constants::WORLD_ID_NONE, game_engine::{context::GameContext, keyboard_events_provider::KeyboardEventsProvider, mouse_events_provider::MouseEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::{LocalizableText, LocalizedStrings}, spacing, ui::components::{Spacing, View}, utils::rect::IntRect};
 
 use super::{inventory::Inventory, map_editor::MapEditor, menu::{Menu, MenuItem, MenuUpdate}};
 
@@ -25,30 +27,39 @@ enum GameMenuItem {
     Inventory,
     MapEditor,
     Status,
+    QuickSave,
+    QuickLoad,
+    ExportSave,
     Exit,
     SaveAndExit,
 }
 
 impl MenuItem for GameMenuItem {
-    fn title(&self) -> String {
+    fn title(&self, strings: &LocalizedStrings) -> String {
         match self {
-            GameMenuItem::Save => "game.menu.save".localized(),
-            GameMenuItem::Inventory => "game.menu.inventory".localized(),
-            GameMenuItem::MapEditor => "game.menu.map_editor".localized(),
-            GameMenuItem::Status => "game.menu.status".localized(),
-            GameMenuItem::Exit => "game.menu.exit".localized(),
-            GameMenuItem::SaveAndExit => "game.menu.save_and_exit".localized(),
+            GameMenuItem::Save => "game.menu.save".localized(strings),
+            GameMenuItem::Inventory => "game.menu.inventory".localized(strings),
+            GameMenuItem::MapEditor => "game.menu.map_editor".localized(strings),
+            GameMenuItem::Status => "game.menu.status".localized(strings),
+            GameMenuItem::QuickSave => "game.menu.quick_save".localized(strings),
+            GameMenuItem::QuickLoad => "game.menu.quick_load".localized(strings),
+            GameMenuItem::ExportSave => "game.menu.export_save".localized(strings),
+            GameMenuItem::Exit => "game.menu.exit".localized(strings),
+            GameMenuItem::SaveAndExit => "game.menu.save_and_exit".localized(strings),
         }
     }
 }
 
 impl GameMenu {
-    pub fn new() -> Self {
+    pub fn new(context: &GameContext) -> Self {
         let menu = Menu::new(
-            "game.menu.title".localized(), 
+            "game.menu.title".localized(&context.strings), 
             vec![
                 GameMenuItem::Status,
                 GameMenuItem::Inventory,
+                GameMenuItem::QuickSave,
+                GameMenuItem::QuickLoad,
+                GameMenuItem::ExportSave,
                 GameMenuItem::Exit,
             ]
         );
@@ -58,7 +69,7 @@ impl GameMenu {
             state: MenuState::Closed,
             menu,
             inventory: Inventory::new(),
-            map_editor: MapEditor::new(),
+            map_editor: MapEditor::new(&context.species),
         }
     }
 
@@ -69,12 +80,18 @@ impl GameMenu {
                 GameMenuItem::MapEditor,
                 GameMenuItem::Status,
                 GameMenuItem::Inventory,
+                GameMenuItem::QuickSave,
+                GameMenuItem::QuickLoad,
+                GameMenuItem::ExportSave,
                 GameMenuItem::SaveAndExit,
             ]
         } else {
             vec![
                 GameMenuItem::Status,
                 GameMenuItem::Inventory,
+                GameMenuItem::QuickSave,
+                GameMenuItem::QuickLoad,
+                GameMenuItem::ExportSave,
                 GameMenuItem::Exit,
             ]
         }
@@ -84,6 +101,14 @@ impl GameMenu {
         !matches!(self.state, MenuState::Closed)
     }
 
+    /// Empty unless the main list is showing, the inventory and the map editor are grids
+    pub fn options(&self, strings: &LocalizedStrings) -> Vec<String> {
+        match self.state {
+            MenuState::Open => self.menu.item_titles(strings),
+            _ => vec![]
+        }
+    }
+
     pub fn close(&mut self) {
         self.menu.clear_selection();
         self.menu.close();
@@ -95,10 +120,11 @@ impl GameMenu {
         camera_vieport: &IntRect, 
         keyboard: &KeyboardEventsProvider, 
         mouse: &MouseEventsProvider,
+        context: &Arc<GameContext>,
         time_since_last_update: f32
     ) -> MenuUpdate {
         if self.is_open() && self.menu.selection_has_been_confirmed {
-            let updates = self.handle_selection();
+            let updates = self.handle_selection(context);
             return (self.menu.is_open, updates)
         }
 
@@ -106,13 +132,13 @@ impl GameMenu {
             MenuState::Closed => self.update_from_close(keyboard),
             MenuState::Open => self.update_from_open(keyboard, time_since_last_update),
             MenuState::Inventory => self.update_from_inventory(keyboard, time_since_last_update),
-            MenuState::MapEditor => self.update_from_map_editor(camera_vieport, keyboard, mouse),
-            MenuState::PlaceItem => self.update_from_place_item(camera_vieport, keyboard, mouse),
+            MenuState::MapEditor => self.update_from_map_editor(camera_vieport, keyboard, mouse, context),
+            MenuState::PlaceItem => self.update_from_place_item(camera_vieport, keyboard, mouse, context),
         };
         (self.is_open(), updates)
     }
 
-    fn handle_selection(&mut self) -> Vec<WorldStateUpdate> {
+    fn handle_selection(&mut self, context: &GameContext) -> Vec<WorldStateUpdate> {
         let selected = self.menu.selected_item();
         self.menu.clear_selection();
 
@@ -122,7 +148,7 @@ impl GameMenu {
                 vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::SaveGame)]
             }
             GameMenuItem::Inventory => {
-                self.inventory.setup();
+                self.inventory.setup(&context.inventory);
                 self.state = MenuState::Inventory;
                 vec![]
             }
@@ -133,7 +159,19 @@ impl GameMenu {
             }
             GameMenuItem::Status => {
                 self.close();
-                vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::DisplayLongText("status.fake".localized()))]
+                vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::DisplayLongText("status.fake".localized(&context.strings)))]
+            }
+            GameMenuItem::QuickSave => {
+                self.close();
+                vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::QuickSave)]
+            }
+            GameMenuItem::QuickLoad => {
+                self.close();
+                vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::QuickLoad)]
+            }
+            GameMenuItem::ExportSave => {
+                self.close();
+                vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::ExportSave)]
             }
             GameMenuItem::SaveAndExit => {
                 self.close();
@@ -176,11 +214,11 @@ impl GameMenu {
         self.inventory.update(keyboard)
     }
 
-    fn update_from_map_editor(&mut self, camera_vieport: &IntRect, keyboard: &KeyboardEventsProvider, mouse: &MouseEventsProvider) -> Vec<WorldStateUpdate> {
+    fn update_from_map_editor(&mut self, camera_vieport: &IntRect, keyboard: &KeyboardEventsProvider, mouse: &MouseEventsProvider, context: &Arc<GameContext>) -> Vec<WorldStateUpdate> {
         if keyboard.has_back_been_pressed {
             self.state = MenuState::Open;
         }
-        self.map_editor.update(camera_vieport, keyboard, mouse);
+        self.map_editor.update(camera_vieport, keyboard, mouse, context);
 
         if self.map_editor.is_placing_item() {
             self.state = MenuState::PlaceItem;
@@ -188,19 +226,19 @@ impl GameMenu {
         vec![]
     }
 
-    fn update_from_place_item(&mut self, camera_vieport: &IntRect, keyboard: &KeyboardEventsProvider, mouse: &MouseEventsProvider) -> Vec<WorldStateUpdate> {
+    fn update_from_place_item(&mut self, camera_vieport: &IntRect, keyboard: &KeyboardEventsProvider, mouse: &MouseEventsProvider, context: &Arc<GameContext>) -> Vec<WorldStateUpdate> {
         if keyboard.has_back_been_pressed {
             self.state = MenuState::MapEditor;
         }
-        self.map_editor.update(camera_vieport, keyboard, mouse)
+        self.map_editor.update(camera_vieport, keyboard, mouse, context)
     }
 
-    pub fn ui(&self, camera_viewport: &IntRect) -> View {
+    pub fn ui(&self, camera_viewport: &IntRect, context: &GameContext) -> View {
         match self.state {
             MenuState::Closed => spacing!(Spacing::Zero),
-            MenuState::Open => self.menu.ui(),
-            MenuState::Inventory => self.inventory.ui(),
-            MenuState::MapEditor | MenuState::PlaceItem => self.map_editor.ui(camera_viewport),
+            MenuState::Open => self.menu.ui(&context.strings),
+            MenuState::Inventory => self.inventory.ui(context),
+            MenuState::MapEditor | MenuState::PlaceItem => self.map_editor.ui(camera_viewport, &context.strings),
         }
     }
 }
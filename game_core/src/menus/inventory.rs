@@ -1,4 +1,4 @@
-use crate::{constants::{SPRITE_SHEET_INVENTORY, TILE_SIZE}, entities::species::{species_by_id, Species}, game_engine::{entity::Entity, inventory::get_inventory, keyboard_events_provider::KeyboardEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::LocalizableText, text, texture, ui::{components::{GridSpacing, Spacing, Typography, View, COLOR_BLACK, COLOR_YELLOW}, scaffold::scaffold}, utils::{rect::IntRect, vector::Vector2d}, zstack};
+use crate::{constants::{SPRITE_SHEET_INVENTORY, TILE_SIZE}, entities::species::Species, game_engine::{context::GameContext, entity::Entity, inventory::InventoryStorage, keyboard_events_provider::KeyboardEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::LocalizableText, text, texture, ui::{components::{GridSpacing, Spacing, Typography, View, COLOR_BLACK, COLOR_YELLOW}, scaffold::scaffold}, utils::{rect::IntRect, vector::Vector2d}, zstack};
 
 use super::menu::MENU_BORDERS_TEXTURES;
 
@@ -14,6 +14,12 @@ enum InventoryState {
     SelectingItem(usize),
 }
 
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Inventory {
     pub fn new() -> Self {
         Self {
@@ -23,8 +29,8 @@ impl Inventory {
         }
     }
 
-    pub fn setup(&mut self) {
-        self.stock = get_inventory()
+    pub fn setup(&mut self, inventory: &InventoryStorage) {
+        self.stock = inventory.items()
     }
 
     pub fn update(&mut self, keyboard: &KeyboardEventsProvider) -> Vec<WorldStateUpdate> {
@@ -68,29 +74,29 @@ impl Inventory {
 }
 
 impl Inventory {
-    pub fn ui(&self) -> View {
+    pub fn ui(&self, context: &GameContext) -> View {
         scaffold(
             true,
             COLOR_BLACK,
             Some(MENU_BORDERS_TEXTURES),
             match self.state {
-                InventoryState::SelectingItem(selected_index) => self.regular_ui(selected_index),                
+                InventoryState::SelectingItem(selected_index) => self.regular_ui(selected_index, context),                
             }
         )
     }
     
-    fn regular_ui(&self, selected_item_index: usize) -> View {
+    fn regular_ui(&self, selected_item_index: usize, context: &GameContext) -> View {
         let ui_elements = vec![
-            text!(Typography::Title, "inventory.title".localized()),
-            text!(Typography::Regular, "inventory.subtitle".localized()),
+            text!(Typography::Title, "inventory.title".localized(&context.strings)),
+            text!(Typography::Regular, "inventory.subtitle".localized(&context.strings)),
             View::VGrid {
                 spacing: GridSpacing::sm(),
                 columns: self.columns,
                 children: self.stock.iter()
                     .map(|e| e.species_id)
-                    .map(species_by_id)
+                    .map(|species_id| context.species.get(species_id))
                     .enumerate()
-                    .map(|(index, species)| { self.item_ui(&species, index, selected_item_index) })
+                    .map(|(index, species)| { self.item_ui(species, index, selected_item_index) })
                     .collect()
             },
         ];
@@ -1,5 +1,6 @@
+use std::sync::Arc;
 
-use crate::{constants::{SPRITE_SHEET_INVENTORY, TILE_SIZE}, entities::{known_species::SPECIES_HERO, species::{EntityType, Species, ALL_SPECIES}}, game_engine::{keyboard_events_provider::KeyboardEventsProvider, mouse_events_provider::MouseEventsProvider, state_updates::WorldStateUpdate}, lang::localizable::LocalizableText, maps::{biome_tiles::Biome, constructions_tiles::Construction}, prefabs::all::new_building, spacing, text, texture, ui::{components::{with_fixed_position, GridSpacing, NonColor, Spacing, Typography, View, COLOR_BLACK, COLOR_BLACK_50, COLOR_RED_60, COLOR_YELLOW}, scaffold::scaffold}, utils::{rect::IntRect, vector::Vector2d}, vstack, zstack};
+use crate::{constants::{SPRITE_SHEET_INVENTORY, TILE_SIZE}, entities::{known_species::SPECIES_HERO, species::{EntityType, Species, SpeciesRegistry}}, game_engine::{context::GameContext, keyboard_events_provider::KeyboardEventsProvider, mouse_events_provider::MouseEventsProvider, state_updates::{EngineStateUpdate, WorldStateUpdate}}, lang::localizable::{LocalizableText, LocalizedStrings}, maps::{biome_tiles::Biome, constructions_tiles::Construction}, prefabs::all::new_building, spacing, text, texture, ui::{components::{with_fixed_position, GridSpacing, NonColor, Spacing, Typography, View, COLOR_BLACK, COLOR_BLACK_50, COLOR_RED_60, COLOR_YELLOW}, scaffold::scaffold}, utils::{rect::IntRect, vector::Vector2d}, vstack, zstack};
 
 use super::menu::MENU_BORDERS_TEXTURES;
 
@@ -20,9 +21,9 @@ enum MapEditorState {
 }
 
 impl MapEditor {
-    pub fn new() -> Self {
+    pub fn new(species: &SpeciesRegistry) -> Self {
         Self {
-            stock: MapEditor::all_possible_items().into_iter().collect(),
+            stock: MapEditor::all_possible_items(species),
             state: MapEditorState::SelectingItem(0),
             current_world_id: 0,
             columns: 12,
@@ -40,6 +41,7 @@ impl MapEditor {
         camera_viewport: &IntRect,    
         keyboard: &KeyboardEventsProvider,
         mouse: &MouseEventsProvider,
+        context: &Arc<GameContext>,
     ) -> Vec<WorldStateUpdate> {
         self.camera_viewport = *camera_viewport;
 
@@ -53,6 +55,7 @@ impl MapEditor {
                 frame,
                 keyboard,
                 mouse,
+                context,
             ),
         }
     }
@@ -114,17 +117,18 @@ impl MapEditor {
         frame: IntRect,
         keyboard: &KeyboardEventsProvider,
         mouse: &MouseEventsProvider,
+        context: &Arc<GameContext>,
     ) -> Vec<WorldStateUpdate> {
         if mouse.has_right_been_pressed {
-            return self.clear_tile(frame);
+            return self.clear_tile(frame, context);
         }
         if self.has_selected_tile() && mouse.is_left_down {
             let updated_frame = self.updated_frame(&frame, mouse, keyboard);
             self.state = MapEditorState::PlacingItem(selected_index, item.clone(), updated_frame);
-            return self.place_item(item, frame);
+            return self.place_item(item, frame, context);
         }
         if mouse.has_left_been_pressed || keyboard.has_confirmation_been_pressed {
-            return self.place_item(item, frame);
+            return self.place_item(item, frame, context);
         }
         if keyboard.has_back_been_pressed {
             self.state = MapEditorState::SelectingItem(selected_index);
@@ -160,7 +164,7 @@ impl MapEditor {
         updated_frame   
     }
 
-    fn place_item(&mut self, item: Stockable, frame: IntRect) -> Vec<WorldStateUpdate> {
+    fn place_item(&mut self, item: Stockable, frame: IntRect, context: &Arc<GameContext>) -> Vec<WorldStateUpdate> {
         let row = frame.y as usize;
         let col = frame.x as usize;
 
@@ -175,34 +179,37 @@ impl MapEditor {
                 _ => vec![WorldStateUpdate::ConstructionTileChange(row, col, construction)],
             },
             Stockable::Entity(species) => match species.entity_type {
-                EntityType::Building => self.place_building(frame, &species),
-                EntityType::Npc => self.place_convertible(frame.offset_y(-1), &species),
-                _ => self.place_convertible(frame, &species),
+                EntityType::Building => self.place_building(frame, &species, context),
+                EntityType::Npc => self.place_convertible(frame.offset_y(-1), &species, context),
+                _ => self.place_convertible(frame, &species, context),
             },
         }
     }
 
-    fn clear_tile(&mut self, frame: IntRect) -> Vec<WorldStateUpdate> {
-        self.place_item(Stockable::ConstructionTile(Construction::Nothing), frame)
+    fn clear_tile(&mut self, frame: IntRect, context: &Arc<GameContext>) -> Vec<WorldStateUpdate> {
+        self.place_item(Stockable::ConstructionTile(Construction::Nothing), frame, context)
     }
 
-    fn place_convertible(&self, frame: IntRect, species: &Species) -> Vec<WorldStateUpdate> {
-        let mut entity = species.make_entity();
+    fn place_convertible(&self, frame: IntRect, species: &Species, context: &GameContext) -> Vec<WorldStateUpdate> {
+        let mut entity = species.make_entity(&context.strings);
         entity.frame.x = frame.x;
         entity.frame.y = frame.y;
         let update = WorldStateUpdate::AddEntity(Box::new(entity));
         vec![update]
     }
 
-    fn place_building(&self, frame: IntRect, species: &Species) -> Vec<WorldStateUpdate> {
+    fn place_building(&self, frame: IntRect, species: &Species, context: &Arc<GameContext>) -> Vec<WorldStateUpdate> {
         let x = frame.x;
         let y = frame.y;
 
-        new_building(self.current_world_id, x, y, species)
-            .into_iter()
-            .map(Box::new)
-            .map(WorldStateUpdate::AddEntity)
-            .collect()
+        match new_building(context, self.current_world_id, x, y, species) {
+            Ok(entities) => entities
+                .into_iter()
+                .map(Box::new)
+                .map(WorldStateUpdate::AddEntity)
+                .collect(),
+            Err(error) => vec![WorldStateUpdate::EngineUpdate(EngineStateUpdate::Error(error))]
+        }
     }
 
     fn has_selected_tile(&self) -> bool {
@@ -303,7 +310,7 @@ impl Stockable {
 }
 
 impl MapEditor {
-    fn all_possible_items() -> Vec<Stockable> {
+    fn all_possible_items(species: &SpeciesRegistry) -> Vec<Stockable> {
         let mut all = vec![
             Stockable::BiomeTile(Biome::Water),
             Stockable::BiomeTile(Biome::Desert),
@@ -341,7 +348,7 @@ impl MapEditor {
             Stockable::ConstructionTile(Construction::StoneBox),
             Stockable::ConstructionTile(Construction::SpoiledTree),
         ];
-        let mut species: Vec<Stockable> = ALL_SPECIES
+        let mut species: Vec<Stockable> = species.all()
             .iter()
             .filter(|s| s.id != SPECIES_HERO)
             .map(|s| Stockable::Entity(s.clone()))
@@ -352,15 +359,15 @@ impl MapEditor {
 }
 
 impl MapEditor {
-    pub fn ui(&self, camera_viewport: &IntRect) -> View {
+    pub fn ui(&self, camera_viewport: &IntRect, strings: &LocalizedStrings) -> View {
         scaffold(
             self.uses_backdrop(),
             self.background_color(),
             Some(MENU_BORDERS_TEXTURES),
             match self.state {
-                MapEditorState::SelectingItem(selected_index) => self.regular_ui(selected_index),
+                MapEditorState::SelectingItem(selected_index) => self.regular_ui(selected_index, strings),
                 MapEditorState::PlacingItem(_, _, ref frame) => {
-                    self.placement_ui(camera_viewport, frame)
+                    self.placement_ui(camera_viewport, frame, strings)
                 }
             },
         )
@@ -377,10 +384,10 @@ impl MapEditor {
         }
     }
 
-    fn placement_ui(&self, camera_viewport: &IntRect, frame: &IntRect) -> View {
+    fn placement_ui(&self, camera_viewport: &IntRect, frame: &IntRect, strings: &LocalizedStrings) -> View {
         vstack!(
             Spacing::MD,
-            text!(Typography::Regular, "map_editor.placement".localized()),
+            text!(Typography::Regular, "map_editor.placement".localized(strings)),
             with_fixed_position(
                 Vector2d::new(
                     TILE_SIZE * (frame.x - camera_viewport.x) as f32,
@@ -395,10 +402,10 @@ impl MapEditor {
         )
     }
 
-    fn regular_ui(&self, selected_item_index: usize) -> View {
+    fn regular_ui(&self, selected_item_index: usize, strings: &LocalizedStrings) -> View {
         let mut ui_elements = vec![
-            text!(Typography::Title, "map_editor.title".localized()),
-            text!(Typography::Regular, "map_editor.subtitle".localized()),
+            text!(Typography::Title, "map_editor.title".localized(strings)),
+            text!(Typography::Regular, "map_editor.subtitle".localized(strings)),
             View::VGrid {
                 spacing: GridSpacing::sm(),
                 columns: self.columns,
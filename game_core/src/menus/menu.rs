@@ -3,7 +3,7 @@ use crate::constants::{MENU_CLOSE_TIME, MENU_OPEN_TIME, SPRITE_SHEET_MENU};
 use crate::ui::components::{empty_view, BordersTextures, TextureInfo};
 use crate::ui::scaffold::scaffold;
 use crate::utils::rect::IntRect;
-use crate::{game_engine::{keyboard_events_provider::KeyboardEventsProvider, state_updates::WorldStateUpdate}, text, ui::components::{Spacing, Typography, View}, utils::animator::Animator, vstack};
+use crate::{game_engine::{keyboard_events_provider::KeyboardEventsProvider, state_updates::WorldStateUpdate}, lang::localizable::LocalizedStrings, text, ui::components::{Spacing, Typography, View}, utils::animator::Animator, vstack};
 
 pub struct Menu<Item: MenuItem> {
     pub title: String,
@@ -19,7 +19,7 @@ pub struct Menu<Item: MenuItem> {
 }
 
 pub trait MenuItem: Clone {
-    fn title(&self) -> String;
+    fn title(&self, strings: &LocalizedStrings) -> String;
 }
 
 pub type MenuUpdate = (bool, Vec<WorldStateUpdate>);
@@ -64,6 +64,10 @@ impl<Item: MenuItem> Menu<Item> {
         self.animator.animate(1.0, 0.0, MENU_CLOSE_TIME)
     }
 
+    pub fn item_titles(&self, strings: &LocalizedStrings) -> Vec<String> {
+        self.items.iter().map(|item| item.title(strings)).collect()
+    }
+
     pub fn selected_item(&self) -> Item {
         self.items[self.selected_index].clone()
     }
@@ -126,24 +130,24 @@ pub const MENU_BORDERS_TEXTURES: BordersTextures = BordersTextures {
 };
 
 impl<Item: MenuItem> Menu<Item> {
-    pub fn ui(&self) -> View {
+    pub fn ui(&self, strings: &LocalizedStrings) -> View {
         if self.is_open {
-            self.menu_ui()
+            self.menu_ui(strings)
         } else {
             empty_view()
         }
     }
 
-    fn menu_ui(&self) -> View {
+    fn menu_ui(&self, strings: &LocalizedStrings) -> View {
         scaffold(
             self.uses_backdrop, 
             (0, 0, 0, (255.0 * self.animator.current_value) as u8), 
             Some(MENU_BORDERS_TEXTURES),
-            self.menu_contents()
+            self.menu_contents(strings)
         )
     }
 
-    pub fn menu_contents(&self) -> View {
+    pub fn menu_contents(&self, strings: &LocalizedStrings) -> View {
         let start_index = self.scroll_offset;
         let end_index = (self.scroll_offset + self.visible_item_count).min(self.items.len());
     
@@ -153,9 +157,9 @@ impl<Item: MenuItem> Menu<Item> {
             .map(|(i, item)| {
                 let actual_index = start_index + i;
                 if actual_index == self.selected_index {
-                    text!(Typography::Selected, format!(" > {}", item.title()))
+                    text!(Typography::Selected, format!(" > {}", item.title(strings)))
                 } else {
-                    text!(Typography::Regular, format!(" {}", item.title()))
+                    text!(Typography::Regular, format!(" {}", item.title(strings)))
                 }
             })
             .collect();
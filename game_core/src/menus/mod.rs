@@ -5,5 +5,6 @@ pub mod inventory;
 pub mod long_text_display;
 pub mod map_editor;
 pub mod menu;
+pub mod slot_picker;
 pub mod text_input;
 pub mod toasts;
\ No newline at end of file
@@ -0,0 +1,124 @@
+use crate::{constants::SPRITE_SHEET_BIOME_TILES, game_engine::{keyboard_events_provider::KeyboardEventsProvider, save_slots::{SaveSlot, SlotMetadata}}, lang::localizable::{LocalizableText, LocalizedStrings}, maps::{biome_tiles::{Biome, BiomeTile}, tiles::SpriteTile}, texture, ui::{components::{empty_view, GridSpacing, Spacing, View}, scaffold::scaffold}, utils::vector::Vector2d, vstack};
+
+use super::menu::{Menu, MenuItem, MENU_BORDERS_TEXTURES};
+
+pub struct SlotPicker {
+    menu: Menu<SlotItem>,
+}
+
+#[derive(Debug, Clone)]
+struct SlotItem {
+    slot: SaveSlot,
+    metadata: Option<SlotMetadata>,
+}
+
+impl MenuItem for SlotItem {
+    fn title(&self, strings: &LocalizedStrings) -> String {
+        let Some(metadata) = &self.metadata else {
+            return "save_slots.empty".localized(strings).replace("%s", &self.slot.id.to_string())
+        };
+        "save_slots.slot"
+            .localized(strings)
+            .replacen("%s", &self.slot.id.to_string(), 1)
+            .replacen("%s", &metadata.current_world.to_string(), 1)
+            .replacen("%s", &format_duration(metadata.playtime as u64), 1)
+            .replacen("%s", &format_duration(metadata.seconds_since_last_save()), 1)
+    }
+}
+
+impl SlotPicker {
+    pub fn new(strings: &LocalizedStrings) -> Self {
+        let mut menu = Menu::empty_with_title("save_slots.title".localized(strings));
+        menu.visible_item_count = 3;
+        Self { menu }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.menu.is_open
+    }
+
+    pub fn options(&self, strings: &LocalizedStrings) -> Vec<String> {
+        self.menu.item_titles(strings)
+    }
+
+    pub fn show(&mut self, slots: Vec<(SaveSlot, Option<SlotMetadata>)>) {
+        self.menu.items = slots.into_iter().map(|(slot, metadata)| SlotItem { slot, metadata }).collect();
+        self.menu.clear_selection();
+        self.menu.show_no_animation();
+    }
+
+    pub fn close(&mut self) {
+        self.menu.clear_selection();
+        self.menu.close();
+    }
+
+    /// Returns the id of the slot the player picked, the picker can't be dismissed otherwise.
+    pub fn update(&mut self, keyboard: &KeyboardEventsProvider, time_since_last_update: f32) -> Option<u32> {
+        self.menu.update(keyboard, time_since_last_update);
+
+        if !self.menu.is_open {
+            self.menu.show_no_animation();
+        }
+        if self.menu.selection_has_been_confirmed {
+            self.menu.selection_has_been_confirmed = false;
+            return Some(self.menu.selected_item().slot.id)
+        }
+        None
+    }
+
+    pub fn ui(&self, strings: &LocalizedStrings) -> View {
+        if !self.menu.is_open {
+            return empty_view()
+        }
+        let preview = self.menu.items
+            .get(self.menu.selected_index)
+            .and_then(|item| item.metadata.as_ref())
+            .map(preview_ui)
+            .unwrap_or_else(empty_view);
+
+        scaffold(
+            true,
+            (0, 0, 0, (255.0 * self.menu.animator.current_value) as u8),
+            Some(MENU_BORDERS_TEXTURES),
+            vstack!(Spacing::XL, self.menu.menu_contents(strings), preview)
+        )
+    }
+}
+
+fn preview_ui(metadata: &SlotMetadata) -> View {
+    let rows: Vec<Vec<Biome>> = metadata.preview.iter().map(|row| row.chars().map(Biome::from_char).collect()).collect();
+    let columns = rows.first().map(|row| row.len()).unwrap_or_default();
+    let biome_at = |row: usize, col: usize, fallback: Biome| {
+        rows.get(row).and_then(|r| r.get(col)).copied().unwrap_or(fallback)
+    };
+
+    let children = rows.iter().enumerate().flat_map(|(row, biomes)| {
+        biomes.iter().enumerate().map(move |(col, biome)| {
+            let mut tile = BiomeTile::from_data(biome.to_char());
+            tile.setup_neighbors(
+                if row > 0 { biome_at(row - 1, col, *biome) } else { *biome },
+                biome_at(row, col + 1, *biome),
+                biome_at(row + 1, col, *biome),
+                if col > 0 { biome_at(row, col - 1, *biome) } else { *biome }
+            );
+            texture!(SPRITE_SHEET_BIOME_TILES, tile.texture_source_rect(0), Vector2d::new(1.0, 1.0))
+        })
+    }).collect();
+
+    View::VGrid {
+        columns,
+        spacing: GridSpacing::new(Spacing::Zero, Spacing::Zero),
+        children
+    }
+}
+
+fn format_duration(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else if minutes < 60 * 24 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}d", minutes / (60 * 24))
+    }
+}
@@ -1,8 +1,9 @@
-use crate::{game_engine::keyboard_events_provider::KeyboardEventsProvider, hstack, lang::localizable::LocalizableText, spacing, text, ui::{components::{Spacing, Typography, View, COLOR_BLACK, COLOR_TRANSPARENT}, scaffold::scaffold}, vstack, zstack};
+use crate::{game_engine::keyboard_events_provider::KeyboardEventsProvider, hstack, lang::localizable::{LocalizableText, LocalizedStrings}, spacing, text, ui::{components::{Spacing, Typography, View, COLOR_BLACK, COLOR_TRANSPARENT}, scaffold::scaffold}, vstack, zstack};
 
 use super::menu::MENU_BORDERS_TEXTURES;
 
 pub struct TextInput {
+    /// Localized when drawn
     pub title: String,
     pub text: String,
     pub is_confirmed: bool,
@@ -12,6 +13,12 @@ pub struct TextInput {
     time_since_shown: f32,
 }
 
+impl Default for TextInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TextInput {
     pub fn new() -> Self {
         Self {
@@ -29,7 +36,7 @@ impl TextInput {
         let did_not_press_character = keyboard.currently_pressed_character.is_none();
 
         self.time_since_shown += time_since_last_update;
-        self.cursor_visible = (self.time_since_shown * 2.3).floor() as u32 % 2 == 0;
+        self.cursor_visible = ((self.time_since_shown * 2.3).floor() as u32).is_multiple_of(2);
 
         if let Some(character) = keyboard.currently_pressed_character {
             if self.cursor_position <= self.text.len() {
@@ -80,7 +87,7 @@ impl TextInput {
 }
 
 impl TextInput {
-    pub fn ui(&self) -> View {
+    pub fn ui(&self, strings: &LocalizedStrings) -> View {
         let (before_cursor, after_cursor) = self.text.split_at(self.cursor_position);        
         let cursor_indicator = if self.cursor_visible { "|" } else { "" };
         
@@ -90,7 +97,7 @@ impl TextInput {
             Some(MENU_BORDERS_TEXTURES),
             vstack!(
                 Spacing::XL,
-                text!(Typography::Title, self.title.clone()),
+                text!(Typography::Title, self.title.localized(strings)),
                 zstack!(
                     Spacing::Zero,
                     COLOR_TRANSPARENT,
@@ -106,7 +113,7 @@ impl TextInput {
                         text!(Typography::Regular, "___________________________________".to_string())
                     )
                 ),
-                text!(Typography::Regular, "text_input.hint".localized())
+                text!(Typography::Regular, "text_input.hint".localized(strings))
             )
         )
     }
@@ -31,6 +31,12 @@ pub struct ToastDisplay {
     queue: VecDeque<Toast>,
 }
 
+impl Default for ToastDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ToastDisplay {
     pub fn new() -> Self {
         Self {
@@ -100,6 +106,10 @@ impl Toast {
         Toast { text, mode: ToastMode::Regular, image: None }
     }
     
+    pub fn important(text: String) -> Self {
+        Toast { text, mode: ToastMode::Important, image: None }
+    }
+
     pub fn regular_with_image(text: String, image: ToastImage) -> Self {
         Toast { text, mode: ToastMode::Regular, image: Some(image) }
     }
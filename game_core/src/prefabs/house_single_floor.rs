@@ -1,27 +1,29 @@
-use crate::{constants::{HOUSE_INTERIOR_COLUMNS, HOUSE_INTERIOR_ROWS}, entities::{known_species::{SPECIES_SEAT_GREEN, SPECIES_TABLE, SPECIES_TELEPORTER}, species::{make_entity_by_species, Species}}, features::destination::Destination, game_engine::{entity::Entity, world::World}, maps::{biome_tiles::Biome, constructions_tiles::Construction}, utils::ids::get_next_id};
+use std::sync::Arc;
 
-pub fn new_house_single_floor(species: &Species, source_world_id: u32, x: i32, y: i32) -> Vec<Entity> {
-    let mut building = species.make_entity();
+use crate::{constants::{HOUSE_INTERIOR_COLUMNS, HOUSE_INTERIOR_ROWS}, entities::{known_species::{SPECIES_SEAT_GREEN, SPECIES_TABLE, SPECIES_TELEPORTER}, species::Species}, features::destination::Destination, game_engine::{context::GameContext, entity::Entity, errors::GameResult, world::World}, maps::{biome_tiles::Biome, constructions_tiles::Construction}};
+
+pub fn new_house_single_floor(context: &Arc<GameContext>, species: &Species, source_world_id: u32, x: i32, y: i32) -> GameResult<Vec<Entity>> {
+    let mut building = species.make_entity(&context.strings);
     building.frame.x = x;
     building.frame.y = y;
 
-    let first_floor_id = get_next_id();
-    let mut door = make_entity_by_species(SPECIES_TELEPORTER);
+    let first_floor_id = context.ids.next_id();
+    let mut door = context.make_entity(SPECIES_TELEPORTER);
     door.destination = Some(Destination::nearest(first_floor_id));
     door.frame.x = x + (building.frame.w as f32 / 2.0).ceil() as i32;
     door.frame.y = y + 3;
 
-    let mut door_back1 = make_entity_by_species(SPECIES_TELEPORTER);
+    let mut door_back1 = context.make_entity(SPECIES_TELEPORTER);
     door_back1.destination = Some(Destination::nearest(source_world_id));
     door_back1.frame.x = (HOUSE_INTERIOR_COLUMNS as f32 / 2.0).ceil() as i32;
     door_back1.frame.y = (HOUSE_INTERIOR_ROWS + 2) as i32;
 
-    let mut door_back2 = make_entity_by_species(SPECIES_TELEPORTER);
+    let mut door_back2 = context.make_entity(SPECIES_TELEPORTER);
     door_back2.destination = Some(Destination::nearest(source_world_id));
     door_back2.frame.x = door_back1.frame.x + 1;
     door_back2.frame.y = door_back1.frame.y;
 
-    let mut first_floor = World::load_or_create(first_floor_id);
+    let mut first_floor = World::load_or_create(first_floor_id, context.clone())?;
 
     for row in 0..HOUSE_INTERIOR_ROWS {
         for col in 0..HOUSE_INTERIOR_COLUMNS {
@@ -39,23 +41,23 @@ pub fn new_house_single_floor(species: &Species, source_world_id: u32, x: i32, y
         first_floor.constructions_tiles.update_tile(row, 0, Construction::LightWall);
     }
 
-    let mut table = make_entity_by_species(SPECIES_TABLE);
+    let mut table = context.make_entity(SPECIES_TABLE);
     table.frame.x = 1;
     table.frame.y = 4;
 
-    let mut seat1 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat1 = context.make_entity(SPECIES_SEAT_GREEN);
     seat1.frame.x = 1;
     seat1.frame.y = 4;
 
-    let mut seat2 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat2 = context.make_entity(SPECIES_SEAT_GREEN);
     seat2.frame.x = 2;
     seat2.frame.y = 4;
 
-    let mut seat3 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat3 = context.make_entity(SPECIES_SEAT_GREEN);
     seat3.frame.x = 1;
     seat3.frame.y = 6;
 
-    let mut seat4 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat4 = context.make_entity(SPECIES_SEAT_GREEN);
     seat4.frame.x = 2;
     seat4.frame.y = 6;
 
@@ -68,5 +70,5 @@ pub fn new_house_single_floor(species: &Species, source_world_id: u32, x: i32, y
     first_floor.add_entity(seat4);
     first_floor.save();
 
-    vec![building, door]   
+    Ok(vec![building, door])
 }
\ No newline at end of file
@@ -1,37 +1,39 @@
-use crate::{constants::{HOUSE_INTERIOR_COLUMNS, HOUSE_INTERIOR_ROWS}, entities::{known_species::{SPECIES_SEAT_GREEN, SPECIES_STAIRS_DOWN, SPECIES_STAIRS_UP, SPECIES_TABLE, SPECIES_TELEPORTER}, species::{make_entity_by_species, Species}}, features::destination::Destination, game_engine::{entity::Entity, world::World}, maps::{biome_tiles::Biome, constructions_tiles::Construction}, utils::ids::get_next_id};
+use std::sync::Arc;
 
-pub fn new_house_two_floors(species: &Species, source_world_id: u32, x: i32, y: i32) -> Vec<Entity> {
-    let mut building = species.make_entity();
+use crate::{constants::{HOUSE_INTERIOR_COLUMNS, HOUSE_INTERIOR_ROWS}, entities::{known_species::{SPECIES_SEAT_GREEN, SPECIES_STAIRS_DOWN, SPECIES_STAIRS_UP, SPECIES_TABLE, SPECIES_TELEPORTER}, species::Species}, features::destination::Destination, game_engine::{context::GameContext, entity::Entity, errors::GameResult, world::World}, maps::{biome_tiles::Biome, constructions_tiles::Construction}};
+
+pub fn new_house_two_floors(context: &Arc<GameContext>, species: &Species, source_world_id: u32, x: i32, y: i32) -> GameResult<Vec<Entity>> {
+    let mut building = species.make_entity(&context.strings);
     building.frame.x = x;
     building.frame.y = y;
 
-    let first_floor_id = get_next_id();
-    let second_floor_id = get_next_id();
+    let first_floor_id = context.ids.next_id();
+    let second_floor_id = context.ids.next_id();
 
-    let mut door = make_entity_by_species(SPECIES_TELEPORTER);
+    let mut door = context.make_entity(SPECIES_TELEPORTER);
     door.destination = Some(Destination::nearest(first_floor_id));
     door.frame.x = x + (building.frame.w as f32 / 2.0).ceil() as i32;
     door.frame.y = y + 4;
 
-    let mut door_back1 = make_entity_by_species(SPECIES_TELEPORTER);
+    let mut door_back1 = context.make_entity(SPECIES_TELEPORTER);
     door_back1.destination = Some(Destination::nearest(source_world_id));
     door_back1.frame.x = (HOUSE_INTERIOR_COLUMNS as f32 / 2.0).ceil() as i32;
     door_back1.frame.y = (HOUSE_INTERIOR_ROWS + 2) as i32;
 
-    let mut door_back2 = make_entity_by_species(SPECIES_TELEPORTER);
+    let mut door_back2 = context.make_entity(SPECIES_TELEPORTER);
     door_back2.destination = Some(Destination::nearest(source_world_id));
     door_back2.frame = door_back1.frame.offset_x(1);
 
-    let mut stairs_up = make_entity_by_species(SPECIES_STAIRS_UP);
+    let mut stairs_up = context.make_entity(SPECIES_STAIRS_UP);
     stairs_up.frame.x = HOUSE_INTERIOR_COLUMNS as i32 - 2;
     stairs_up.frame.y = 0;
 
-    let mut stairs_up_door = make_entity_by_species(SPECIES_TELEPORTER);
+    let mut stairs_up_door = context.make_entity(SPECIES_TELEPORTER);
     stairs_up_door.destination = Some(Destination::nearest(second_floor_id));
     stairs_up_door.frame.x = stairs_up.frame.x;
     stairs_up_door.frame.y = stairs_up.frame.y + 1;
 
-    let mut first_floor = World::load_or_create(first_floor_id);
+    let mut first_floor = World::load_or_create(first_floor_id, context.clone())?;
 
     for row in 0..HOUSE_INTERIOR_ROWS {
         for col in 0..HOUSE_INTERIOR_COLUMNS {
@@ -49,23 +51,23 @@ pub fn new_house_two_floors(species: &Species, source_world_id: u32, x: i32, y:
         first_floor.constructions_tiles.update_tile(row, 0, Construction::LightWall);
     }
 
-    let mut table = make_entity_by_species(SPECIES_TABLE);
+    let mut table = context.make_entity(SPECIES_TABLE);
     table.frame.x = 1;
     table.frame.y = 4;
 
-    let mut seat1 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat1 = context.make_entity(SPECIES_SEAT_GREEN);
     seat1.frame.x = 1;
     seat1.frame.y = 4;
 
-    let mut seat2 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat2 = context.make_entity(SPECIES_SEAT_GREEN);
     seat2.frame.x = 2;
     seat2.frame.y = 4;
 
-    let mut seat3 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat3 = context.make_entity(SPECIES_SEAT_GREEN);
     seat3.frame.x = 1;
     seat3.frame.y = 6;
 
-    let mut seat4 = make_entity_by_species(SPECIES_SEAT_GREEN);
+    let mut seat4 = context.make_entity(SPECIES_SEAT_GREEN);
     seat4.frame.x = 2;
     seat4.frame.y = 6;
 
@@ -80,16 +82,16 @@ pub fn new_house_two_floors(species: &Species, source_world_id: u32, x: i32, y:
     first_floor.add_entity(seat4);
     first_floor.save();    
 
-    let mut stairs_down = make_entity_by_species(SPECIES_STAIRS_DOWN);
+    let mut stairs_down = context.make_entity(SPECIES_STAIRS_DOWN);
     stairs_down.frame.x = HOUSE_INTERIOR_COLUMNS as i32 - 2;
     stairs_down.frame.y = 1;
 
-    let mut stairs_down_door = make_entity_by_species(SPECIES_TELEPORTER);
+    let mut stairs_down_door = context.make_entity(SPECIES_TELEPORTER);
     stairs_down_door.destination = Some(Destination::nearest(first_floor_id));
     stairs_down_door.frame.x = stairs_down.frame.x;
     stairs_down_door.frame.y = stairs_down.frame.y + 1;
 
-    let mut second_floor = World::load_or_create(second_floor_id);
+    let mut second_floor = World::load_or_create(second_floor_id, context.clone())?;
 
     for row in 0..HOUSE_INTERIOR_ROWS {
         for col in 0..HOUSE_INTERIOR_COLUMNS {
@@ -109,5 +111,5 @@ pub fn new_house_two_floors(species: &Species, source_world_id: u32, x: i32, y:
     second_floor.add_entity(stairs_down_door);
     second_floor.save();    
 
-    vec![building, door]   
+    Ok(vec![building, door])
 }
\ No newline at end of file
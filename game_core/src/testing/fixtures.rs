@@ -0,0 +1,209 @@
+use std::{collections::{BTreeMap, HashMap}, sync::Arc};
+
+use crate::{config::Config, constants::{SPRITE_SHEET_BIOME_TILES, SPRITE_SHEET_CONSTRUCTION_TILES}, entities::{known_species::SPECIES_HERO, species::{EntityType, Species, SpeciesRegistry, SPECIES_NONE}}, features::directions::MovementDirections, game_engine::{context::GameContext, storage::{KeyValueStorage, StoredValues}, world::World}, lang::localizable::LocalizedStrings, maps::{biome_tiles::BiomeTile, constructions_tiles::ConstructionTile, tiles::TileSet}, utils::rect::IntRect};
+
+const EMPTY_CELLS: [char; 2] = ['.', ' '];
+
+/// A 1x1 species, use ids that are not in `data/species.json`.
+pub fn fixture_species(id: u32, entity_type: EntityType, is_rigid: bool) -> Species {
+    Species {
+        id,
+        name: format!("fixture.species.{}", id),
+        entity_type,
+        is_rigid,
+        sprite_frame: IntRect::new(0, 0, 1, 1),
+        ..SPECIES_NONE
+    }
+}
+
+/// A 1x2 hero that walks with the keyboard, fixtures can replace it by registering another species with `SPECIES_HERO`.
+pub fn fixture_hero() -> Species {
+    Species {
+        base_speed: 4.0,
+        sprite_frame: IntRect::new(0, 0, 1, 2),
+        movement_directions: MovementDirections::Keyboard,
+        hp: 6,
+        ..fixture_species(SPECIES_HERO, EntityType::Hero, true)
+    }
+}
+
+/// Builds worlds from ASCII art, using the codes of `Biome::from_char` and `Construction::from_char`.
+/// Entities are placed by a separate grid, where each symbol maps to a species via the legend.
+/// Species and strings only come from the fixture, so its context never reads `data` or `lang`.
+pub struct WorldFixture {
+    id: u32,
+    biomes: Vec<String>,
+    constructions: Vec<String>,
+    entities: Vec<String>,
+    legend: HashMap<char, u32>,
+    species: Vec<Species>,
+    strings: HashMap<String, String>,
+    storage: StoredValues,
+}
+
+impl WorldFixture {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            biomes: vec![],
+            constructions: vec![],
+            entities: vec![],
+            legend: HashMap::new(),
+            species: vec![],
+            strings: HashMap::new(),
+            storage: BTreeMap::new(),
+        }
+    }
+
+    pub fn biomes(mut self, rows: &[&str]) -> Self {
+        self.biomes = rows.iter().map(|row| row.to_string()).collect();
+        self
+    }
+
+    pub fn constructions(mut self, rows: &[&str]) -> Self {
+        self.constructions = rows.iter().map(|row| row.to_string()).collect();
+        self
+    }
+
+    pub fn entities(mut self, rows: &[&str]) -> Self {
+        self.entities = rows.iter().map(|row| row.to_string()).collect();
+        self
+    }
+
+    pub fn legend(mut self, symbol: char, species_id: u32) -> Self {
+        self.legend.insert(symbol, species_id);
+        self
+    }
+
+    pub fn species(mut self, species: Species) -> Self {
+        self.species.push(species);
+        self
+    }
+
+    pub fn string(mut self, key: &str, value: &str) -> Self {
+        self.strings.insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    pub fn storage(mut self, key: &str, value: u32) -> Self {
+        self.storage.insert(key.to_owned(), value.into());
+        self
+    }
+
+    pub fn context(&self) -> GameContext {
+        let config = Config::in_memory();
+        let mut species = SpeciesRegistry::new(vec![fixture_hero()]);
+        species.register(self.species.clone());
+        let mut strings = LocalizedStrings::new(&config.current_lang);
+        strings.register(&config.current_lang, self.strings.clone());
+
+        GameContext::in_memory(species, strings, KeyValueStorage::from_values(self.storage.clone()))
+    }
+
+    pub fn build(&self) -> World {
+        self.build_in(Arc::new(self.context()))
+    }
+
+    pub fn build_in(&self, context: Arc<GameContext>) -> World {
+        let (columns, rows) = self.size();
+        let mut world = World::new(self.id, context);
+
+        let biomes = filled(&self.biomes, columns, rows, '1');
+        world.load_biome_tiles(TileSet::<BiomeTile>::from_rows(SPRITE_SHEET_BIOME_TILES, &biomes));
+
+        let constructions = filled(&self.constructions, columns, rows, '0');
+        world.load_construction_tiles(TileSet::<ConstructionTile>::from_rows(SPRITE_SHEET_CONSTRUCTION_TILES, &constructions));
+
+        for (y, row) in self.entities.iter().enumerate() {
+            for (x, symbol) in row.chars().enumerate() {
+                if EMPTY_CELLS.contains(&symbol) {
+                    continue
+                }
+                let species_id = *self.legend.get(&symbol).unwrap_or_else(|| panic!("Missing legend for '{}'", symbol));
+                let mut entity = world.context.make_entity(species_id);
+                entity.frame.x = x as i32;
+                entity.frame.y = y as i32;
+                world.add_entity(entity);
+            }
+        }
+
+        world.update_tiles_hitmap();
+        world.visible_entities = world.compute_visible_entities(&world.bounds);
+        world.update_hitmaps();
+        world
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let grids = [&self.biomes, &self.constructions, &self.entities];
+        let columns = grids.iter().flat_map(|grid| grid.iter().map(|row| row.chars().count())).max().unwrap_or(0);
+        let rows = grids.iter().map(|grid| grid.len()).max().unwrap_or(0);
+        (columns.max(1), rows.max(1))
+    }
+}
+
+fn filled(grid: &[String], columns: usize, rows: usize, fill: char) -> Vec<String> {
+    (0..rows)
+        .map(|row| {
+            let mut chars: Vec<char> = grid.get(row).map(|r| r.chars().collect()).unwrap_or_default();
+            chars.resize(columns, fill);
+            chars.into_iter().collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{entities::{known_species::SPECIES_HERO, species::EntityType}, lang::localizable::LocalizableText, maps::{biome_tiles::Biome, constructions_tiles::Construction}};
+
+    use super::{fixture_hero, fixture_species, WorldFixture};
+
+    const SPECIES_FIXTURE_ROCK: u32 = 900_001;
+
+    #[test]
+    fn builds_tiles_and_entities_from_ascii_art() {
+        let world = WorldFixture::new(1)
+            .biomes(&[
+                "1112",
+                "1112",
+            ])
+            .constructions(&[
+                "0000",
+                "8000",
+            ])
+            .entities(&[
+                ".r..",
+                "....",
+            ])
+            .legend('r', SPECIES_FIXTURE_ROCK)
+            .species(fixture_species(SPECIES_FIXTURE_ROCK, EntityType::StaticObject, true))
+            .build();
+
+        assert_eq!(world.bounds.w, 4);
+        assert_eq!(world.bounds.h, 2);
+        assert_eq!(world.biome_tiles.tiles[0][3].tile_type, Biome::Water);
+        assert_eq!(world.constructions_tiles.tiles[1][0].tile_type, Construction::Forest);
+
+        let entities = world.entities.borrow();
+        assert_eq!(entities.len(), 1);
+        assert_eq!((entities[0].frame.x, entities[0].frame.y), (1, 0));
+        assert!(world.hitmap[0][1]);
+        assert!(world.hitmap[1][0]);
+        assert!(world.hitmap[0][3]);
+        assert!(!world.hitmap[0][0]);
+    }
+
+    #[test]
+    fn registers_species_strings_and_storage_in_memory() {
+        let world = WorldFixture::new(1)
+            .string("fixture.greeting", "Hello!")
+            .storage("fixture.key", 3)
+            .build();
+
+        assert_eq!("fixture.greeting".localized(&world.context.strings), "Hello!");
+        assert_eq!(world.context.storage.get_value_for_key("fixture.key"), Some(3));
+        assert!(world.context.config.is_in_memory());
+        assert_eq!(world.context.species.all().len(), 1);
+        assert_eq!(world.context.species.get(SPECIES_HERO).name, fixture_hero().name);
+        assert_eq!("game.menu.status".localized(&world.context.strings), "game.menu.status");
+    }
+}
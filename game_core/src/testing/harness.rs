@@ -0,0 +1,378 @@
+use crate::{constants::{TILE_SIZE, WORLD_ID_NONE}, features::destination::Destination, game_engine::{context::GameContext, engine::GameEngine, simulation::SimulationMode, storage::StorageValue}, lang::localizable::LocalizableText, utils::{directions::Direction, rect::IntRect}};
+
+use super::fixtures::WorldFixture;
+
+const TIME_STEP: f32 = 1.0 / 60.0;
+const MAX_FRAMES_PER_TILE: usize = 120;
+
+/// Drives a headless, deterministic engine with high level player actions.
+pub struct TestHarness {
+    pub engine: GameEngine,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Keys {
+    up_pressed: bool,
+    right_pressed: bool,
+    down_pressed: bool,
+    left_pressed: bool,
+    up_down: bool,
+    right_down: bool,
+    down_down: bool,
+    left_down: bool,
+    escape_pressed: bool,
+    menu_pressed: bool,
+    confirm_pressed: bool,
+    attack_pressed: bool,
+}
+
+impl Keys {
+    fn direction(direction: Direction, pressed: bool) -> Self {
+        let mut keys = Keys::default();
+        match direction {
+            Direction::Up => { keys.up_down = true; keys.up_pressed = pressed }
+            Direction::Right => { keys.right_down = true; keys.right_pressed = pressed }
+            Direction::Down => { keys.down_down = true; keys.down_pressed = pressed }
+            Direction::Left => { keys.left_down = true; keys.left_pressed = pressed }
+            Direction::Unknown | Direction::Still => {}
+        }
+        keys
+    }
+}
+
+impl Default for TestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestHarness {
+    pub fn new() -> Self {
+        Self::with_context(GameContext::for_tests())
+    }
+
+    pub fn with_context(context: GameContext) -> Self {
+        let simulation = SimulationMode::Deterministic { seed: 1, time_step: TIME_STEP };
+        let mut engine = GameEngine::with_context(context, simulation);
+        engine.window_size_changed(TILE_SIZE * 30.0, TILE_SIZE * 20.0, 1.0, 8.0, 2.0);
+        engine.start();
+
+        let mut harness = Self { engine };
+        harness.wait(0.5);
+        harness
+    }
+
+    /// Starts the game in a world built from the fixture, with the hero at the given tile.
+    pub fn with_fixture(fixture: WorldFixture, x: i32, y: i32) -> Self {
+        let mut harness = Self::with_context(fixture.context());
+        let mut world = fixture.build_in(harness.engine.context.clone());
+        world.setup(WORLD_ID_NONE, &Direction::Down, x, y);
+        world.update(0.001);
+
+        harness.engine.world = world;
+        harness.engine.menu.current_world_id = harness.engine.world.id;
+        harness.wait(0.5);
+        harness
+    }
+
+    pub fn teleport(&mut self, world_id: u32, x: i32, y: i32) -> &mut Self {
+        self.engine.teleport(&Destination::new(world_id, x, y));
+        self.wait(0.5)
+    }
+
+    pub fn wait(&mut self, seconds: f32) -> &mut Self {
+        let frames = (seconds / TIME_STEP).ceil() as usize;
+        for _ in 0..frames {
+            self.tick(Keys::default());
+        }
+        self
+    }
+
+    /// Holds the arrow key until the hero has moved by `tiles`, or gives up if it's blocked.
+    pub fn walk(&mut self, direction: Direction, tiles: i32) -> &mut Self {
+        let (dx, dy) = direction.as_col_row_offset();
+        let start = self.hero_frame();
+        let target = (start.x + dx * tiles, start.y + dy * tiles);
+
+        self.tick(Keys::direction(direction, true));
+
+        for _ in 0..(tiles.max(1) as usize * MAX_FRAMES_PER_TILE) {
+            let frame = self.hero_frame();
+            if (frame.x, frame.y) == target {
+                break
+            }
+            self.tick(Keys::direction(direction, false));
+        }
+        self.tick(Keys::default());
+        self
+    }
+
+    pub fn face(&mut self, direction: Direction) -> &mut Self {
+        self.tick(Keys::direction(direction, true));
+        self.tick(Keys::default())
+    }
+
+    pub fn interact(&mut self) -> &mut Self {
+        self.press(Keys { confirm_pressed: true, ..Default::default() })
+    }
+
+    pub fn attack(&mut self) -> &mut Self {
+        self.press(Keys { attack_pressed: true, ..Default::default() })
+    }
+
+    pub fn open_menu(&mut self) -> &mut Self {
+        self.press(Keys { menu_pressed: true, ..Default::default() })
+    }
+
+    pub fn back(&mut self) -> &mut Self {
+        self.press(Keys { escape_pressed: true, ..Default::default() })
+    }
+
+    /// Moves the selection of the open menu down to the option with the given text, or localization key, and confirms it.
+    pub fn choose(&mut self, option: &str) -> &mut Self {
+        let options = self.engine.menu_options();
+        let localized_option = option.localized(&self.engine.context.strings);
+        let Some(index) = options.iter().position(|title| title.trim() == option || title.trim() == localized_option) else {
+            panic!("No option `{}` in the open menu, available options are {:?}", option, options)
+        };
+        for _ in 0..index {
+            self.press(Keys { down_pressed: true, down_down: true, ..Default::default() });
+        }
+        self.interact()
+    }
+
+    pub fn hero_frame(&self) -> IntRect {
+        self.engine.world.cached_hero_props.frame
+    }
+
+    pub fn assert_world(&mut self, world_id: u32) -> &mut Self {
+        assert_eq!(self.engine.world.id, world_id, "Hero is not in the expected world");
+        self
+    }
+
+    pub fn assert_hero_at(&mut self, x: i32, y: i32) -> &mut Self {
+        let frame = self.hero_frame();
+        assert_eq!((frame.x, frame.y), (x, y), "Hero is not at the expected position");
+        self
+    }
+
+    pub fn assert_entity_count(&mut self, species_id: u32, count: usize) -> &mut Self {
+        let actual = self.engine.world.entities.borrow().iter().filter(|e| e.species_id == species_id).count();
+        assert_eq!(actual, count, "Unexpected number of entities of species {}", species_id);
+        self
+    }
+
+    pub fn assert_inventory_count(&mut self, species_id: u32, count: usize) -> &mut Self {
+        let actual = self.engine.context.inventory.items().iter().filter(|e| e.species_id == species_id).count();
+        assert_eq!(actual, count, "Unexpected number of items of species {} in inventory", species_id);
+        self
+    }
+
+    pub fn assert_storage(&mut self, key: &str, value: Option<StorageValue>) -> &mut Self {
+        assert_eq!(self.engine.context.storage.get(key), value, "Unexpected value for {}", key);
+        self
+    }
+
+    pub fn assert_menu_open(&mut self, is_open: bool) -> &mut Self {
+        assert_eq!(self.engine.menu.is_open(), is_open);
+        self
+    }
+
+    pub fn assert_dialogue_open(&mut self, is_open: bool) -> &mut Self {
+        assert_eq!(self.engine.dialogue_menu.is_open(), is_open);
+        self
+    }
+
+    pub fn assert_long_text_open(&mut self, is_open: bool) -> &mut Self {
+        assert_eq!(self.engine.long_text_display.is_open, is_open);
+        self
+    }
+
+    fn press(&mut self, keys: Keys) -> &mut Self {
+        self.tick(keys);
+        self.tick(Keys::default())
+    }
+
+    fn tick(&mut self, keys: Keys) -> &mut Self {
+        self.engine.update_keyboard(
+            keys.up_pressed, keys.right_pressed, keys.down_pressed, keys.left_pressed,
+            keys.up_down, keys.right_down, keys.down_down, keys.left_down,
+            keys.escape_pressed, keys.menu_pressed, keys.confirm_pressed, keys.attack_pressed, false,
+            None, TIME_STEP
+        );
+        self.engine.update(TIME_STEP);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{constants::WORLD_ID_DEMO, dialogues::models::Dialogue, entities::{known_species::SPECIES_KUNAI, species::{EntityType, Species}}, game_engine::{locks::LockType, storage::{StorageKey, StorageValue}}, testing::fixtures::{fixture_species, WorldFixture}, utils::directions::Direction};
+
+    use super::TestHarness;
+
+    const SPECIES_FIXTURE_BOULDER: u32 = 900_201;
+    const SPECIES_FIXTURE_NPC: u32 = 900_202;
+    const SPECIES_FIXTURE_PUSHABLE: u32 = 900_203;
+    const SPECIES_FIXTURE_PLATE: u32 = 900_204;
+    const SPECIES_FIXTURE_GATE: u32 = 900_205;
+    const SPECIES_FIXTURE_REWARD: u32 = 900_206;
+
+    #[test]
+    fn hero_walks_by_the_requested_number_of_tiles() {
+        let mut game = TestHarness::new();
+        let start = game.hero_frame();
+
+        game.walk(Direction::Down, 2)
+            .assert_world(WORLD_ID_DEMO)
+            .assert_hero_at(start.x, start.y + 2)
+            .assert_storage(&StorageKey::latest_world(), Some(WORLD_ID_DEMO.into()));
+    }
+
+    #[test]
+    fn status_can_be_read_from_game_menu() {
+        let mut game = TestHarness::new();
+
+        game.open_menu().assert_menu_open(true);
+        game.choose("game.menu.status")
+            .assert_menu_open(false)
+            .assert_long_text_open(true);
+    }
+
+    #[test]
+    fn attacking_shoots_a_kunai_from_inventory() {
+        let mut game = TestHarness::new();
+        let context = game.engine.context.clone();
+        context.inventory.add(context.make_entity(SPECIES_KUNAI), &context.ids);
+
+        game.attack()
+            .assert_inventory_count(SPECIES_KUNAI, 0)
+            .assert_entity_count(SPECIES_KUNAI, 1);
+    }
+
+    #[test]
+    fn hero_stops_in_front_of_obstacles() {
+        let fixture = WorldFixture::new(900_200)
+            .biomes(&["1111111111"; 8])
+            .entities(&[
+                "",
+                "",
+                "",
+                "",
+                "......b",
+            ])
+            .legend('b', SPECIES_FIXTURE_BOULDER)
+            .species(fixture_species(SPECIES_FIXTURE_BOULDER, EntityType::StaticObject, true));
+
+        let mut game = TestHarness::with_fixture(fixture, 2, 3);
+
+        game.assert_world(900_200)
+            .assert_hero_at(2, 3);
+        game.walk(Direction::Right, 6)
+            .assert_hero_at(5, 3);
+    }
+
+    #[test]
+    fn dialogues_write_typed_values_for_their_world_and_npc() {
+        let fixture = WorldFixture::new(900_210)
+            .biomes(&["1111111111"; 8])
+            .entities(&["", "", "", "", "", "..n"])
+            .legend('n', SPECIES_FIXTURE_NPC)
+            .species(fixture_species(SPECIES_FIXTURE_NPC, EntityType::Npc, true));
+
+        let mut game = TestHarness::with_fixture(fixture, 2, 3);
+        let npc_id = {
+            let mut entities = game.engine.world.entities.borrow_mut();
+            let npc = entities.iter_mut().find(|e| e.species_id == SPECIES_FIXTURE_NPC).unwrap();
+            npc.dialogues = serde_json::from_str(r#"[
+                { "condition": "entity:mood == \"happy\" and world:gate_open", "text": "fixture.dialogue.again" },
+                { "key": "always", "expected_value": 1, "text": "fixture.dialogue.first", "sets": { "world:gate_open": true, "entity:mood": "happy", "quest.stage": 2 } }
+            ]"#).unwrap();
+            npc.id
+        };
+        let next_dialogue = |game: &TestHarness| -> Option<Dialogue> {
+            let entities = game.engine.world.entities.borrow();
+            let npc = entities.iter().find(|e| e.id == npc_id).unwrap();
+            npc.next_dialogue(&game.engine.world)
+        };
+        assert_eq!(next_dialogue(&game).unwrap().text, "fixture.dialogue.first");
+
+        game.interact().assert_dialogue_open(true);
+        game.wait(1.0).choose("ok").assert_dialogue_open(false);
+
+        let storage = &game.engine.context.storage;
+        assert_eq!(storage.get(&StorageKey::in_world(900_210, "gate_open")), Some(StorageValue::Bool(true)));
+        assert_eq!(storage.get(&StorageKey::of_entity(npc_id, "mood")), Some(StorageValue::from("happy")));
+        assert_eq!(storage.get("quest.stage"), Some(StorageValue::Int(2)));
+        assert_eq!(next_dialogue(&game).unwrap().text, "fixture.dialogue.again");
+    }
+
+    #[test]
+    fn pushing_a_boulder_on_the_plate_opens_the_gate() {
+        let fixture = WorldFixture::new(900_220)
+            .biomes(&["1111111111"; 10])
+            .entities(&[
+                "",
+                "",
+                "",
+                "",
+                "..b.p",
+                "",
+                ".g",
+            ])
+            .legend('b', SPECIES_FIXTURE_PUSHABLE)
+            .legend('p', SPECIES_FIXTURE_PLATE)
+            .legend('g', SPECIES_FIXTURE_GATE)
+            .species(fixture_species(SPECIES_FIXTURE_PUSHABLE, EntityType::PushableObject, false))
+            .species(Species { lock_type: LockType::Yellow, ..fixture_species(SPECIES_FIXTURE_PLATE, EntityType::PressurePlate, false) })
+            .species(Species { lock_type: LockType::Yellow, ..fixture_species(SPECIES_FIXTURE_GATE, EntityType::Gate, true) });
+
+        let mut game = TestHarness::with_fixture(fixture, 1, 3);
+
+        game.walk(Direction::Down, 3).assert_hero_at(1, 4);
+        game.walk(Direction::Up, 1)
+            .walk(Direction::Right, 2)
+            .assert_hero_at(3, 3)
+            .assert_storage(LockType::Yellow.pressure_plate(), Some(StorageValue::Int(1)));
+        game.walk(Direction::Left, 2)
+            .walk(Direction::Down, 3)
+            .assert_hero_at(1, 6);
+    }
+
+    #[test]
+    fn npc_gives_a_reward_once_then_moves_on_to_the_next_dialogue() {
+        let fixture = WorldFixture::new(900_230)
+            .biomes(&["1111111111"; 8])
+            .entities(&["", "", "", "", "", "..n"])
+            .legend('n', SPECIES_FIXTURE_NPC)
+            .species(fixture_species(SPECIES_FIXTURE_NPC, EntityType::Npc, true))
+            .species(fixture_species(SPECIES_FIXTURE_REWARD, EntityType::PickableObject, false))
+            .string("fixture.dialogue.gift", "Take this")
+            .string("fixture.dialogue.thanks", "Use it well");
+
+        let mut game = TestHarness::with_fixture(fixture, 2, 3);
+        game.engine.world.entities.borrow_mut().iter_mut()
+            .filter(|e| e.species_id == SPECIES_FIXTURE_NPC)
+            .for_each(|npc| {
+                npc.dialogues = serde_json::from_value(serde_json::json!([
+                    { "condition": "dialogue.answer.fixture.dialogue.gift == 1", "text": "fixture.dialogue.thanks" },
+                    { "key": "always", "expected_value": 1, "text": "fixture.dialogue.gift", "reward": SPECIES_FIXTURE_REWARD }
+                ])).unwrap();
+            });
+
+        game.interact()
+            .assert_dialogue_open(true)
+            .wait(1.0)
+            .choose("ok")
+            .assert_dialogue_open(false)
+            .assert_inventory_count(SPECIES_FIXTURE_REWARD, 1)
+            .assert_storage("dialogue.reward.fixture.dialogue.gift", Some(StorageValue::Int(1)));
+
+        game.wait(1.0).interact().assert_dialogue_open(true);
+        assert_eq!(game.engine.dialogue_menu.dialogue.text, "fixture.dialogue.thanks");
+        game.wait(1.0)
+            .choose("ok")
+            .assert_dialogue_open(false)
+            .assert_inventory_count(SPECIES_FIXTURE_REWARD, 1);
+    }
+}
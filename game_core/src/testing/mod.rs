@@ -0,0 +1,2 @@
+pub mod fixtures;
+pub mod harness;
\ No newline at end of file
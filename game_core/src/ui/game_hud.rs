@@ -1,27 +1,79 @@
-use crate::game_engine::engine::GameEngine;
+use crate::{constants::{SPRITE_SHEET_HUD, WORLD_ID_NONE}, entities::known_species::SPECIES_HERO, game_engine::engine::GameEngine, text, texture, utils::{rect::IntRect, vector::Vector2d}};
 
-use super::{components::{NonColor, COLOR_BLACK_70, COLOR_TRANSPARENT}, layouts::{AnchorPoint, Layout}};
+use super::{components::{empty_view, NonColor, Spacing, Typography, View, COLOR_BLACK_50, COLOR_BLACK_70, COLOR_TRANSPARENT}, layouts::{AnchorPoint, Layout}};
+
+const LOG_OVERLAY_ENTRIES: usize = 8;
 
 impl GameEngine {
     pub fn hud_ui(&self, width: i32, height: i32) -> Layout {
+        let strings = &self.context.strings;
+
         Layout::new(
             width, 
             height, 
             self.hud_background_color(),
             vec![
                 (AnchorPoint::TopRight, self.toast.regular_toast_ui()),
-                (AnchorPoint::TopLeft, self.toast.important_toast_ui()),
-                (AnchorPoint::BottomCenter, self.menu.ui(&self.camera_viewport)),
-                (AnchorPoint::BottomCenter, self.entity_options_menu.ui()),
-                (AnchorPoint::BottomCenter, self.dialogue_menu.ui()),
-                (AnchorPoint::BottomCenter, self.confirmation_dialog.ui()),
+                (AnchorPoint::TopLeft, self.top_left_ui()),
+                (AnchorPoint::BottomLeft, self.log_overlay_ui()),
+                (AnchorPoint::BottomCenter, self.menu.ui(&self.camera_viewport, &self.context)),
+                (AnchorPoint::BottomCenter, self.entity_options_menu.ui(strings)),
+                (AnchorPoint::BottomCenter, self.dialogue_menu.ui(strings)),
+                (AnchorPoint::Center, self.slot_picker.ui(strings)),
+                (AnchorPoint::BottomCenter, self.confirmation_dialog.ui(strings)),
                 (AnchorPoint::BottomCenter, self.long_text_display.ui()),
-                (AnchorPoint::Center, self.death_screen.ui()),
+                (AnchorPoint::Center, self.death_screen.ui(strings)),
                 (AnchorPoint::Center, self.loading_screen.ui())
             ]
         )
     }
     
+    fn top_left_ui(&self) -> View {
+        View::VStack {
+            spacing: Spacing::SM,
+            children: vec![
+                self.hearts_ui(),
+                self.toast.important_toast_ui()
+            ]
+        }
+    }
+
+    fn hearts_ui(&self) -> View {
+        if self.creative_mode || self.slot_picker.is_open() || self.world.id == WORLD_ID_NONE {
+            return empty_view()
+        }
+        let hp = self.world.cached_hero_props.hp;
+        let max_hp = self.context.species.get(SPECIES_HERO).hp;
+
+        View::HStack {
+            spacing: Spacing::XS,
+            children: (0..max_hp).map(|index| {
+                let x = if index < hp { 0 } else { 1 };
+                texture!(SPRITE_SHEET_HUD, IntRect::new(x, 0, 1, 1), Vector2d::new(1.0, 1.0))
+            }).collect()
+        }
+    }
+
+    fn log_overlay_ui(&self) -> View {
+        if !self.creative_mode {
+            return empty_view()
+        }
+        let entries = self.context.logger.recent_entries(LOG_OVERLAY_ENTRIES);
+        if entries.is_empty() {
+            return empty_view()
+        }
+        View::ZStack {
+            spacing: Spacing::SM,
+            background_color: COLOR_BLACK_50,
+            children: vec![
+                View::VStack {
+                    spacing: Spacing::XS,
+                    children: entries.iter().map(|entry| text!(Typography::Regular, entry.to_string())).collect()
+                }
+            ]
+        }
+    }
+    
     fn hud_background_color(&self) -> NonColor {
         let progress = self.loading_screen.progress();
         if progress > 0.0 && progress < 1.0 {
@@ -29,7 +81,7 @@ impl GameEngine {
             let alpha_int = (alpha * 255.0) as u8;
             return (0, 0, 0, alpha_int)
         }
-        if self.death_screen.is_open {
+        if self.death_screen.is_open() {
             return COLOR_BLACK_70
         }
         COLOR_TRANSPARENT
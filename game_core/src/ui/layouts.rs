@@ -7,6 +7,7 @@ pub enum AnchorPoint {
     Center,
     TopLeft,
     TopRight,
+    BottomLeft,
     BottomCenter,
 }
 
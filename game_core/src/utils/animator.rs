@@ -7,6 +7,12 @@ pub struct Animator {
     pub is_active: bool,
 }
 
+impl Default for Animator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Animator {
     pub fn new() -> Self {
         Animator {
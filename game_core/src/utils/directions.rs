@@ -14,6 +14,18 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// Inverse of `direction as i64`, for directions kept in storage.
+    pub fn from_int(value: i64) -> Self {
+        match value {
+            0 => Direction::Up,
+            1 => Direction::Down,
+            2 => Direction::Right,
+            3 => Direction::Left,
+            5 => Direction::Still,
+            _ => Direction::Unknown
+        }
+    }
+
     pub fn as_vector(&self) -> Vector2d {
         let (col, row) = self.as_col_row_offset();
         Vector2d::new(col as f32, row as f32)
@@ -0,0 +1,119 @@
+use std::{ffi::OsString, fs::{self, File}, io::Write, path::{Path, PathBuf}};
+
+use crate::{constants::SAVE_BACKUPS_COUNT, game_engine::errors::{GameError, GameResult}};
+
+/// Writes to a temporary file first and then renames it into place, so that a crash
+/// mid-write never leaves a truncated file behind.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> GameResult<()> {
+    let temp_path = path_with_suffix(path, "tmp");
+    {
+        let mut file = File::create(&temp_path).map_err(|e| GameError::io(&temp_path, e))?;
+        file.write_all(contents).map_err(|e| GameError::io(&temp_path, e))?;
+        file.sync_all().map_err(|e| GameError::io(&temp_path, e))?;
+    }
+    fs::rename(&temp_path, path).map_err(|e| GameError::io(path, e))
+}
+
+/// Copies the current contents to the rolling backups, see `backup_path`.
+/// Meant for explicit saves, writes alone never touch the backups.
+pub fn back_up(path: &Path) -> GameResult<()> {
+    if !path.exists() {
+        return Ok(())
+    }
+    for number in (1..SAVE_BACKUPS_COUNT).rev() {
+        let from = backup_path(path, number);
+        if from.exists() {
+            let to = backup_path(path, number + 1);
+            fs::rename(&from, &to).map_err(|e| GameError::io(&to, e))?;
+        }
+    }
+    if SAVE_BACKUPS_COUNT > 0 {
+        let backup = backup_path(path, 1);
+        fs::copy(path, &backup).map_err(|e| GameError::io(&backup, e))?;
+    }
+    Ok(())
+}
+
+/// Backup number 1 is the most recent one.
+pub fn backup_path(path: &Path, number: usize) -> PathBuf {
+    path_with_suffix(path, &format!("bak{}", number))
+}
+
+/// Most recent backup that is still valid json, if any.
+pub fn latest_valid_backup(path: &Path) -> Option<PathBuf> {
+    (1..=SAVE_BACKUPS_COUNT)
+        .map(|number| backup_path(path, number))
+        .find(|backup| {
+            fs::read_to_string(backup)
+                .ok()
+                .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+                .is_some()
+        })
+}
+
+/// Replaces the file with its latest valid backup, the damaged file is kept next to it for inspection.
+pub fn restore_latest_backup(path: &Path) -> GameResult<PathBuf> {
+    let backup = latest_valid_backup(path).ok_or_else(|| GameError::FileNotFound(backup_path(path, 1)))?;
+    let contents = fs::read(&backup).map_err(|e| GameError::io(&backup, e))?;
+
+    if path.exists() && !damaged_path(path).exists() {
+        set_aside_damaged(path)?;
+    }
+    let temp_path = path_with_suffix(path, "tmp");
+    fs::write(&temp_path, contents).map_err(|e| GameError::io(&temp_path, e))?;
+    fs::rename(&temp_path, path).map_err(|e| GameError::io(path, e))?;
+    Ok(backup)
+}
+
+/// Moves a file that could not be parsed out of the way, so that a new one can be saved in its place.
+pub fn set_aside_damaged(path: &Path) -> GameResult<PathBuf> {
+    let damaged_path = damaged_path(path);
+    fs::rename(path, &damaged_path).map_err(|e| GameError::io(&damaged_path, e))?;
+    Ok(damaged_path)
+}
+
+fn damaged_path(path: &Path) -> PathBuf {
+    path_with_suffix(path, "damaged")
+}
+
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::constants::SAVE_BACKUPS_COUNT;
+
+    use super::{back_up, backup_path, latest_valid_backup, restore_latest_backup, write_atomically};
+
+    #[test]
+    fn keeps_rolling_backups_and_restores_the_latest_valid_one() {
+        let folder = env::temp_dir().join(format!("nokemon-files-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("save.json");
+
+        write_atomically(&path, b"{}").unwrap();
+        write_atomically(&path, b"{}").unwrap();
+        assert!(!backup_path(&path, 1).exists());
+
+        for value in 0..SAVE_BACKUPS_COUNT + 2 {
+            write_atomically(&path, format!("{{\"value\": {}}}", value).as_bytes()).unwrap();
+            back_up(&path).unwrap();
+        }
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), format!("{{\"value\": {}}}", SAVE_BACKUPS_COUNT + 1));
+        assert!(!backup_path(&path, SAVE_BACKUPS_COUNT + 1).exists());
+
+        fs::write(&path, "{ \"value\": ").unwrap();
+        fs::write(backup_path(&path, 1), "{ \"val").unwrap();
+        assert_eq!(latest_valid_backup(&path), Some(backup_path(&path, 2)));
+
+        restore_latest_backup(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), format!("{{\"value\": {}}}", SAVE_BACKUPS_COUNT));
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -1,17 +1,51 @@
-use lazy_static::lazy_static;
-
 use std::{sync::atomic::{AtomicU32, Ordering}, time::{SystemTime, UNIX_EPOCH}};
 
-lazy_static! {
-    pub static ref NEXT_ID: AtomicU32 = AtomicU32::new(
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_secs()
-            .saturating_sub(1720000000) as u32
-    );
+const SEEDED_IDS_BASE: u32 = 0x8000_0000;
+
+pub struct IdGenerator {
+    next_id: AtomicU32,
 }
 
-pub fn get_next_id() -> u32 {
-    NEXT_ID.fetch_add(1, Ordering::Relaxed)
-}
\ No newline at end of file
+impl Default for IdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator {
+    pub fn new() -> Self {
+        Self::starting_at(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs()
+                .saturating_sub(1720000000) as u32
+        )
+    }
+
+    // Seeded ids live in the upper half of the range, so they don't clash
+    // with the time-based ids already stored in world files.
+    pub fn seeded(seed: u64) -> Self {
+        Self::starting_at(SEEDED_IDS_BASE + (seed % 0x0100_0000) as u32)
+    }
+
+    fn starting_at(first_id: u32) -> Self {
+        Self {
+            next_id: AtomicU32::new(first_id)
+        }
+    }
+
+    pub fn next_id(&self) -> u32 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The id `next_id` returns next, without using it.
+    pub fn peek(&self) -> u32 {
+        self.next_id.load(Ordering::Relaxed)
+    }
+
+    /// Replays continue from the first id of the recording, so that entities get the same ids as back then.
+    pub fn restart_at(&self, first_id: u32) {
+        self.next_id.store(first_id, Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,244 @@
+use std::{collections::{HashMap, VecDeque}, fmt, sync::{Arc, RwLock}};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    Engine,
+    World,
+    Entities,
+    Storage,
+    Inventory,
+    Lang,
+    Replay,
+    Ffi,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub level: LogLevel,
+    pub subsystem: Subsystem,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggerConfig {
+    pub level: LogLevel,
+    pub subsystem_levels: HashMap<Subsystem, LogLevel>,
+    pub prints_to_console: bool,
+    pub capacity: usize,
+}
+
+/// Log of a single engine, clones share filters and entries with the original,
+/// so that background threads of the engine can write to it.
+#[derive(Clone, Default)]
+pub struct Logger {
+    state: Arc<RwLock<LoggerState>>,
+}
+
+#[derive(Default)]
+struct LoggerState {
+    config: LoggerConfig,
+    entries: VecDeque<LogEntry>,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::Info,
+            subsystem_levels: HashMap::new(),
+            prints_to_console: false,
+            capacity: 200,
+        }
+    }
+}
+
+impl LoggerConfig {
+    pub fn level_for(&self, subsystem: Subsystem) -> LogLevel {
+        self.subsystem_levels.get(&subsystem).copied().unwrap_or(self.level)
+    }
+}
+
+impl Logger {
+    pub fn new(config: LoggerConfig) -> Self {
+        let state = LoggerState { entries: VecDeque::with_capacity(config.capacity), config };
+        Self { state: Arc::new(RwLock::new(state)) }
+    }
+
+    pub fn configure(&self, config: LoggerConfig) {
+        let mut state = self.state.write().unwrap();
+        state.config = config;
+        let capacity = state.config.capacity;
+        while state.entries.len() > capacity {
+            state.entries.pop_front();
+        }
+    }
+
+    /// Checked by the `log_*` macros before formatting the message.
+    pub fn is_enabled(&self, level: LogLevel, subsystem: Subsystem) -> bool {
+        level >= self.state.read().unwrap().config.level_for(subsystem)
+    }
+
+    pub fn log(&self, level: LogLevel, subsystem: Subsystem, message: String) {
+        if !self.is_enabled(level, subsystem) {
+            return
+        }
+        let entry = LogEntry { level, subsystem, message };
+        let mut state = self.state.write().unwrap();
+
+        if state.config.prints_to_console {
+            if entry.level >= LogLevel::Warning {
+                eprintln!("{}", entry);
+            } else {
+                println!("{}", entry);
+            }
+        }
+        while state.entries.len() >= state.config.capacity.max(1) {
+            state.entries.pop_front();
+        }
+        state.entries.push_back(entry);
+    }
+
+    pub fn recent_entries(&self, count: usize) -> Vec<LogEntry> {
+        let state = self.state.read().unwrap();
+        let skip = state.entries.len().saturating_sub(count);
+        state.entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}] [{:?}] {}", self.level, self.subsystem, self.message)
+    }
+}
+
+impl Subsystem {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "engine" => Some(Subsystem::Engine),
+            "world" => Some(Subsystem::World),
+            "entities" => Some(Subsystem::Entities),
+            "storage" => Some(Subsystem::Storage),
+            "inventory" => Some(Subsystem::Inventory),
+            "lang" => Some(Subsystem::Lang),
+            "replay" => Some(Subsystem::Replay),
+            "ffi" => Some(Subsystem::Ffi),
+            _ => None
+        }
+    }
+}
+
+impl LogLevel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warning" => Some(LogLevel::Warning),
+            "error" => Some(LogLevel::Error),
+            "off" => Some(LogLevel::Off),
+            _ => None
+        }
+    }
+}
+
+/// Messages are only formatted if the logger keeps entries of the given level and subsystem.
+#[macro_export]
+macro_rules! log_at {
+    ($logger:expr, $level:expr, $subsystem:expr, $($arg:tt)*) => {{
+        let logger: &$crate::utils::logger::Logger = &$logger;
+        if logger.is_enabled($level, $subsystem) {
+            logger.log($level, $subsystem, format!($($arg)*));
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($logger:expr, $subsystem:expr, $($arg:tt)*) => {
+        $crate::log_at!($logger, $crate::utils::logger::LogLevel::Debug, $subsystem, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($logger:expr, $subsystem:expr, $($arg:tt)*) => {
+        $crate::log_at!($logger, $crate::utils::logger::LogLevel::Info, $subsystem, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! log_warning {
+    ($logger:expr, $subsystem:expr, $($arg:tt)*) => {
+        $crate::log_at!($logger, $crate::utils::logger::LogLevel::Warning, $subsystem, $($arg)*)
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($logger:expr, $subsystem:expr, $($arg:tt)*) => {
+        $crate::log_at!($logger, $crate::utils::logger::LogLevel::Error, $subsystem, $($arg)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use super::{LogLevel, Logger, LoggerConfig, Subsystem};
+
+    struct Unformattable;
+
+    impl fmt::Display for Unformattable {
+        fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+            panic!("Filtered out messages should not be formatted")
+        }
+    }
+
+    fn messages(logger: &Logger) -> Vec<String> {
+        logger.recent_entries(usize::MAX).into_iter().map(|e| e.message).collect()
+    }
+
+    #[test]
+    fn keeps_only_the_most_recent_entries() {
+        let logger = Logger::new(LoggerConfig { capacity: 2, ..Default::default() });
+        log_info!(logger, Subsystem::World, "first");
+        log_info!(logger, Subsystem::World, "second");
+        log_info!(logger, Subsystem::World, "third");
+
+        assert_eq!(messages(&logger), vec!["second", "third"]);
+    }
+
+    #[test]
+    fn filters_by_subsystem_level() {
+        let mut config = LoggerConfig { level: LogLevel::Warning, ..Default::default() };
+        config.subsystem_levels.insert(Subsystem::Storage, LogLevel::Debug);
+        let logger = Logger::new(config);
+
+        log_info!(logger, Subsystem::World, "hidden {}", Unformattable);
+        log_error!(logger, Subsystem::World, "shown");
+        log_debug!(logger, Subsystem::Storage, "also shown");
+
+        assert_eq!(messages(&logger), vec!["shown", "also shown"]);
+    }
+
+    #[test]
+    fn each_logger_keeps_its_own_entries_and_filters() {
+        let quiet = Logger::new(LoggerConfig { level: LogLevel::Error, ..Default::default() });
+        let verbose = Logger::new(LoggerConfig { level: LogLevel::Debug, ..Default::default() });
+        let shared = verbose.clone();
+
+        log_debug!(quiet, Subsystem::Engine, "quiet");
+        log_debug!(shared, Subsystem::Engine, "verbose");
+
+        assert!(messages(&quiet).is_empty());
+        assert_eq!(messages(&verbose), vec!["verbose"]);
+    }
+}
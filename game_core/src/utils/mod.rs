@@ -1,6 +1,8 @@
 pub mod animator;
 pub mod directions;
+pub mod files;
 pub mod ids;
+pub mod logger;
 pub mod rect;
 pub mod timed_content_provider;
 pub mod vector;
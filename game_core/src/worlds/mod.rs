@@ -1,2 +1,5 @@
+pub mod world_binary;
+pub mod world_cache;
+pub mod world_overlay;
 pub mod world_serde;
 pub mod world_setup;
\ No newline at end of file
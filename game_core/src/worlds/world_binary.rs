@@ -0,0 +1,164 @@
+use std::{fs::{self, File, Metadata}, io::Read, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{game_engine::{errors::{GameError, GameResult}, migrations::{migrate, DataKind}}, utils::files::write_atomically};
+
+use super::world_serde::{read_json_world_data, WorldData};
+
+const MAGIC: &[u8; 4] = b"NKWB";
+const HEADER_SIZE: usize = 16;
+
+/// Binary version of a world file, next to the json one.
+pub fn binary_world_path(json_path: &Path) -> PathBuf {
+    json_path.with_extension("bin")
+}
+
+/// Json stays the authoring format, the binary file is only used while it was made from the current json.
+/// Only the metadata of the json is checked, so loading never has to read it.
+/// Bundles can ship without the json, then the binary file is all there is.
+pub fn is_binary_up_to_date(json_path: &Path) -> bool {
+    let Some(stored_stamp) = source_stamp_of_binary(&binary_world_path(json_path)) else {
+        return false
+    };
+    match fs::metadata(json_path) {
+        Ok(metadata) => source_stamp(&metadata) == Some(stored_stamp),
+        Err(_) => true
+    }
+}
+
+/// Size and modification time of the json, hashed together.
+fn source_stamp(metadata: &Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64;
+    let mut bytes = metadata.len().to_le_bytes().to_vec();
+    bytes.extend_from_slice(&modified.to_le_bytes());
+    Some(content_hash(&bytes))
+}
+
+fn source_stamp_of_binary(path: &Path) -> Option<u64> {
+    let mut header = [0; HEADER_SIZE];
+    File::open(path).and_then(|mut file| file.read_exact(&mut header)).ok()?;
+    if &header[..4] != MAGIC {
+        return None
+    }
+    Some(u64::from_le_bytes(header[8..16].try_into().ok()?))
+}
+
+/// FNV-1a, unlike `DefaultHasher` it gives the same result on every platform and compiler version.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Magic bytes, version as little endian u32, stamp of the json it was made from as little endian u64,
+/// then the world as MessagePack.
+pub fn encode_world<T: Serialize>(path: &Path, world: &T, source_stamp: u64) -> GameResult<Vec<u8>> {
+    let payload = rmp_serde::to_vec_named(world).map_err(|e| GameError::invalid_data(path, e))?;
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&DataKind::World.latest_version().to_le_bytes());
+    bytes.extend_from_slice(&source_stamp.to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+pub fn decode_world(path: &Path, bytes: &[u8]) -> GameResult<WorldData> {
+    if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+        return Err(GameError::invalid_data(path, "Not a binary world file"))
+    }
+    let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    let payload = &bytes[HEADER_SIZE..];
+
+    if version == DataKind::World.latest_version() {
+        return rmp_serde::from_slice(payload).map_err(|e| GameError::invalid_data(path, e))
+    }
+    let value: Value = rmp_serde::from_slice(payload).map_err(|e| GameError::invalid_data(path, e))?;
+    let value = migrate(DataKind::World, value).map_err(|e| GameError::invalid_data(path, e))?;
+    serde_json::from_value(value).map_err(|e| GameError::invalid_data(path, e))
+}
+
+pub fn read_binary_world_data(path: &Path) -> GameResult<WorldData> {
+    let bytes = fs::read(path).map_err(|e| GameError::io(path, e))?;
+    decode_world(path, &bytes)
+}
+
+/// `json_path` is the json the world was read from or just written to.
+pub fn write_binary_world<T: Serialize>(path: &Path, world: &T, json_path: &Path) -> GameResult<()> {
+    let metadata = fs::metadata(json_path).map_err(|e| GameError::io(json_path, e))?;
+    let stamp = source_stamp(&metadata).unwrap_or_default();
+    write_atomically(path, &encode_world(path, world, stamp)?)
+}
+
+/// Converts a world from json to binary or the other way around, depending on the extension,
+/// returns the path of the file that was written.
+pub fn convert_world_file(path: &Path) -> GameResult<PathBuf> {
+    if path.extension().is_some_and(|extension| extension == "bin") {
+        let data = read_binary_world_data(path)?;
+        let destination = path.with_extension("json");
+        let serialized = serde_json::to_string_pretty(&data).map_err(|e| GameError::invalid_data(&destination, e))?;
+        write_atomically(&destination, serialized.as_bytes())?;
+        Ok(destination)
+    } else {
+        let data = read_json_world_data(path)?.ok_or_else(|| GameError::FileNotFound(path.to_path_buf()))?;
+        let destination = binary_world_path(path);
+        write_binary_world(&destination, &data, path)?;
+        Ok(destination)
+    }
+}
+
+/// Converts every json world in `folder` to binary, returns the paths of the binary files.
+pub fn convert_worlds_folder(folder: &Path) -> GameResult<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(folder)
+        .map_err(|e| GameError::io(folder, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| DataKind::of_file(path) == Some(DataKind::World))
+        .collect();
+    paths.sort();
+    paths.iter().map(|path| convert_world_file(path)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::{config::Config, worlds::world_serde::{read_json_world_data, read_world_data}};
+
+    use super::{binary_world_path, convert_world_file, is_binary_up_to_date};
+
+    #[test]
+    fn converts_worlds_both_ways_without_losing_data() {
+        let folder = env::temp_dir().join(format!("nokemon-binary-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let json_path = folder.join("1002.json");
+        fs::copy(Config::for_tests().world_path(1002), &json_path).unwrap();
+        let original = serde_json::to_value(read_json_world_data(&json_path).unwrap().unwrap()).unwrap();
+
+        let binary_path = convert_world_file(&json_path).unwrap();
+        assert_eq!(binary_path, binary_world_path(&json_path));
+        assert!(fs::metadata(&binary_path).unwrap().len() < fs::metadata(&json_path).unwrap().len());
+
+        fs::remove_file(&json_path).unwrap();
+        let from_binary = serde_json::to_value(read_world_data(&json_path).unwrap().unwrap()).unwrap();
+        assert_eq!(from_binary, original);
+
+        assert_eq!(convert_world_file(&binary_path).unwrap(), json_path);
+        let round_trip = serde_json::to_value(read_json_world_data(&json_path).unwrap().unwrap()).unwrap();
+        assert_eq!(round_trip, original);
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn binary_worlds_go_stale_when_the_json_changes() {
+        let folder = env::temp_dir().join(format!("nokemon-binary-stale-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let json_path = folder.join("1002.json");
+        fs::copy(Config::for_tests().world_path(1002), &json_path).unwrap();
+        convert_world_file(&json_path).unwrap();
+        assert!(is_binary_up_to_date(&json_path));
+
+        let json = fs::read_to_string(&json_path).unwrap();
+        fs::write(&json_path, json.replacen("\"creep_spawn_enabled\": false", "\"creep_spawn_enabled\": true", 1)).unwrap();
+        assert!(!is_binary_up_to_date(&json_path));
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
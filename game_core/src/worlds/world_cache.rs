@@ -0,0 +1,169 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, path::PathBuf, sync::{Arc, Mutex}, thread};
+
+use crate::{constants::WORLD_CACHE_CAPACITY, log_debug, log_warning, utils::logger::{Logger, Subsystem}};
+
+use super::world_serde::{read_world_data, WorldData};
+
+/// Keeps the parsed data of the most recently used worlds, so that going back and forth
+/// between worlds doesn't read and deserialize the same files again.
+/// Worlds are still rebuilt from the data on every visit, so they reset just like when loaded from disk.
+pub struct WorldCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+struct CacheState {
+    capacity: usize,
+    entries: VecDeque<(u32, Arc<WorldData>)>,
+    preloading: HashSet<u32>,
+    unavailable: HashSet<u32>,
+    versions: HashMap<u32, u32>,
+}
+
+impl Default for WorldCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorldCache {
+    pub fn new() -> Self {
+        Self::with_capacity(WORLD_CACHE_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let state = CacheState {
+            capacity,
+            entries: VecDeque::new(),
+            preloading: HashSet::new(),
+            unavailable: HashSet::new(),
+            versions: HashMap::new(),
+        };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    pub fn get(&self, id: u32) -> Option<Arc<WorldData>> {
+        self.state.lock().unwrap().get(id)
+    }
+
+    pub fn insert(&self, id: u32, data: WorldData) -> Arc<WorldData> {
+        let data = Arc::new(data);
+        self.state.lock().unwrap().insert(id, data.clone());
+        data
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.state.lock().unwrap().entries.iter().any(|(cached_id, _)| *cached_id == id)
+    }
+
+    /// Drops the cached data, including any preload still running, call after writing the world file.
+    pub fn invalidate(&self, id: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.retain(|(cached_id, _)| *cached_id != id);
+        state.unavailable.remove(&id);
+        *state.versions.entry(id).or_default() += 1;
+    }
+
+    pub fn invalidate_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        state.entries.clear();
+        state.unavailable.clear();
+        state.preloading.iter().for_each(|id| _ = state.versions.entry(*id).or_default());
+        state.versions.values_mut().for_each(|version| *version += 1);
+    }
+
+    /// Reads and parses the world file on a background thread, unless it's already cached or being read.
+    /// Missing or broken files are not retried until invalidated, loading them reports the error as usual.
+    pub fn preload(&self, id: u32, path: PathBuf, logger: &Logger) {
+        let version = {
+            let mut state = self.state.lock().unwrap();
+            if state.preloading.contains(&id) || state.unavailable.contains(&id) || state.entries.iter().any(|(cached_id, _)| *cached_id == id) {
+                return
+            }
+            state.preloading.insert(id);
+            state.versions.get(&id).copied().unwrap_or_default()
+        };
+        let (state, logger) = (self.state.clone(), logger.clone());
+
+        thread::spawn(move || {
+            let result = read_world_data(&path);
+            let mut state = state.lock().unwrap();
+            state.preloading.remove(&id);
+
+            if state.versions.get(&id).copied().unwrap_or_default() != version {
+                return
+            }
+            match result {
+                Ok(Some(data)) => {
+                    log_debug!(logger, Subsystem::World, "Preloaded world {}", id);
+                    state.insert(id, Arc::new(data));
+                }
+                Ok(None) => {
+                    state.unavailable.insert(id);
+                }
+                Err(error) => {
+                    log_warning!(logger, Subsystem::World, "Failed to preload world {}: {}", id, error);
+                    state.unavailable.insert(id);
+                }
+            }
+        });
+    }
+
+    pub fn is_preloading(&self) -> bool {
+        !self.state.lock().unwrap().preloading.is_empty()
+    }
+}
+
+impl CacheState {
+    fn get(&mut self, id: u32) -> Option<Arc<WorldData>> {
+        let position = self.entries.iter().position(|(cached_id, _)| *cached_id == id)?;
+        let entry = self.entries.remove(position)?;
+        let data = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(data)
+    }
+
+    fn insert(&mut self, id: u32, data: Arc<WorldData>) {
+        self.entries.retain(|(cached_id, _)| *cached_id != id);
+        self.entries.push_front((id, data));
+        self.entries.truncate(self.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::{config::Config, utils::logger::Logger};
+
+    use super::WorldCache;
+
+    fn wait_for_preloads(cache: &WorldCache) {
+        while cache.is_preloading() {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn keeps_the_most_recently_used_worlds() {
+        let config = Config::for_tests();
+        let cache = WorldCache::with_capacity(2);
+
+        for id in [1001, 1002, 1003] {
+            cache.preload(id, config.world_path(id), &Logger::default());
+            wait_for_preloads(&cache);
+        }
+        assert!(!cache.contains(1001));
+        assert!(cache.contains(1002));
+        assert!(cache.contains(1003));
+
+        cache.get(1002);
+        cache.preload(1001, config.world_path(1001), &Logger::default());
+        wait_for_preloads(&cache);
+        assert!(cache.contains(1002));
+        assert!(!cache.contains(1003));
+
+        cache.invalidate(1002);
+        assert!(!cache.contains(1002));
+    }
+}
@@ -0,0 +1,326 @@
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fs::{self, File}, io::{BufReader, ErrorKind}, path::{Path, PathBuf}, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{utils::files::write_atomically, constants::HERO_ENTITY_ID, entities::species::EntityType, game_engine::{entity::{Entity, EntityId}, errors::{GameError, GameResult}, locks::LockType, migrations::{from_reader, to_versioned_json, DataKind}, world::World}};
+
+/// Changes made while playing, stored per save on top of the level files,
+/// so that the levels themselves only ever change from the map editor.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorldOverlay {
+    #[serde(default)]
+    pub removed: BTreeSet<EntityId>,
+
+    #[serde(default)]
+    pub added: Vec<Entity>,
+
+    #[serde(default)]
+    pub moved: BTreeMap<EntityId, (i32, i32)>,
+
+    #[serde(default)]
+    pub locks: BTreeMap<EntityId, LockType>,
+}
+
+/// What a world looked like when loaded from the level file, used to tell what changed since.
+#[derive(Debug, Default)]
+pub struct WorldChanges {
+    is_tracking: bool,
+    level_entities: HashMap<EntityId, (i32, i32, LockType)>,
+    removed: BTreeSet<EntityId>,
+    added: BTreeSet<EntityId>,
+}
+
+/// Overlays of the current save, one file per world.
+/// Disabled in creative mode, where worlds are saved as a whole instead.
+pub struct WorldOverlays {
+    path: RwLock<Option<PathBuf>>,
+    overlays: RwLock<HashMap<u32, WorldOverlay>>,
+    is_enabled: RwLock<bool>,
+}
+
+impl WorldOverlays {
+    pub fn load(path: &Path) -> Self {
+        Self::with_path(Some(path.to_path_buf()))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::with_path(None)
+    }
+
+    fn with_path(path: Option<PathBuf>) -> Self {
+        Self {
+            path: RwLock::new(path),
+            overlays: RwLock::new(HashMap::new()),
+            is_enabled: RwLock::new(true),
+        }
+    }
+
+    /// Forgets the overlays read so far and uses the ones stored at `path` from now on.
+    pub fn switch_to(&self, path: &Path) {
+        *self.path.write().unwrap() = Some(path.to_path_buf());
+        self.overlays.write().unwrap().clear();
+    }
+
+    /// Forgets the overlays read so far, so that they are read again from disk.
+    pub fn reload(&self) {
+        self.overlays.write().unwrap().clear();
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        *self.is_enabled.write().unwrap() = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self.is_enabled.read().unwrap()
+    }
+
+    pub fn get(&self, world_id: u32) -> GameResult<WorldOverlay> {
+        if !self.is_enabled() {
+            return Ok(WorldOverlay::default())
+        }
+        if let Some(overlay) = self.overlays.read().unwrap().get(&world_id) {
+            return Ok(overlay.clone())
+        }
+        let overlay = match self.file_path(world_id) {
+            Some(path) => read_overlay(&path)?,
+            None => WorldOverlay::default()
+        };
+        self.overlays.write().unwrap().insert(world_id, overlay.clone());
+        Ok(overlay)
+    }
+
+    /// Worlds that never changed don't get a file.
+    pub fn set(&self, world_id: u32, overlay: WorldOverlay) -> GameResult<()> {
+        if !self.is_enabled() || overlay.is_empty() && self.get(world_id)?.is_empty() {
+            return Ok(())
+        }
+        if let Some(path) = self.file_path(world_id) {
+            write_overlay(&path, &overlay)?;
+        }
+        self.overlays.write().unwrap().insert(world_id, overlay);
+        Ok(())
+    }
+
+    /// Every non empty overlay of the current save, including the ones never read so far.
+    pub fn all(&self) -> GameResult<BTreeMap<u32, WorldOverlay>> {
+        let mut ids: BTreeSet<u32> = self.overlays.read().unwrap().keys().copied().collect();
+        ids.extend(self.stored_world_ids()?);
+
+        let mut overlays = BTreeMap::new();
+        for id in ids {
+            let overlay = self.get(id)?;
+            if !overlay.is_empty() {
+                overlays.insert(id, overlay);
+            }
+        }
+        Ok(overlays)
+    }
+
+    /// Replaces every overlay of the current save, even while disabled.
+    pub fn replace_all(&self, overlays: BTreeMap<u32, WorldOverlay>) -> GameResult<()> {
+        for id in self.stored_world_ids()? {
+            if let Some(path) = self.file_path(id) {
+                fs::remove_file(&path).map_err(|e| GameError::io(&path, e))?;
+            }
+        }
+        for (id, overlay) in &overlays {
+            if let Some(path) = self.file_path(*id) {
+                write_overlay(&path, overlay)?;
+            }
+        }
+        *self.overlays.write().unwrap() = overlays.into_iter().collect();
+        Ok(())
+    }
+
+    fn stored_world_ids(&self) -> GameResult<Vec<u32>> {
+        let Some(folder) = self.path.read().unwrap().clone() else {
+            return Ok(vec![])
+        };
+        let entries = match fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(GameError::io(&folder, e))
+        };
+        Ok(entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|path| path.file_stem()?.to_str()?.parse().ok())
+            .collect())
+    }
+
+    fn file_path(&self, world_id: u32) -> Option<PathBuf> {
+        self.path.read().unwrap().as_ref().map(|path| path.join(format!("{}.json", world_id)))
+    }
+}
+
+impl WorldOverlay {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.moved.is_empty() && self.locks.is_empty()
+    }
+}
+
+fn read_overlay(path: &Path) -> GameResult<WorldOverlay> {
+    match File::open(path) {
+        Ok(file) => from_reader(DataKind::Overlay, path, BufReader::new(file)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(WorldOverlay::default()),
+        Err(e) => Err(GameError::io(path, e))
+    }
+}
+
+fn write_overlay(path: &Path, overlay: &WorldOverlay) -> GameResult<()> {
+    let serialized = to_versioned_json(DataKind::Overlay, overlay).map_err(|e| GameError::invalid_data(path, e))?;
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).map_err(|e| GameError::io(folder, e))?;
+    }
+    write_atomically(path, serialized.as_bytes())
+}
+
+impl WorldChanges {
+    pub fn tracking(entities: &[Entity]) -> Self {
+        Self {
+            is_tracking: true,
+            level_entities: entities
+                .iter()
+                .filter(|e| e.id != HERO_ENTITY_ID)
+                .map(|e| (e.id, (e.frame.x, e.frame.y, e.lock_type)))
+                .collect(),
+            removed: BTreeSet::new(),
+            added: BTreeSet::new(),
+        }
+    }
+
+    /// Changes that are never saved, for worlds that don't belong to the current save anymore.
+    pub fn untracked() -> Self {
+        Self::default()
+    }
+
+    /// Defeated entities are not recorded, they come back on the next visit like they always did.
+    fn did_remove(&mut self, entity: &Entity) {
+        let was_added = self.added.remove(&entity.id);
+        if !was_added && !entity.is_dying && self.level_entities.contains_key(&entity.id) {
+            self.removed.insert(entity.id);
+        }
+    }
+
+    fn did_add(&mut self, entity: &Entity) {
+        if is_persistent_addition(entity) {
+            self.added.insert(entity.id);
+        }
+    }
+}
+
+/// Npcs and bullets added while playing are spawned creeps and projectiles, they don't outlive a visit.
+fn is_persistent_addition(entity: &Entity) -> bool {
+    !matches!(entity.entity_type, EntityType::Hero | EntityType::Npc | EntityType::Bullet) && !entity.is_dying && entity.remaining_lifespan < 0.0
+}
+
+fn can_be_moved(entity: &Entity) -> bool {
+    matches!(entity.entity_type, EntityType::PushableObject | EntityType::RailObject)
+}
+
+impl World {
+    pub fn apply_overlay(&mut self, overlay: WorldOverlay) {
+        overlay.removed.iter().for_each(|id| self.remove_entity_by_id(*id));
+        {
+            let mut entities = self.entities.borrow_mut();
+            for (id, (x, y)) in &overlay.moved {
+                if let Some(entity) = self.index_for_entity(*id).and_then(|index| entities.get_mut(index)) {
+                    entity.frame.x = *x;
+                    entity.frame.y = *y;
+                    self.spatial_grid.update(*id, &entity.frame);
+                }
+            }
+            for (id, lock_type) in &overlay.locks {
+                if let Some(entity) = self.index_for_entity(*id).and_then(|index| entities.get_mut(index)) {
+                    entity.lock_type = *lock_type;
+                }
+            }
+        }
+        for entity in overlay.added {
+            let (_, id) = self.add_entity(entity);
+            self.changes.added.insert(id);
+        }
+        self.changes.removed.extend(overlay.removed);
+    }
+
+    /// Everything that changed since the world was loaded from the level file, including previous visits.
+    pub fn overlay(&self) -> WorldOverlay {
+        let entities = self.entities.borrow();
+        let changes = &self.changes;
+        let mut overlay = WorldOverlay { removed: changes.removed.clone(), ..Default::default() };
+
+        for entity in entities.iter() {
+            if changes.added.contains(&entity.id) {
+                overlay.added.push(entity.clone());
+            } else if let Some((x, y, lock_type)) = changes.level_entities.get(&entity.id) {
+                if can_be_moved(entity) && (entity.frame.x, entity.frame.y) != (*x, *y) {
+                    overlay.moved.insert(entity.id, (entity.frame.x, entity.frame.y));
+                }
+                if entity.lock_type != *lock_type {
+                    overlay.locks.insert(entity.id, entity.lock_type);
+                }
+            }
+        }
+        overlay
+    }
+
+    pub fn save_overlay(&self) -> GameResult<()> {
+        if self.creative_mode || !self.changes.is_tracking {
+            return Ok(())
+        }
+        self.context.overlays.set(self.id, self.overlay())
+    }
+
+    pub fn record_addition(&mut self, id: EntityId) {
+        let entities = self.entities.borrow();
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get(index)) {
+            self.changes.did_add(entity);
+        }
+    }
+
+    pub fn record_removal(&mut self, id: EntityId) {
+        let entities = self.entities.borrow();
+        if let Some(entity) = self.index_for_entity(id).and_then(|index| entities.get(index)) {
+            self.changes.did_remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{entities::species::EntityType, game_engine::{entity::EntityId, locks::LockType, state_updates::WorldStateUpdate}, testing::harness::TestHarness};
+
+    fn first_of_type(game: &TestHarness, entity_type: EntityType) -> EntityId {
+        game.engine.world.entities.borrow().iter().find(|e| e.entity_type == entity_type).unwrap().id
+    }
+
+    #[test]
+    fn play_mode_changes_survive_leaving_the_world() {
+        let mut game = TestHarness::new();
+        game.teleport(1002, 10, 10);
+
+        let bundle = first_of_type(&game, EntityType::Bundle);
+        let teleporter = first_of_type(&game, EntityType::Teleporter);
+        let pushable = first_of_type(&game, EntityType::PushableObject);
+        game.engine.world.apply_state_updates(vec![
+            WorldStateUpdate::RemoveEntity(bundle),
+            WorldStateUpdate::ChangeLock(teleporter, LockType::Yellow),
+        ]);
+        let pushed_to = {
+            let mut entities = game.engine.world.entities.borrow_mut();
+            let entity = entities.iter_mut().find(|e| e.id == pushable).unwrap();
+            entity.frame.x += 1;
+            (entity.frame.x, entity.frame.y)
+        };
+
+        game.teleport(1003, 10, 10).teleport(1002, 10, 10);
+
+        let world = &game.engine.world;
+        let entities = world.entities.borrow();
+        assert!(world.index_for_entity(bundle).is_none());
+        assert_eq!(entities.iter().find(|e| e.id == teleporter).unwrap().lock_type, LockType::Yellow);
+        let pushable = entities.iter().find(|e| e.id == pushable).unwrap();
+        assert_eq!((pushable.frame.x, pushable.frame.y), pushed_to);
+        assert!(game.engine.context.overlays.get(1002).unwrap().removed.contains(&bundle));
+    }
+}
@@ -1,59 +1,78 @@
-use std::{fs::File, io::{BufReader, Write}};
+use std::{fs::File, io::{BufReader, ErrorKind}, path::Path, sync::Arc};
 
-use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::Error;
-use crate::{config::config, constants::{SPRITE_SHEET_BIOME_TILES, SPRITE_SHEET_CONSTRUCTION_TILES, WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::known_species::SPECIES_HERO, game_engine::{entity::Entity, world::World}, maps::{biome_tiles::{Biome, BiomeTile}, constructions_tiles::ConstructionTile, tiles::TileSet}};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use crate::{log_debug, log_error, log_info, log_warning, utils::{files::write_atomically, logger::Subsystem}, constants::{ACTIVE_REGION_MARGIN, OFF_SCREEN_UPDATE_INTERVAL, SPRITE_SHEET_BIOME_TILES, SPRITE_SHEET_CONSTRUCTION_TILES, WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::known_species::SPECIES_HERO, game_engine::{context::GameContext, entity::Entity, errors::{GameError, GameResult}, migrations::{from_reader, DataKind}, world::World}, maps::{biome_tiles::{Biome, BiomeTile}, constructions_tiles::ConstructionTile, tiles::TileSet}, worlds::{world_binary::{binary_world_path, is_binary_up_to_date, read_binary_world_data, write_binary_world}, world_overlay::WorldChanges}};
 
 impl World {
-    pub fn load(id: u32) -> Option<Self> {
-        let mut path = config().levels_path.clone();
-        path.push(format!("{}.json", id));
-
-        if let Ok(file) = File::open(path) {
-            let reader = BufReader::new(file);        
-            let result: Result<Self, Error> = serde_json::from_reader(reader);
+    /// Returns `None` only if the world was never saved, a file that can't be read or parsed is an error.
+    /// Changes made while playing the current save are applied on top of the level.
+    pub fn load(id: u32, context: Arc<GameContext>) -> GameResult<Option<Self>> {
+        if context.config.is_in_memory() {
+            return Ok(None)
+        }
+        let overlay = context.overlays.get(id)?;
 
-            if let Ok(world) = result {
-                println!("Game saved successfully!");
-                return Some(world)
-            } else {
-                println!("Failed to parse game {}.json: {:#?}", id, result.err());
-            } 
-        } else {
-            println!("Failed to load game file at {}.json", id);
+        if let Some(data) = context.worlds.get(id) {
+            log_debug!(context.logger, Subsystem::World, "Loaded world {} from cache", id);
+            let mut world = Self::from_data((*data).clone(), context);
+            world.apply_overlay(overlay);
+            return Ok(Some(world))
+        }
+        match read_world_data(&context.config.world_path(id)) {
+            Ok(Some(data)) => {
+                log_debug!(context.logger, Subsystem::World, "Loaded world {}", id);
+                let data = context.worlds.insert(id, data);
+                let mut world = Self::from_data((*data).clone(), context);
+                world.apply_overlay(overlay);
+                Ok(Some(world))
+            }
+            Ok(None) => {
+                log_warning!(context.logger, Subsystem::World, "No game file at {}.json", id);
+                Ok(None)
+            }
+            Err(error) => {
+                log_error!(context.logger, Subsystem::World, "{}", error);
+                Err(error)
+            }
         }
-        None
     }
 
-    pub fn load_or_create(id: u32) -> Self {
-        Self::load(id).unwrap_or_else(|| {
-            let new = Self::new_with_default_biomes(id);
-            new.save();
-            new
-        })
+    pub fn load_or_create(id: u32, context: Arc<GameContext>) -> GameResult<Self> {
+        if let Some(world) = Self::load(id, context.clone())? {
+            return Ok(world)
+        }
+        let new = Self::new_with_default_biomes(id, context);
+        new.save();
+        Ok(new)
     }
 
     pub fn save(&self) {
-        let mut path = config().levels_path.clone();
-        path.push(format!("{}.json", self.id));
+        if self.context.config.is_in_memory() {
+            return
+        }
+        let path = self.context.config.world_path(self.id);
+        self.context.worlds.invalidate(self.id);
 
         if let Ok(serialized_world) = serde_json::to_string_pretty(self) {
-            if let Ok(mut file) = File::create(path.clone()) {
-                if let Err(e) = file.write_all(serialized_world.as_bytes()) {
-                    eprintln!("Failed to write save file: {}", e);
-                } else {
-                    println!("Game saved successfully to {}.json", self.id);
-                }
+            if let Err(e) = write_atomically(&path, serialized_world.as_bytes()) {
+                log_error!(self.context.logger, Subsystem::World, "Failed to write save file: {}", e);
             } else {
-                eprintln!("Failed to create save file");
+                log_info!(self.context.logger, Subsystem::World, "Game saved successfully to {}.json", self.id);
+
+                let binary_path = binary_world_path(&path);
+                if binary_path.exists() {
+                    if let Err(e) = write_binary_world(&binary_path, self, &path) {
+                        log_error!(self.context.logger, Subsystem::World, "Failed to write binary save file: {}", e);
+                    }
+                }
             }
         } else {
-            eprintln!("Failed to serialize game world");
+            log_error!(self.context.logger, Subsystem::World, "Failed to serialize game world");
         }
     }
 
-    fn new_with_default_biomes(id: u32) -> Self {
-        let mut world = World::new(id);
+    fn new_with_default_biomes(id: u32, context: Arc<GameContext>) -> Self {
+        let mut world = World::new(id, context);
 
         let biome_tile_set = TileSet::<BiomeTile>::with_tiles(
             SPRITE_SHEET_BIOME_TILES, 
@@ -83,8 +102,28 @@ impl World {
     }    
 }
 
-#[derive(Serialize, Deserialize)]
-struct WorldData {
+/// Reads a world file, or its binary version when that is up to date, `None` if neither exists.
+pub fn read_world_data(path: &Path) -> GameResult<Option<WorldData>> {
+    if is_binary_up_to_date(path) {
+        return read_binary_world_data(&binary_world_path(path)).map(Some)
+    }
+    read_json_world_data(path)
+}
+
+pub fn read_json_world_data(path: &Path) -> GameResult<Option<WorldData>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(GameError::io(path, e))
+    };
+    from_reader(DataKind::World, path, BufReader::new(file)).map(Some)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WorldData {
+    #[serde(default)]
+    version: u32,
+
     id: u32,
 
     #[serde(default)]
@@ -119,6 +158,20 @@ struct WorldData {
 
     #[serde(default)]
     pressure_plate_down_yellow: bool,
+
+    #[serde(default = "default_active_region_margin")]
+    active_region_margin: i32,
+
+    #[serde(default = "default_off_screen_update_interval")]
+    off_screen_update_interval: f32,
+}
+
+fn default_active_region_margin() -> i32 {
+    ACTIVE_REGION_MARGIN
+}
+
+fn default_off_screen_update_interval() -> f32 {
+    OFF_SCREEN_UPDATE_INTERVAL
 }
 
 impl Serialize for World {
@@ -129,6 +182,7 @@ impl Serialize for World {
             .collect();
 
         let mut state = serializer.serialize_struct("World", 4)?;
+        state.serialize_field("version", &DataKind::World.latest_version())?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("biome_tiles", &self.biome_tiles)?;
         state.serialize_field("constructions_tiles", &self.constructions_tiles)?;
@@ -141,15 +195,15 @@ impl Serialize for World {
         state.serialize_field("pressure_plate_down_blue", &self.pressure_plate_down_blue)?;
         state.serialize_field("pressure_plate_down_silver", &self.pressure_plate_down_silver)?;
         state.serialize_field("pressure_plate_down_yellow", &self.pressure_plate_down_yellow)?;
+        state.serialize_field("active_region_margin", &self.active_region_margin)?;
+        state.serialize_field("off_screen_update_interval", &self.off_screen_update_interval)?;
         state.end()
     }
 }
 
-impl<'de> Deserialize<'de> for World {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
-        let data = WorldData::deserialize(deserializer)?;
-
-        let mut world = World::new(data.id);        
+impl World {
+    fn from_data(data: WorldData, context: Arc<GameContext>) -> Self {
+        let mut world = World::new(data.id, context);        
         world.default_biome = data.default_biome;
         world.creep_spawn_enabled = data.creep_spawn_enabled;
         world.creep_spawn_interval = data.creep_spawn_interval;
@@ -158,9 +212,12 @@ impl<'de> Deserialize<'de> for World {
         world.pressure_plate_down_blue = data.pressure_plate_down_blue;
         world.pressure_plate_down_silver = data.pressure_plate_down_silver;
         world.pressure_plate_down_yellow = data.pressure_plate_down_yellow;
+        world.active_region_margin = data.active_region_margin;
+        world.off_screen_update_interval = data.off_screen_update_interval;
+        world.changes = WorldChanges::tracking(&data.entities);
         data.entities.into_iter().for_each(|e| _ = world.add_entity(e));        
         world.load_biome_tiles(data.biome_tiles);
         world.load_construction_tiles(data.constructions_tiles);
-        Ok(world)
+        world
     }
 }
\ No newline at end of file
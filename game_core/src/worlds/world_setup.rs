@@ -1,13 +1,57 @@
-use crate::{constants::{WORLD_ID_DEMO, WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::{known_species::SPECIES_HERO, species::make_entity_by_species}, game_engine::{storage::save_pressure_plate_states, world::World}, utils::directions::Direction};
+use crate::{constants::{WORLD_ID_DEMO, WORLD_SIZE_COLUMNS, WORLD_SIZE_ROWS}, entities::known_species::SPECIES_HERO, game_engine::{storage::{save_pressure_plate_states, StorageKey}, world::World}, utils::directions::Direction};
 
 impl World {
     pub fn setup(&mut self, source: u32, hero_direction: &Direction, original_x: i32, original_y: i32) {
+        self.prepare_for_hero();
+        let entrance = self.add_hero(source, hero_direction, original_x, original_y);
+        self.save_entrance(entrance);
+    }
+
+    /// Places the hero exactly at a saved position, or like a teleport without coordinates if that is blocked now.
+    pub fn setup_restoring(&mut self, source: u32, hero_direction: &Direction, x: i32, y: i32) {
+        self.prepare_for_hero();
+        self.load_entrance();
+
+        if self.is_free_for_hero(x, y) {
+            self.add_hero(source, hero_direction, x, y);
+        } else {
+            self.add_hero(source, hero_direction, 0, 0);
+        }
+    }
+
+    pub fn is_free_for_hero(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 {
+            return false
+        }
+        self.hitmap.get(y as usize + 1).and_then(|row| row.get(x as usize)).is_some_and(|is_blocked| !is_blocked)
+    }
+
+    /// Entrances are kept in storage, so that resumed games still know where the hero came in from.
+    pub fn load_entrance(&mut self) {
+        let value = |key| self.context.storage.get(&StorageKey::in_world(self.id, key)).and_then(|value| value.as_int());
+
+        if let (Some(x), Some(y), Some(direction)) = (value("entrance.x"), value("entrance.y"), value("entrance.direction")) {
+            self.entrance = (x as i32, y as i32, Direction::from_int(direction));
+        }
+    }
+
+    fn save_entrance(&mut self, entrance: (i32, i32, Direction)) {
+        let storage = &self.context.storage;
+        storage.set(&StorageKey::in_world(self.id, "entrance.x"), entrance.0 as i64);
+        storage.set(&StorageKey::in_world(self.id, "entrance.y"), entrance.1 as i64);
+        storage.set(&StorageKey::in_world(self.id, "entrance.direction"), entrance.2 as i64);
+        self.entrance = entrance;
+    }
+
+    fn prepare_for_hero(&mut self) {
         self.update_tiles_hitmap();
         self.update_hitmaps();
         save_pressure_plate_states(self);
+    }
 
+    fn add_hero(&mut self, source: u32, hero_direction: &Direction, original_x: i32, original_y: i32) -> (i32, i32, Direction) {
         let (x, y) = self.destination_x_y(source, original_x, original_y);        
-        let mut entity = make_entity_by_species(SPECIES_HERO);
+        let mut entity = self.context.make_entity(SPECIES_HERO);
 
         if y > 0 && !self.hitmap[(y + 1) as usize][x as usize] {
             entity.frame.x = x;
@@ -28,12 +72,15 @@ impl World {
         }
         
         entity.immobilize_for_seconds(0.2);        
+        let position = (entity.frame.x, entity.frame.y, entity.direction);
         self.add_entity(entity);
+        position
     }    
 
     pub fn set_creative_mode(&mut self, enabled: bool) {
         self.creative_mode = enabled;
-        self.entities.borrow_mut().iter_mut().for_each(|e| e.setup(enabled));
+        self.entities.borrow_mut().iter_mut().for_each(|e| e.setup(&self.context, enabled));
+        self.reindex_entities();
     }
 
     fn destination_x_y(&self, source: u32, original_x: i32, original_y: i32) -> (i32, i32) {
@@ -51,3 +98,21 @@ impl World {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::fixtures::WorldFixture;
+
+    #[test]
+    fn the_hero_fits_in_the_first_row() {
+        let world = WorldFixture::new(900_600)
+            .biomes(&["1111", "1111", "1111"])
+            .constructions(&["", ".1"])
+            .build();
+
+        assert!(world.is_free_for_hero(0, 0));
+        assert!(!world.is_free_for_hero(1, 0));
+        assert!(!world.is_free_for_hero(-1, 0));
+        assert!(!world.is_free_for_hero(0, 2));
+    }
+}
@@ -7,6 +7,8 @@
 
 import SwiftUI
 
+var engine: OpaquePointer?
+
 struct ContentView: View {
     var body: some View {
         VStack {
@@ -20,18 +22,36 @@ struct ContentView: View {
             
             test_integration()
             
-            initialize_config(
-                "en",
-                folderContaining(name: "1001", extension: "json", folder: "data"),
-                filePath(name: "species", extension: "json", folder: "data"),
-                filePath(name: "inventory", extension: "json", folder: "data"),
-                saveFilePath(),
-                folderContaining(name: "en", extension: "stringx", folder: "lang")
+            engine = nokemon_engine_new(
+                EngineConfig(
+                    current_lang: strdup("en"),
+                    levels_path: strdup(folderContaining(name: "1001", extension: "json", folder: "data")),
+                    species_path: strdup(filePath(name: "species", extension: "json", folder: "data")),
+                    inventory_path: strdup(filePath(name: "inventory", extension: "json", folder: "data")),
+                    key_value_storage_path: strdup(saveFilePath()),
+                    localized_strings_path: strdup(folderContaining(name: "en", extension: "stringx", folder: "lang")),
+                    saves_path: strdup(savesFolderPath()),
+                    fixed_time_step: 0,
+                    seed: 0,
+                    respawn_hp: 1,
+                    loses_consumables: false,
+                    log_level: LogLevel_Warning,
+                    prints_log_to_console: true
+                )
             )
             
-            initialize_game(false)
-            window_size_changed(400, 400, 1, 1, 1)
-            update_game(0.1)
+            guard engine != nil else {
+                print("Failed to create engine: \(lastError(nil) ?? "unknown error")")
+                return
+            }
+            
+            initialize_game(engine, false)
+            window_size_changed(engine, 400, 400, 1, 1, 1)
+            update_game(engine, 0.1)
+            
+            if let error = lastError(engine) {
+                print("Loading error: \(error)")
+            }
             
             fetchRenderableItems { renderableItems in
                 for item in renderableItems {
@@ -76,7 +96,7 @@ public struct RenderableItem {
 }
 
 @_silgen_name("renderables")
-func renderables(_ length: UnsafeMutablePointer<size_t>?) -> UnsafeMutablePointer<RenderableItem>?
+func renderables(_ engine: OpaquePointer?, _ length: UnsafeMutablePointer<size_t>?) -> UnsafeMutablePointer<RenderableItem>?
 
 @_silgen_name("free_renderables")
 func free_renderables(_ ptr: UnsafeMutablePointer<RenderableItem>?, _ length: size_t)
@@ -84,7 +104,7 @@ func free_renderables(_ ptr: UnsafeMutablePointer<RenderableItem>?, _ length: si
 func fetchRenderableItems(_ callback: @escaping ([RenderableItem]) -> Void) {
     var length: size_t = 0
 
-    guard let ptr = renderables(&length) else {
+    guard let ptr = renderables(engine, &length) else {
         print("Failed to fetch renderables")
         return
     }
@@ -96,6 +116,11 @@ func fetchRenderableItems(_ callback: @escaping ([RenderableItem]) -> Void) {
     free_renderables(ptr, length)
 }
 
+func lastError(_ engine: OpaquePointer?) -> String? {
+    guard let message = nokemon_last_error(engine) else { return nil }
+    return String(cString: message)
+}
+
 func filePath(name: String, extension ext: String, folder: String) -> String {
     Bundle.main.url(forResource: name, withExtension: ext, subdirectory: folder)?
         .absoluteString
@@ -107,17 +132,15 @@ func folderContaining(name: String, extension ext: String, folder: String) -> St
         .replacingOccurrences(of: "/\(name).\(ext)", with: "")
 }
 
+func savesFolderPath() -> String {
+    let documentsDirectory = FileManager.default.urls(for: .documentDirectory, in: .userDomainMask).first!
+    return documentsDirectory.appendingPathComponent("saves").path
+}
+
+/// Save of versions without slots, the engine copies it into slot 1 the first time it runs
 func saveFilePath() -> String {
-    let fileManager = FileManager.default
-    let documentsDirectory = fileManager.urls(for: .documentDirectory, in: .userDomainMask).first!
-    let saveFileURL = documentsDirectory.appendingPathComponent("save.json")
-    
-    if !fileManager.fileExists(atPath: saveFileURL.path) {
-        let defaultContents = "{\"always\": 1}".data(using: .utf8)
-        fileManager.createFile(atPath: saveFileURL.path, contents: defaultContents, attributes: nil)
-    }
-    
-    return saveFileURL.path
+    let documentsDirectory = FileManager.default.urls(for: .documentDirectory, in: .userDomainMask).first!
+    return documentsDirectory.appendingPathComponent("save.json").path
 }
 
 public typealias Biome = UInt32
@@ -134,7 +157,8 @@ public struct BiomeTile {
 }
 
 @_silgen_name("get_biome_tiles")
-func get_biome_tiles(_ out_tiles: UnsafeMutablePointer<UnsafePointer<BiomeTile>?>?,
+func get_biome_tiles(_ engine: OpaquePointer?,
+                     _ out_tiles: UnsafeMutablePointer<UnsafePointer<BiomeTile>?>?,
                      _ out_len_x: UnsafeMutablePointer<size_t>?,
                      _ out_len_y: UnsafeMutablePointer<size_t>?)
 
@@ -148,7 +172,7 @@ func fetchBiomeTiles(_ callback: @escaping ([[BiomeTile]]) -> Void) {
     var lenX: size_t = 0
     var lenY: size_t = 0
 
-    get_biome_tiles(&tilesPtr, &lenX, &lenY)
+    get_biome_tiles(engine, &tilesPtr, &lenX, &lenY)
 
     guard let tilesPtr = tilesPtr else {
         print("Failed to fetch biome tiles")